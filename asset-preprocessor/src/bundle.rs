@@ -0,0 +1,263 @@
+#![allow(clippy::type_complexity)]
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use ordered_float::NotNan;
+
+use renderer_types::bundle::AssetBundle;
+use renderer_types::{lod, BoundingBox, GeoDegree, GridCell, Vertex};
+
+use crate::validate::{self, BuildOptions, Issue, MissingStationFallback, ValidationReport};
+use crate::{
+    parse_shapefile, parse_tsunami_shapefile, parse_world_shapefile, prefecture_names,
+    romanized_names_parser, station_codes_parser,
+};
+
+/// assets_dir以下のshapefileと観測点一覧からバンドルを組み立てる。
+/// 組み立てる前に整合性を検査し、エラーがあればその検査結果を返す。
+pub fn build(
+    assets_dir: &Path,
+    version: String,
+    options: &BuildOptions,
+) -> Result<(AssetBundle, ValidationReport), ValidationReport> {
+    let mut report = validate::validate(assets_dir, options);
+    if report.has_errors() {
+        return Err(report);
+    }
+
+    let lod_levels = lod::levels(lod::SCREEN_SPACE_ERROR);
+
+    let (
+        tsunami_vertices,
+        tsunami_indices,
+        tsunami_area_code_to_internal_code,
+        tsunami_area_names,
+        tsunami_area_bounding_boxes,
+        tsunami_area_markers,
+    ) = parse_tsunami_shapefile::read(assets_dir);
+
+    let (world_vertices, world_triangles, world_dropped_rings) =
+        parse_world_shapefile::read(assets_dir, &lod_levels);
+
+    let s = std::fs::read_to_string(assets_dir.join("intensity_stations.json")).unwrap();
+
+    #[allow(non_snake_case)]
+    let (
+        mut intensity_station_minimized,
+        area_code__intensity_station_range,
+        station_code__index,
+        area_code__pref_code,
+    ) = station_codes_parser::read(&s);
+
+    #[allow(non_snake_case)]
+    let pref_code__name = prefecture_names::read(assets_dir);
+    let romanized_names = romanized_names_parser::read(assets_dir);
+
+    #[allow(non_snake_case)]
+    let (
+        area_code__bbox,
+        area_code__centers,
+        vertices,
+        indices,
+        area_lines,
+        pref_lines,
+        scale_level_map,
+        area_labels,
+        pref_labels,
+        area_code__name,
+        area_rings,
+        dropped_rings,
+        map_extent,
+    ) = parse_shapefile::read(assets_dir, &lod_levels, &area_code__pref_code);
+
+    // 面にならず描けないリングは、検査結果に警告として残す
+    let dropped_rings = [
+        ("earthquake_detailed", dropped_rings),
+        ("world_land", world_dropped_rings),
+    ];
+    for (file, rings) in dropped_rings {
+        for (points, location) in rings {
+            report.push(Issue::DroppedRing {
+                file,
+                points,
+                longitude: location.x(),
+                latitude: location.y(),
+            });
+        }
+    }
+
+    let (area_ring_vertices, area_ring_table, area_grid) = build_area_index(&area_rings);
+
+    let area_prefectures: HashMap<u32, u32> = area_code__pref_code
+        .iter()
+        .map(|(area, pref)| (area.0, pref.0))
+        .collect();
+
+    let area_names: HashMap<u32, String> = area_code__name
+        .into_iter()
+        .map(|(code, name)| (code.0, name))
+        .collect();
+
+    // (Name, Lon, Lat, Weight)
+    // 名前の分からない都道府県は検査で警告済みで、ラベルを出さない
+    let pref_labels: Vec<(String, f32, f32, f32)> = pref_labels
+        .iter()
+        .filter_map(|label| {
+            let name = pref_code__name.get(&label.pref_code)?;
+
+            Some((
+                name.clone(),
+                label.anchor.x(),
+                label.anchor.y(),
+                label.weight,
+            ))
+        })
+        .collect();
+
+    // (AreaCode, Lon, Lat, Weight)
+    let area_labels: Vec<(u32, f32, f32, f32)> = area_labels
+        .into_iter()
+        .map(|label| {
+            (
+                label.area_code.0,
+                label.anchor.x(),
+                label.anchor.y(),
+                label.weight,
+            )
+        })
+        .collect();
+
+    // <AreaCode, (StationIndex, (BBox))>
+    let mut areas: HashMap<u32, (usize, (f32, f32, f32, f32))> = HashMap::new();
+
+    for (code, bbox) in &area_code__bbox {
+        let area_center = area_code__centers.get(code).unwrap();
+
+        let Some(area) = area_code__intensity_station_range.get(code) else {
+            // 観測点の無い区域は検査済みで、optionsに従って扱う
+            match options.missing_station {
+                MissingStationFallback::Centroid => {
+                    intensity_station_minimized.push((area_center.x(), area_center.y()));
+                    areas.insert(
+                        code.0,
+                        (intensity_station_minimized.len() - 1, bbox_to_tuple(bbox)),
+                    );
+                }
+                MissingStationFallback::Skip => (),
+                // 検査を通っていればここには来ないが、来たらバンドルを作らずに報告する
+                MissingStationFallback::Abort => {
+                    report.push(Issue::AreaWithoutStation {
+                        area_code: code.0,
+                        fallback: MissingStationFallback::Abort,
+                    });
+                    return Err(report);
+                }
+            }
+            continue;
+        };
+
+        let stations = &intensity_station_minimized[area.start_i..area.start_i + area.n];
+
+        let nearest_intensity_station_index = stations
+            .iter()
+            .enumerate()
+            .min_by_key(|(_i, &station)| {
+                let station = Vertex::<GeoDegree>::new(station.0, station.1);
+                NotNan::new(area_center.euclidean_distance(&station))
+                    .expect("なぁん…観測点距離が何故かNaN")
+            })
+            .map(|(offset, _station)| area.start_i + offset)
+            .expect("エリア内に一つも観測点がない");
+
+        areas.insert(
+            code.0,
+            (nearest_intensity_station_index, bbox_to_tuple(bbox)),
+        );
+    }
+
+    let bundle = AssetBundle {
+        version,
+        intensity_station_positions: intensity_station_minimized,
+        areas,
+        station_codes: station_code__index,
+        vertices,
+        map_triangles: indices,
+        area_lines,
+        pref_lines,
+        scale_level_map,
+        map_extent: bbox_to_tuple(&map_extent),
+        pref_labels,
+        area_labels,
+        area_names,
+        tsunami_area_names,
+        prefecture_names: pref_code__name
+            .into_iter()
+            .map(|(code, name)| (code.0, name))
+            .collect(),
+        romanized_area_names: romanized_names.areas,
+        romanized_tsunami_area_names: romanized_names.tsunami_areas,
+        romanized_prefecture_names: romanized_names.prefectures,
+        area_prefectures,
+        area_ring_vertices,
+        area_rings: area_ring_table,
+        area_grid,
+        tsunami_vertices,
+        tsunami_indices,
+        tsunami_area_code_to_internal_code,
+        tsunami_area_bounding_boxes: tsunami_area_bounding_boxes
+            .iter()
+            .map(|(code, bbox)| (*code, bbox_to_tuple(bbox)))
+            .collect(),
+        tsunami_area_markers: tsunami_area_markers
+            .into_iter()
+            .map(|(code, position)| (code, position.into()))
+            .collect(),
+        world_vertices,
+        world_triangles,
+    };
+
+    Ok((bundle, report))
+}
+
+/// 点から細分区域を引くための索引を作る。
+/// 各リングの頂点をまとめた配列と、(AreaCode, Start, Len, (BBox))のリング表、
+/// GridCellごとにそのセルと外接矩形が重なるリングの番号を並べた表を返す。
+fn build_area_index(
+    area_rings: &[(renderer_types::codes::地震情報細分区域, Vec<(f32, f32)>)],
+) -> (
+    Vec<(f32, f32)>,
+    Vec<(u32, u32, u32, (f32, f32, f32, f32))>,
+    HashMap<u64, Vec<u32>>,
+) {
+    let mut vertices = Vec::new();
+    let mut rings = Vec::new();
+    let mut grid: HashMap<u64, Vec<u32>> = HashMap::new();
+
+    for (i, (area_code, points)) in area_rings.iter().enumerate() {
+        let bbox = BoundingBox::from_vertices_float(
+            &points
+                .iter()
+                .map(|(x, y)| Vertex::<GeoDegree>::new(*x, *y))
+                .collect::<Vec<_>>(),
+        );
+
+        rings.push((
+            area_code.0,
+            vertices.len() as u32,
+            points.len() as u32,
+            bbox_to_tuple(&bbox),
+        ));
+        vertices.extend_from_slice(points);
+
+        for cell in GridCell::covering(&bbox) {
+            grid.entry(cell.key()).or_default().push(i as u32);
+        }
+    }
+
+    (vertices, rings, grid)
+}
+
+fn bbox_to_tuple(bb: &BoundingBox<GeoDegree>) -> (f32, f32, f32, f32) {
+    (bb.min.x(), bb.min.y(), bb.max.x(), bb.max.y())
+}
@@ -0,0 +1,341 @@
+//! 本物のshapefileが手元に無くてもビルド・テストできるようにするための合成データ。
+//! 実在しない3つの細分区域と、それに沿った津波予報区を日本海上に置く。
+//! 100には湖の穴が開いている。
+//!
+//! ```text
+//!  39.5 +---------+---------+
+//!       | 100 []  |         |
+//!  38.5 +---------+   200   |
+//!       |   101   |         |
+//!  37.5 +---------+---------+
+//!     140.0     141.0     142.0
+//! ```
+//!
+//! 100と101は都道府県1、200は都道府県2に属する。都道府県の名前はdbfだけで与える。
+//! 世界の陸地として、日本の範囲の外と内に1つずつ陸地を置く。内側のものはバンドルに入らない。
+//! 津波予報区300は、線として見えないほど短い小島の海岸線として南の沖に置く。
+//! ローマ字の名前は、津波予報区300のものだけを用意しない。
+
+use std::path::Path;
+
+use shapefile::dbase::{FieldName, FieldValue, Record, TableWriterBuilder};
+use shapefile::{Point, Polygon, PolygonRing, Polyline};
+
+use crate::prefecture_names;
+
+/// (AreaCode, PrefCode, Name, 時計回りの外周)
+const AREAS: [(u32, u32, &str, &[(f64, f64)]); 3] = [
+    (
+        100,
+        1,
+        "試験区域北",
+        &[(140.0, 38.5), (140.0, 39.5), (141.0, 39.5), (141.0, 38.5)],
+    ),
+    (
+        101,
+        1,
+        "試験区域南",
+        &[(140.0, 37.5), (140.0, 38.5), (141.0, 38.5), (141.0, 37.5)],
+    ),
+    (
+        200,
+        2,
+        "試験区域東",
+        &[
+            (141.0, 37.5),
+            (141.0, 38.5),
+            (141.0, 39.5),
+            (142.0, 39.5),
+            (142.0, 37.5),
+        ],
+    ),
+];
+
+/// (PrefCode, Name)
+const PREFECTURES: [(u32, &str); 2] = [(1, "試験県"), (2, "試験府")];
+
+/// (PrefCode, ローマ字の名前)
+const ROMANIZED_PREFECTURES: [(u32, &str); 2] = [(1, "Shiken-ken"), (2, "Shiken-fu")];
+
+/// (AreaCode, ローマ字の名前)
+const ROMANIZED_AREAS: [(u32, &str); 3] = [
+    (100, "Shiken Kuiki Kita"),
+    (101, "Shiken Kuiki Minami"),
+    (200, "Shiken Kuiki Higashi"),
+];
+
+/// (TsunamiAreaCode, ローマ字の名前)
+const ROMANIZED_TSUNAMI_AREAS: [(u32, &str); 3] = [
+    (100, "Shiken Engan Kita"),
+    (101, "Shiken Engan Minami"),
+    (200, "Shiken Engan Higashi"),
+];
+
+/// (TsunamiAreaCode, Name, 海岸線)
+const TSUNAMI_AREAS: [(u32, &str, &[(f64, f64)]); 4] = [
+    (100, "試験沿岸北", &[(140.0, 39.5), (140.0, 38.5)]),
+    (101, "試験沿岸南", &[(140.0, 38.5), (140.0, 37.5)]),
+    (200, "試験沿岸東", &[(142.0, 37.5), (142.0, 39.5)]),
+    (300, "試験小島", &[(141.5, 37.0), (141.55, 37.0)]),
+];
+
+/// (AreaCode, 湖の穴)
+const LAKE: (u32, [(f64, f64); 4]) = (
+    100,
+    [(140.4, 38.9), (140.6, 38.9), (140.6, 39.1), (140.4, 39.1)],
+);
+
+/// (Name, 時計回りの外周)
+const WORLD_LAND: [(&str, &[(f64, f64)]); 2] = [
+    (
+        "試験大陸",
+        &[
+            (-75.0, -45.0),
+            (-75.0, -20.0),
+            (-65.0, -20.0),
+            (-65.0, -45.0),
+        ],
+    ),
+    (
+        "試験島",
+        &[(130.0, 33.0), (130.0, 34.0), (131.0, 34.0), (131.0, 33.0)],
+    ),
+];
+
+/// (StationCode, AreaCode, PrefCode, Name, Lon, Lat)
+const STATIONS: [(u32, u32, u32, &str, f32, f32); 4] = [
+    (1000001, 100, 1, "試験観測点北", 140.5, 38.7),
+    (1000002, 100, 1, "試験観測点北西", 140.2, 39.3),
+    (1000101, 101, 1, "試験観測点南", 140.5, 38.0),
+    (2000001, 200, 2, "試験観測点東", 141.5, 38.5),
+];
+
+/// 合成データをassets_dirと同じ配置でdirに書き出す。
+pub fn write(dir: &Path) {
+    write_areas(&dir.join("shapefile/earthquake_detailed"));
+    write_prefectures(&dir.join(prefecture_names::DBF_PATH));
+    write_tsunami_areas(&dir.join("shapefile/tsunami_forecast"));
+    write_world_land(&dir.join("shapefile/world_land"));
+    write_stations(&dir.join("intensity_stations.json"));
+    write_romanized_names(&dir.join("romanized_names.json"));
+}
+
+fn table_builder() -> TableWriterBuilder {
+    TableWriterBuilder::new()
+        .add_character_field(FieldName::try_from("code").unwrap(), 16)
+        .add_character_field(FieldName::try_from("name").unwrap(), 64)
+}
+
+fn record(code: u32, name: &str) -> Record {
+    let mut record = Record::default();
+    record.insert(
+        "code".to_string(),
+        FieldValue::Character(Some(code.to_string())),
+    );
+    record.insert(
+        "name".to_string(),
+        FieldValue::Character(Some(name.to_string())),
+    );
+    record
+}
+
+fn points(coordinates: &[(f64, f64)]) -> Vec<Point> {
+    coordinates
+        .iter()
+        .map(|(x, y)| Point::new(*x, *y))
+        .collect()
+}
+
+fn write_areas(dir: &Path) {
+    std::fs::create_dir_all(dir).unwrap();
+
+    let mut writer = shapefile::Writer::from_path(
+        dir.join("earthquake_detailed_simplified.shp"),
+        table_builder(),
+    )
+    .unwrap();
+
+    for (code, _, name, ring) in AREAS {
+        let mut rings = vec![PolygonRing::Outer(points(ring))];
+        if code == LAKE.0 {
+            rings.push(PolygonRing::Inner(points(&LAKE.1)));
+        }
+
+        let polygon = Polygon::with_rings(rings);
+        writer
+            .write_shape_and_record(&polygon, &record(code, name))
+            .unwrap();
+    }
+}
+
+fn write_prefectures(path: &Path) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+
+    let records: Vec<_> = PREFECTURES
+        .iter()
+        .map(|(code, name)| record(*code, name))
+        .collect();
+
+    table_builder()
+        .build_with_file_dest(path)
+        .unwrap()
+        .write_records(&records)
+        .unwrap();
+}
+
+fn write_tsunami_areas(dir: &Path) {
+    std::fs::create_dir_all(dir).unwrap();
+
+    let mut writer =
+        shapefile::Writer::from_path(dir.join("tsunami_forecast_simplified.shp"), table_builder())
+            .unwrap();
+
+    for (code, name, line) in TSUNAMI_AREAS {
+        let polyline = Polyline::new(points(line));
+        writer
+            .write_shape_and_record(&polyline, &record(code, name))
+            .unwrap();
+    }
+}
+
+fn write_world_land(dir: &Path) {
+    std::fs::create_dir_all(dir).unwrap();
+
+    let mut writer =
+        shapefile::Writer::from_path(dir.join("world_land_simplified.shp"), table_builder())
+            .unwrap();
+
+    for (i, (name, ring)) in WORLD_LAND.iter().enumerate() {
+        let polygon = Polygon::new(PolygonRing::Outer(points(ring)));
+        writer
+            .write_shape_and_record(&polygon, &record(i as u32, name))
+            .unwrap();
+    }
+}
+
+fn write_stations(path: &Path) {
+    let stations: Vec<_> = STATIONS
+        .iter()
+        .map(|(station_code, area_code, pref_code, name, lon, lat)| {
+            serde_json::json!({
+                "lat": lat.to_string(),
+                "lon": lon,
+                "name": name,
+                "pref": pref_code.to_string(),
+                "affi": "0",
+                "areaCode": area_code.to_string(),
+                "cityCode": format!("{station_code:07}"),
+                "stationCode": format!("{station_code:07}"),
+            })
+        })
+        .collect();
+
+    std::fs::write(path, serde_json::to_string(&stations).unwrap()).unwrap();
+}
+
+fn write_romanized_names(path: &Path) {
+    let table = |names: &[(u32, &str)]| -> serde_json::Map<String, serde_json::Value> {
+        names
+            .iter()
+            .map(|(code, name)| (code.to_string(), (*name).into()))
+            .collect()
+    };
+
+    let names = serde_json::json!({
+        "areas": table(&ROMANIZED_AREAS),
+        "tsunami_areas": table(&ROMANIZED_TSUNAMI_AREAS),
+        "prefectures": table(&ROMANIZED_PREFECTURES),
+    });
+
+    std::fs::write(path, names.to_string()).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use renderer_types::codes;
+
+    use crate::bundle;
+    use crate::fixture::*;
+    use crate::validate::BuildOptions;
+
+    #[test]
+    fn test_build_bundle_from_fixture() {
+        let dir = std::env::temp_dir().join(format!("eew-renderer-fixture-{}", std::process::id()));
+        write(&dir);
+
+        let (bundle, report) =
+            bundle::build(&dir, "fixture".to_string(), &BuildOptions::default()).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(report.diagnostics.is_empty(), "{report}");
+        assert_eq!(bundle.areas.len(), AREAS.len());
+        assert_eq!(bundle.station_codes.len(), STATIONS.len());
+        assert_eq!(
+            bundle.tsunami_area_code_to_internal_code.len(),
+            TSUNAMI_AREAS.len()
+        );
+        assert_eq!(bundle.area_names[&100], "試験区域北");
+        assert_eq!(bundle.tsunami_area_names[&200], "試験沿岸東");
+        assert_eq!(
+            bundle.tsunami_area_bounding_boxes[&100],
+            (140.0, 38.5, 140.0, 39.5)
+        );
+        assert_eq!(bundle.area_prefectures[&101], 1);
+
+        // 都道府県のラベルはdbfの名前で付く
+        let mut pref_names: Vec<_> = bundle
+            .pref_labels
+            .iter()
+            .map(|(name, ..)| name.as_str())
+            .collect();
+        pref_names.sort();
+        assert_eq!(pref_names, ["試験府", "試験県"]);
+        assert_eq!(bundle.prefecture_names[&2], "試験府");
+
+        // ローマ字の名前は用意されたものだけが入る
+        assert_eq!(bundle.romanized_area_names[&101], "Shiken Kuiki Minami");
+        assert_eq!(bundle.romanized_prefecture_names[&1], "Shiken-ken");
+        assert!(!bundle.romanized_tsunami_area_names.contains_key(&300));
+
+        // 短い海岸線の予報区だけが、その重心に印を描く対象になる
+        assert_eq!(bundle.tsunami_area_markers.len(), 1);
+        let (lon, lat) = bundle.tsunami_area_markers[&300];
+        assert!((lon - 141.525).abs() < 1e-4 && (lat - 37.0).abs() < 1e-4);
+
+        // 小さな区域も、最も粗いLODまで潰れずに残る
+        assert_eq!(bundle.map_triangles.len(), bundle.scale_level_map.len());
+        assert!(bundle.map_triangles.iter().all(|lod| !lod.is_empty()));
+
+        // 湖の部分は塗りつぶされない
+        let land_area: f32 = bundle.map_triangles[0]
+            .chunks(3)
+            .map(|t| {
+                let [a, b, c] = [0, 1, 2].map(|i| bundle.vertices[t[i] as usize]);
+                ((b.0 - a.0) * (c.1 - a.1) - (c.0 - a.0) * (b.1 - a.1)).abs() / 2.0
+            })
+            .sum();
+        assert!((land_area - (4.0 - 0.04)).abs() < 1e-3, "{land_area}");
+
+        // 世界の陸地は日本の範囲の外にあるものだけが入る
+        assert_eq!(bundle.world_triangles.len(), bundle.scale_level_map.len());
+        let world_area: f32 = bundle.world_triangles[0]
+            .chunks(3)
+            .map(|t| {
+                let [a, b, c] = [0, 1, 2].map(|i| bundle.world_vertices[t[i] as usize]);
+                ((b.0 - a.0) * (c.1 - a.1) - (c.0 - a.0) * (b.1 - a.1)).abs() / 2.0
+            })
+            .sum();
+        assert!((world_area - 250.0).abs() < 1e-3, "{world_area}");
+
+        // 100と101の境界は細分区域界、200との境界は都道府県界になる
+        assert!(bundle.area_lines.iter().all(|lod| !lod.is_empty()));
+        assert!(bundle.pref_lines.iter().all(|lod| !lod.is_empty()));
+
+        // 区域の代表点は区域内の観測点から選ばれる
+        let (station_index, _) = bundle.areas[&codes::地震情報細分区域(100).0];
+        assert_eq!(
+            bundle.intensity_station_positions[station_index],
+            (140.5, 38.7)
+        );
+    }
+}
@@ -1,4 +1,11 @@
+pub mod bundle;
+pub mod fixture;
 mod math;
-pub mod parse_lake_shapefile;
 pub mod parse_shapefile;
 pub mod parse_tsunami_shapefile;
+pub mod parse_world_shapefile;
+pub mod prefecture_names;
+mod romanized_names_parser;
+mod station_codes_parser;
+mod topology;
+pub mod validate;
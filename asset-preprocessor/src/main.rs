@@ -0,0 +1,114 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Args, Parser, Subcommand};
+
+use asset_preprocessor::validate::{BuildOptions, MissingStationFallback};
+
+#[derive(Parser)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// shapefileと観測点一覧からrenderer用のアセットバンドルを書き出す
+    Build {
+        #[command(flatten)]
+        source: Source,
+
+        #[clap(long, default_value = "assets.bin")]
+        output: PathBuf,
+
+        /// ログやトップページに表示されるバンドルのバージョン
+        #[clap(long)]
+        bundle_version: String,
+    },
+
+    /// アセットの不整合を全て洗い出し、結果をJSONで出力する
+    Validate {
+        #[command(flatten)]
+        source: Source,
+
+        /// 結果の書き出し先。指定しなければ標準出力に出す
+        #[clap(long)]
+        report: Option<PathBuf>,
+    },
+}
+
+#[derive(Args)]
+struct Source {
+    /// shapefile/とintensity_stations.jsonを含むディレクトリ
+    #[clap(long, default_value = "assets")]
+    assets_dir: PathBuf,
+
+    /// 観測点が一つも無い細分区域の扱い
+    #[clap(long, value_enum, default_value_t)]
+    missing_station: MissingStationFallback,
+}
+
+impl Source {
+    fn options(&self) -> BuildOptions {
+        BuildOptions {
+            missing_station: self.missing_station,
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Command::Build {
+            source,
+            output,
+            bundle_version,
+        } => {
+            let (bundle, report) = match asset_preprocessor::bundle::build(
+                &source.assets_dir,
+                bundle_version,
+                &source.options(),
+            ) {
+                Ok(built) => built,
+                Err(report) => {
+                    eprintln!("{report}");
+                    return ExitCode::FAILURE;
+                }
+            };
+
+            if !report.diagnostics.is_empty() {
+                eprintln!("{report}");
+            }
+
+            let bytes = bundle.encode().expect("アセットバンドルのエンコードに失敗");
+            std::fs::write(&output, &bytes).expect("アセットバンドルの書き込みに失敗");
+
+            println!(
+                "Wrote asset bundle {} to {} ({} bytes)",
+                bundle.version,
+                output.display(),
+                bytes.len()
+            );
+        }
+
+        Command::Validate { source, report } => {
+            let result =
+                asset_preprocessor::validate::validate(&source.assets_dir, &source.options());
+
+            match report {
+                Some(path) => {
+                    std::fs::write(&path, result.to_json()).expect("検査結果の書き込みに失敗");
+                    eprintln!("{result}");
+                }
+                None => println!("{}", result.to_json()),
+            }
+
+            if result.has_errors() {
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    ExitCode::SUCCESS
+}
@@ -91,23 +91,163 @@ impl Ring {
         AdjacentPointsIter::new(&self.points)
     }
 
+    /// 経緯度をそのまま平面座標とみなした符号付き面積。時計回りなら負になる。
+    pub(crate) fn signed_area(&self) -> f64 {
+        signed_area(&self.points)
+    }
+
+    /// shapefileでは外周を時計回り、穴を反時計回りに置く
+    pub(crate) fn is_exterior(&self) -> bool {
+        self.signed_area() <= 0.0
+    }
+
+    /// 点がリングの内側にあるか(境界上の点はどちらにもなり得る)
+    pub(crate) fn contains(&self, point: &Point) -> bool {
+        let (x, y) = (point.longitude.0, point.latitude.0);
+
+        self.points
+            .iter()
+            .tuple_windows()
+            .filter(|(a, b)| (a.latitude.0 > y) != (b.latitude.0 > y))
+            .filter(|(a, b)| {
+                let t = (y - a.latitude.0) / (b.latitude.0 - a.latitude.0);
+                x < a.longitude.0 + t * (b.longitude.0 - a.longitude.0)
+            })
+            .count()
+            % 2
+            == 1
+    }
+}
+
+/// 経緯度をそのまま平面座標とみなした、閉じた点列の符号付き面積
+pub(crate) fn signed_area(points: &[Point]) -> f64 {
+    points
+        .iter()
+        .tuple_windows()
+        .map(|(a, b)| {
+            a.longitude.0 as f64 * b.latitude.0 as f64 - b.longitude.0 as f64 * a.latitude.0 as f64
+        })
+        .sum::<f64>()
+        / 2.0
+}
+
+/// 外周と、その内側の穴
+pub(crate) struct Polygon {
+    pub(crate) exterior: Ring,
+    pub(crate) holes: Vec<Ring>,
+}
+
+impl Polygon {
+    pub(crate) fn new(exterior: Ring, holes: Vec<Ring>) -> Self {
+        Self { exterior, holes }
+    }
+
+    /// shapefileの1レコード分のリングを巻き方向で外周と穴に分け、
+    /// 穴はそれを含む最も小さな外周に割り当てる。
+    /// どの外周にも含まれない穴は、巻き方向の誤りとみなして外周として扱う。
+    pub(crate) fn assemble(rings: Vec<Ring>) -> Vec<Polygon> {
+        let enclosing = enclosing_exteriors(&rings);
+
+        let mut polygons: Vec<Option<Polygon>> = Vec::new();
+        let mut holes = Vec::new();
+        let mut polygon_of_ring = vec![0; rings.len()];
+
+        for (i, ring) in rings.into_iter().enumerate() {
+            match enclosing[i] {
+                Some(exterior) if exterior != i => holes.push((exterior, ring)),
+                _ => {
+                    polygon_of_ring[i] = polygons.len();
+                    polygons.push(Some(Polygon::new(ring, Vec::new())));
+                }
+            }
+        }
+
+        for (exterior, hole) in holes {
+            if let Some(polygon) = &mut polygons[polygon_of_ring[exterior]] {
+                polygon.holes.push(hole);
+            }
+        }
+
+        polygons.into_iter().flatten().collect()
+    }
+
+    /// 外周、穴の順に全てのリングを返す
+    pub(crate) fn rings(&self) -> impl Iterator<Item = &Ring> {
+        std::iter::once(&self.exterior).chain(&self.holes)
+    }
+
     pub(crate) fn triangulate(&self) -> Vec<Point> {
+        let points = self.rings().flat_map(|ring| ring.points()).collect_vec();
+
+        let hole_indices = self
+            .rings()
+            .scan(0, |start, ring| {
+                *start += ring.points().len();
+                Some(*start)
+            })
+            .take(self.holes.len())
+            .collect_vec();
+
         earcutr::earcut(
-            &self
-                .points
+            &points
                 .iter()
                 .flat_map(|p| [p.longitude.0, p.latitude.0])
                 .collect_vec(),
-            &[],
+            &hole_indices,
             2,
         )
         .unwrap()
         .iter()
-        .map(|i| self.points[*i])
+        .map(|i| *points[*i])
         .collect()
     }
 }
 
+impl From<&Ring> for geo::LineString {
+    fn from(val: &Ring) -> Self {
+        geo::LineString::new(val.points.iter().map(|p| p.into()).collect())
+    }
+}
+
+impl From<&Polygon> for geo::Polygon {
+    fn from(val: &Polygon) -> Self {
+        geo::Polygon::new(
+            (&val.exterior).into(),
+            val.holes.iter().map(|hole| hole.into()).collect(),
+        )
+    }
+}
+
+/// 各リングについて、外周ならそれ自身の番号を、穴ならそれを含む最も小さな外周の番号を返す。
+/// どの外周にも含まれない穴はNoneになる。
+pub(crate) fn enclosing_exteriors(rings: &[Ring]) -> Vec<Option<usize>> {
+    rings
+        .iter()
+        .enumerate()
+        .map(|(i, ring)| {
+            if ring.is_exterior() {
+                return Some(i);
+            }
+
+            rings
+                .iter()
+                .enumerate()
+                .filter(|(_, exterior)| exterior.is_exterior())
+                .filter(|(_, exterior)| {
+                    // 穴が外周と頂点を共有していることがあるので、共有していない頂点で調べる
+                    let point = ring
+                        .points()
+                        .iter()
+                        .find(|p| !exterior.points().contains(p))
+                        .unwrap_or(&ring.points()[0]);
+                    exterior.contains(point)
+                })
+                .min_by(|(_, a), (_, b)| a.signed_area().abs().total_cmp(&b.signed_area().abs()))
+                .map(|(exterior, _)| exterior)
+        })
+        .collect()
+}
+
 impl From<Vec<shapefile::Point>> for Ring {
     fn from(value: Vec<shapefile::Point>) -> Self {
         let points = value.into_iter().map(|p| p.into()).collect();
@@ -182,14 +322,6 @@ impl Line {
             vertices: points.to_owned(),
         }
     }
-
-    pub(crate) fn join_first(&mut self, mut other: Self) {
-        if other.vertices.last() == self.vertices.first() {
-            other.vertices.pop();
-        }
-        other.vertices.append(&mut self.vertices);
-        self.vertices = other.vertices;
-    }
 }
 
 impl From<Vec<shapefile::Point>> for Line {
@@ -269,4 +401,35 @@ mod tests {
 
         assert_eq!(line1, line2);
     }
+
+    fn ring(coordinates: &[(f32, f32)]) -> Ring {
+        Ring::new(
+            coordinates
+                .iter()
+                .map(|(x, y)| Point::new(Of32::from(*y), Of32::from(*x)))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn polygon_assembles_holes_by_winding() {
+        let polygons = Polygon::assemble(vec![
+            // 時計回りの外周と、その内側の反時計回りの穴
+            ring(&[(0.0, 0.0), (0.0, 3.0), (3.0, 3.0), (3.0, 0.0), (0.0, 0.0)]),
+            ring(&[(1.0, 1.0), (2.0, 1.0), (2.0, 2.0), (1.0, 2.0), (1.0, 1.0)]),
+            // どの外周にも含まれない反時計回りのリング
+            ring(&[(5.0, 0.0), (6.0, 0.0), (6.0, 1.0), (5.0, 1.0), (5.0, 0.0)]),
+        ]);
+
+        assert_eq!(polygons.len(), 2);
+        assert_eq!(polygons[0].holes.len(), 1);
+        assert!(polygons[1].holes.is_empty());
+
+        let area: f64 = polygons[0]
+            .triangulate()
+            .chunks(3)
+            .map(|t| signed_area(&[t[0], t[1], t[2], t[0]]).abs())
+            .sum();
+        assert!((area - 8.0).abs() < 1e-6, "{area}");
+    }
 }
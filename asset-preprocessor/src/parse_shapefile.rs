@@ -3,7 +3,6 @@
 use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
-use geo::Simplify;
 use itertools::Itertools;
 use shapefile::dbase::{FieldValue, Record};
 use shapefile::{Shape, ShapeReader};
@@ -11,21 +10,22 @@ use shapefile::{Shape, ShapeReader};
 use renderer_types::*;
 
 use crate::math::*;
+use crate::topology::Topology;
 
-struct VertexBuffer {
+pub(crate) struct VertexBuffer {
     buffer: Vec<(Of32, Of32)>,
     dict: HashMap<(Of32, Of32), usize>,
 }
 
 impl VertexBuffer {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         Self {
             buffer: Default::default(),
             dict: Default::default(),
         }
     }
 
-    fn insert(&mut self, v: (Of32, Of32)) -> usize {
+    pub(crate) fn insert(&mut self, v: (Of32, Of32)) -> usize {
         match self.dict.get(&v) {
             Some(index) => *index,
             None => {
@@ -37,7 +37,7 @@ impl VertexBuffer {
         }
     }
 
-    fn into_buffer(self) -> Vec<(f32, f32)> {
+    pub(crate) fn into_buffer(self) -> Vec<(f32, f32)> {
         self.buffer.into_iter().map(|(x, y)| (x.0, y.0)).collect()
     }
 }
@@ -60,7 +60,10 @@ Simplified shape files are not found.
  - assets/shapefile/earthquake_detailed/earthquake_detailed_simplified.dbf
 
 Please follow:
-  https://github.com/EEWBot/eew-renderer/wiki#shapefile-%E5%85%A5%E6%89%8B%E5%85%88"#
+  https://github.com/EEWBot/eew-renderer/wiki#shapefile-%E5%85%A5%E6%89%8B%E5%85%88
+
+Or build with synthetic fixture assets instead:
+  cargo build --features fixture-assets  (or set EEW_RENDERER_FIXTURE_ASSETS=1)"#
             )
         };
 
@@ -80,8 +83,9 @@ Please follow:
 
 struct AreaRings {
     area_code: codes::地震情報細分区域,
+    name: Option<String>,
     bounding_box: BoundingBox<GeoDegree>,
-    rings: Vec<Ring>,
+    polygons: Vec<Polygon>,
 }
 
 impl AreaRings {
@@ -97,17 +101,24 @@ impl AreaRings {
             FieldValue::Character(None) => codes::地震情報細分区域::UNNUMBERED, // 北方領土・諸外国等がNoneになる
             _ => panic!("知らないshapefileきた？🤔"),
         };
+        let name = match record.get("name") {
+            Some(FieldValue::Character(name)) => name.clone(),
+            _ => None,
+        };
         let bounding_box = (*polygon.bbox()).into();
-        let rings = polygon
-            .rings()
-            .iter()
-            .map(|ring| Ring::from(ring.points().to_vec()))
-            .collect();
+        let polygons = Polygon::assemble(
+            polygon
+                .rings()
+                .iter()
+                .map(|ring| Ring::from(ring.points().to_vec()))
+                .collect(),
+        );
 
         Some(Self {
             area_code,
+            name,
             bounding_box,
-            rings,
+            polygons,
         })
     }
 }
@@ -126,23 +137,27 @@ impl<'a> PointReferences<'a> {
         shapefile.entries.iter().for_each(|area_rings| {
             let area_code = area_rings.area_code;
 
-            area_rings.rings.iter().for_each(|ring| {
-                ring.iter_adjacent_points().for_each(|point_set| {
-                    let reference = map
-                        .entry(point_set.current)
-                        .or_insert(PointReference::new(area_to_pref));
-
-                    reference.mark_area(area_code);
-                    reference.mark_point(point_set.previous);
-                    reference.mark_point(point_set.next);
+            area_rings
+                .polygons
+                .iter()
+                .flat_map(|p| p.rings())
+                .for_each(|ring| {
+                    ring.iter_adjacent_points().for_each(|point_set| {
+                        let reference = map
+                            .entry(point_set.current)
+                            .or_insert(PointReference::new(area_to_pref));
+
+                        reference.mark_area(area_code);
+                        reference.mark_point(point_set.previous);
+                        reference.mark_point(point_set.next);
+                    });
                 });
-            });
         });
 
         Self { map }
     }
 
-    fn cut_points(&self) -> Vec<Point> {
+    fn cut_points(&self) -> HashSet<Point> {
         self.map
             .iter()
             .filter(|(_, r)| r.adjacent_points_count() >= 3)
@@ -195,7 +210,8 @@ impl<'a> PointReference<'a> {
             .area_references()
             .iter()
             .filter(|a| **a != codes::地震情報細分区域::UNNUMBERED)
-            .map(|a| *self.area_to_pref.get(a).unwrap());
+            // 観測点の無い区域は都道府県が分からないので数えない
+            .filter_map(|a| self.area_to_pref.get(a).copied());
         HashSet::from_iter(areas)
     }
 
@@ -204,6 +220,8 @@ impl<'a> PointReference<'a> {
     }
 }
 pub fn read(
+    assets_dir: &Path,
+    lod_levels: &[lod::LodLevel],
     #[allow(non_snake_case)] area_code__pref_code: &HashMap<
         codes::地震情報細分区域,
         codes::地震情報都道府県等,
@@ -212,20 +230,55 @@ pub fn read(
     HashMap<codes::地震情報細分区域, BoundingBox<GeoDegree>>, // area_bounding_box
     HashMap<codes::地震情報細分区域, Vertex<GeoDegree>>,      // area_centers
     Vec<(f32, f32)>,                                          // vertex_buffer
-    Vec<u32>,                                                 // map_indices
+    Vec<Vec<u32>>,                                            // map_indices (LODごと)
     Vec<Vec<u32>>,                                            // area_lines
     Vec<Vec<u32>>,                                            // pref_lines
     Vec<(f32, usize)>,                                        // scale_level_map
+    Vec<AreaLabel>,                                           // area_labels
+    Vec<PrefectureLabel>,                                     // pref_labels
+    HashMap<codes::地震情報細分区域, String>,                 // area_names
+    Vec<(codes::地震情報細分区域, Vec<(f32, f32)>)>,          // area_rings
+    Vec<(usize, Vertex<GeoDegree>)>,                          // dropped_rings (点の数, 位置)
+    BoundingBox<GeoDegree>,                                   // map_extent
 ) {
     let shapefile = Shapefile::new(
-        "../assets/shapefile/earthquake_detailed/earthquake_detailed_simplified.shp",
-        "../assets/shapefile/earthquake_detailed/earthquake_detailed_simplified.dbf",
+        assets_dir.join("shapefile/earthquake_detailed/earthquake_detailed_simplified.shp"),
+        assets_dir.join("shapefile/earthquake_detailed/earthquake_detailed_simplified.dbf"),
     );
     let mut vertex_buffer = VertexBuffer::new();
 
     // @Siro_256 にゃ～っ…！ (ΦωΦ）
 
     let area_centers = calculate_area_centers(&shapefile);
+    let (area_labels, pref_labels) = calculate_label_anchors(&shapefile, area_code__pref_code);
+
+    let area_names: HashMap<codes::地震情報細分区域, String> = shapefile
+        .entries
+        .iter()
+        .filter(|area_rings| area_rings.area_code != codes::地震情報細分区域::UNNUMBERED)
+        .filter_map(|area_rings| Some((area_rings.area_code, area_rings.name.clone()?)))
+        .collect();
+
+    let area_ring_points = shapefile
+        .entries
+        .iter()
+        .filter(|area_rings| area_rings.area_code != codes::地震情報細分区域::UNNUMBERED)
+        .flat_map(|area_rings| {
+            area_rings
+                .polygons
+                .iter()
+                .flat_map(|p| p.rings())
+                .map(|ring| {
+                    let points = ring
+                        .points()
+                        .iter()
+                        .map(|p| (p.longitude.0, p.latitude.0))
+                        .collect();
+
+                    (area_rings.area_code, points)
+                })
+        })
+        .collect();
 
     let area_bounding_box: HashMap<codes::地震情報細分区域, BoundingBox<GeoDegree>> = shapefile
         .entries
@@ -234,92 +287,70 @@ pub fn read(
         .map(|area_rings| (area_rings.area_code, area_rings.bounding_box))
         .collect();
 
-    let map_indices = shapefile
+    // 番号の無い北方領土なども含める
+    let map_extent = shapefile
         .entries
         .iter()
-        .flat_map(|area_rings| &area_rings.rings)
-        .flat_map(|r| r.triangulate())
-        .map(|p| vertex_buffer.insert(p.into()) as u32)
-        .collect();
+        .map(|area_rings| area_rings.bounding_box)
+        .reduce(|merged, bounding_box| merged.merge_float(&bounding_box))
+        .unwrap_or(extent::JAPAN);
 
     let references = PointReferences::tally_of(&shapefile, area_code__pref_code);
 
-    let rings = shapefile
+    let polygons = shapefile
         .entries
         .iter()
-        .flat_map(|area_rings| &area_rings.rings)
+        .flat_map(|area_rings| &area_rings.polygons)
         .collect_vec();
 
-    let cut_points = references.cut_points();
-    let lines = cut_rings(&rings, &cut_points);
+    // 境界を弧に分けてLODごとに一度だけ簡略化し、塗りつぶしと境界線の両方に使う
+    let topology = Topology::new(&polygons, &references.cut_points());
+    let simplified_arcs = lod_levels
+        .iter()
+        .map(|level| topology.simplify(level))
+        .collect_vec();
 
-    let lines: Vec<_> = lines
-        .into_iter()
-        .counts()
+    let map_indices = simplified_arcs
+        .iter()
+        .map(|arcs| {
+            topology
+                .triangulate(arcs)
+                .into_iter()
+                .map(|p| vertex_buffer.insert(p.into()) as u32)
+                .collect()
+        })
+        .collect();
+
+    let dropped_rings = topology
+        .dropped_rings()
         .into_iter()
-        .filter_map(|(l, c)| if c > 1 { Some(l) } else { None })
+        .map(|ring| {
+            let location = Vertex::new(ring.location.longitude.0, ring.location.latitude.0);
+            (ring.points, location)
+        })
         .collect();
 
-    let area_lines = lines
+    let shared_arcs = topology.shared_arcs();
+
+    let area_lines = shared_arcs
         .iter()
-        .filter(|l| references.pref_reference_count(l) == 1)
+        .copied()
+        .filter(|arc| references.pref_reference_count(&topology.arcs()[*arc]) == 1)
         .collect_vec();
 
-    let pref_lines = lines
+    let pref_lines = shared_arcs
         .iter()
-        .filter(|l| references.pref_reference_count(l) >= 2)
+        .copied()
+        .filter(|arc| references.pref_reference_count(&topology.arcs()[*arc]) >= 2)
         .collect_vec();
 
-    let lod_details = [
-        (100.0_f32.powf(1.00), 0.000),
-        (100.0_f32.powf(0.96), 0.003),
-        (100.0_f32.powf(0.92), 0.006),
-        (100.0_f32.powf(0.88), 0.009),
-        (100.0_f32.powf(0.84), 0.012),
-        (100.0_f32.powf(0.80), 0.015),
-        (100.0_f32.powf(0.76), 0.018),
-        (100.0_f32.powf(0.72), 0.021),
-        (100.0_f32.powf(0.68), 0.024),
-        (100.0_f32.powf(0.64), 0.027),
-        (100.0_f32.powf(0.60), 0.030),
-        (100.0_f32.powf(0.56), 0.033),
-        (100.0_f32.powf(0.52), 0.036),
-        (100.0_f32.powf(0.48), 0.039),
-        (100.0_f32.powf(0.44), 0.042),
-        (100.0_f32.powf(0.40), 0.045),
-        (100.0_f32.powf(0.36), 0.048),
-        (100.0_f32.powf(0.32), 0.051),
-        (100.0_f32.powf(0.28), 0.054),
-        (100.0_f32.powf(0.24), 0.057),
-        (100.0_f32.powf(0.60), 0.060),
-        (100.0_f32.powf(0.56), 0.063),
-        (100.0_f32.powf(0.52), 0.066),
-        (100.0_f32.powf(0.48), 0.069),
-        (100.0_f32.powf(0.44), 0.072),
-        (100.0_f32.powf(0.40), 0.075),
-        (100.0_f32.powf(0.36), 0.078),
-        (100.0_f32.powf(0.32), 0.081),
-        (100.0_f32.powf(0.28), 0.084),
-        (100.0_f32.powf(0.24), 0.087),
-        (100.0_f32.powf(0.60), 0.090),
-        (100.0_f32.powf(0.56), 0.093),
-        (100.0_f32.powf(0.52), 0.096),
-        (100.0_f32.powf(0.48), 0.099),
-        (100.0_f32.powf(0.44), 0.102),
-        (100.0_f32.powf(0.40), 0.105),
-        (100.0_f32.powf(0.36), 0.108),
-        (100.0_f32.powf(0.32), 0.111),
-        (100.0_f32.powf(0.28), 0.114),
-        (100.0_f32.powf(0.24), 0.117),
-    ];
-
-    let area_lines = gen_lod(&mut vertex_buffer, &lod_details, &area_lines);
-    let pref_lines = gen_lod(&mut vertex_buffer, &lod_details, &pref_lines);
-
-    let scale_level_map = lod_details
-        .into_iter()
+    let area_lines = gen_lod(&mut vertex_buffer, &simplified_arcs, &area_lines);
+    let pref_lines = gen_lod(&mut vertex_buffer, &simplified_arcs, &pref_lines);
+
+    let scale_level_map = lod_levels
+        .iter()
         .enumerate()
-        .map(|(i, (s, _))| (s, i))
+        .map(|(i, level)| (level.minimum_scale, i))
         .collect();
 
     // ฅ•ω•ฅ Meow
@@ -334,68 +365,31 @@ pub fn read(
             area_lines,
             pref_lines,
             scale_level_map,
+            area_labels,
+            pref_labels,
+            area_names,
+            area_ring_points,
+            dropped_rings,
+            map_extent,
         )
     }
 }
 
-fn cut_rings(rings: &[&Ring], cut_points: &[Point]) -> Vec<Line> {
-    let mut lines: Vec<Line> = Vec::new();
-
-    rings.iter().for_each(|ring| {
-        let points = ring.points();
-        let mut ring_lines: Vec<Line> = Vec::new();
-        let mut start_index: usize = 0;
-
-        for (i, p) in points.iter().enumerate() {
-            if i == 0 {
-                continue;
-            }
-            if i == points.len() - 1 {
-                continue;
-            }
-            if !cut_points.contains(p) {
-                continue;
-            }
-            ring_lines.push(Line::new(&points[start_index..=i]));
-            start_index = i;
-        }
-        ring_lines.push(Line::new(&points[start_index..points.len()]));
-
-        if ring_lines.len() >= 2 {
-            let first_point = ring_lines.first().unwrap().vertices.first().unwrap();
-
-            if !cut_points.contains(first_point) {
-                let last_line = ring_lines.pop().unwrap();
-                ring_lines.first_mut().unwrap().join_first(last_line);
-            }
-        }
-
-        lines.append(&mut ring_lines);
-    });
-
-    lines
-}
-
+/// LODごとに、簡略化済みの弧のうちarcsに含まれるものを線の頂点番号列にする
 fn gen_lod(
     vertex_buffer: &mut VertexBuffer,
-    lod_details: &[(f32, f64)],
-    base_lines: &[&Line],
+    simplified_arcs: &[Vec<Line>],
+    arcs: &[usize],
 ) -> Vec<Vec<u32>> {
-    let geo_lines: Vec<geo::LineString> = base_lines
-        .iter()
-        .map(|l| geo::LineString::from(*l))
-        .collect();
-
-    lod_details
+    simplified_arcs
         .iter()
-        .map(|(_, e)| geo_lines.iter().map(|l| l.simplify(*e)).collect_vec())
-        .map(|l| {
+        .map(|lines| {
             let mut v = Vec::new();
-            for l in l {
-                let l =
-                    l.0.iter()
-                        .map(|c| (Of32::from(c.x as f32), Of32::from(c.y as f32)))
-                        .map(|v| vertex_buffer.insert(v) as u32);
+            for arc in arcs {
+                let l = lines[*arc]
+                    .vertices
+                    .iter()
+                    .map(|p| vertex_buffer.insert((*p).into()) as u32);
                 v.extend(l);
                 v.push(0);
             }
@@ -405,12 +399,98 @@ fn gen_lod(
         .collect()
 }
 
+/// 細分区域名を表示する位置
+pub struct AreaLabel {
+    pub area_code: codes::地震情報細分区域,
+    pub name: String,
+    pub anchor: Vertex<GeoDegree>,
+    /// 表示の優先度。面積(平方度)をそのまま使う。
+    pub weight: f32,
+}
+
+/// 都道府県名を表示する位置
+pub struct PrefectureLabel {
+    pub pref_code: codes::地震情報都道府県等,
+    pub anchor: Vertex<GeoDegree>,
+    /// 表示の優先度。面積(平方度)をそのまま使う。
+    pub weight: f32,
+}
+
+/// ラベルの表示位置を決める。
+/// 重心は凹んだ形状や飛び地では領域外になり得るので、必ず領域内に収まる点を使う。
+/// いずれも優先度の高い(面積の大きい)順に並べて返す。
+fn calculate_label_anchors(
+    shapefile: &Shapefile,
+    area_to_pref: &HashMap<codes::地震情報細分区域, codes::地震情報都道府県等>,
+) -> (Vec<AreaLabel>, Vec<PrefectureLabel>) {
+    use geo::{
+        algorithm::{Area, InteriorPoint},
+        MultiPolygon, Polygon,
+    };
+
+    fn to_polygons(area_rings: &AreaRings) -> Vec<Polygon> {
+        area_rings.polygons.iter().map(Polygon::from).collect()
+    }
+
+    fn anchor_of(polygons: Vec<Polygon>) -> Option<(Vertex<GeoDegree>, f32)> {
+        let multi_polygon = MultiPolygon::new(polygons);
+        let anchor = multi_polygon.interior_point()?;
+
+        Some((
+            Vertex::new(anchor.x() as f32, anchor.y() as f32),
+            multi_polygon.unsigned_area() as f32,
+        ))
+    }
+
+    let numbered_areas = shapefile
+        .entries
+        .iter()
+        .filter(|area_rings| area_rings.area_code != codes::地震情報細分区域::UNNUMBERED)
+        .collect_vec();
+
+    let area_labels = numbered_areas
+        .iter()
+        .filter_map(|area_rings| {
+            let name = area_rings.name.clone()?;
+            let (anchor, weight) = anchor_of(to_polygons(area_rings))?;
+
+            Some(AreaLabel {
+                area_code: area_rings.area_code,
+                name,
+                anchor,
+                weight,
+            })
+        })
+        .sorted_by(|a, b| b.weight.total_cmp(&a.weight))
+        .collect();
+
+    let pref_labels = numbered_areas
+        .iter()
+        .filter_map(|area_rings| Some((*area_to_pref.get(&area_rings.area_code)?, area_rings)))
+        .into_group_map()
+        .into_iter()
+        .filter_map(|(pref_code, areas)| {
+            let polygons = areas.into_iter().flat_map(to_polygons).collect();
+            let (anchor, weight) = anchor_of(polygons)?;
+
+            Some(PrefectureLabel {
+                pref_code,
+                anchor,
+                weight,
+            })
+        })
+        .sorted_by(|a, b| b.weight.total_cmp(&a.weight))
+        .collect();
+
+    (area_labels, pref_labels)
+}
+
 fn calculate_area_centers(
     shapefile: &Shapefile,
 ) -> HashMap<codes::地震情報細分区域, Vertex<GeoDegree>> {
     use geo::{
         algorithm::{Area, Centroid},
-        LineString, Polygon,
+        Polygon,
     };
 
     let area_weighted_vectors: HashMap<codes::地震情報細分区域, Vec<(f64, geo::Point)>> = shapefile
@@ -419,10 +499,9 @@ fn calculate_area_centers(
         .filter(|area_rings| area_rings.area_code != codes::地震情報細分区域::UNNUMBERED)
         .map(|area_rings| {
             let area_polygons: Vec<(f64, geo::Point)> = area_rings
-                .rings
+                .polygons
                 .iter()
-                .map(|ring| LineString::new(ring.points().iter().map(|p| p.into()).collect_vec()))
-                .map(|geo_line_string| Polygon::new(geo_line_string, vec![]))
+                .map(Polygon::from)
                 .map(|geo_polygon| (geo_polygon.unsigned_area(), geo_polygon.centroid().unwrap()))
                 .collect();
 
@@ -7,7 +7,10 @@ use shapefile::dbase::{FieldValue, Record};
 use shapefile::{Shape, ShapeReader};
 
 use crate::math::*;
-use renderer_types::codes;
+use renderer_types::{codes, BoundingBox, GeoDegree, Vertex};
+
+/// 海岸線の長さ(度)がこれより短い津波予報区は、全国を表示したときに線として見えないので印で描く
+const MARKER_COASTLINE_LENGTH: f32 = 0.3;
 
 struct TsunamiAreaCodeBuffer {
     area_code_to_internal_code: HashMap<u32, u16>,
@@ -70,9 +73,53 @@ impl VertexBuffer {
     }
 }
 
+/// 津波予報区ごとの海岸線の長さと重心を求めるための累積
+#[derive(Default)]
+struct CoastlineStats {
+    length: f32,
+    /// 線分の中点を長さで重み付けした和
+    weighted_sum: (f32, f32),
+    vertex_sum: (f32, f32),
+    vertex_count: usize,
+}
+
+impl CoastlineStats {
+    fn add(&mut self, line: &Line) {
+        for v in &line.vertices {
+            self.vertex_sum.0 += v.longitude.0;
+            self.vertex_sum.1 += v.latitude.0;
+            self.vertex_count += 1;
+        }
+
+        for (a, b) in line.vertices.iter().zip(line.vertices.iter().skip(1)) {
+            let (dx, dy) = (b.longitude.0 - a.longitude.0, b.latitude.0 - a.latitude.0);
+            let length = dx.hypot(dy);
+
+            self.length += length;
+            self.weighted_sum.0 += (a.longitude.0 + b.longitude.0) / 2.0 * length;
+            self.weighted_sum.1 += (a.latitude.0 + b.latitude.0) / 2.0 * length;
+        }
+    }
+
+    /// 海岸線の重心。長さの無い点だけの予報区では頂点の平均を使う。
+    fn centroid(&self) -> Vertex<GeoDegree> {
+        if self.length > 0.0 {
+            Vertex::new(
+                self.weighted_sum.0 / self.length,
+                self.weighted_sum.1 / self.length,
+            )
+        } else {
+            let n = self.vertex_count as f32;
+            Vertex::new(self.vertex_sum.0 / n, self.vertex_sum.1 / n)
+        }
+    }
+}
+
 struct AreaLines {
     lines: Vec<Line>,
+    bounding_box: BoundingBox<GeoDegree>,
     tsunami_area_code: codes::津波予報区,
+    name: Option<String>,
 }
 
 impl AreaLines {
@@ -95,6 +142,13 @@ impl AreaLines {
             return None;
         }
 
+        let name = match record.get("name") {
+            Some(FieldValue::Character(name)) => name.clone(),
+            _ => None,
+        };
+
+        let bounding_box = (*polyline.bbox()).into();
+
         let lines: Vec<_> = polyline
             .parts()
             .iter()
@@ -103,7 +157,9 @@ impl AreaLines {
 
         Some(Self {
             lines,
+            bounding_box,
             tsunami_area_code,
+            name,
         })
     }
 }
@@ -126,7 +182,10 @@ Simplified shape files are not found.
  - assets/shapefile/tsunami_forecast/tsunami_forecast_simplified.dbf
 
 Please follow:
-  https://github.com/EEWBot/eew-renderer/wiki#shapefile-%E5%85%A5%E6%89%8B%E5%85%88"#
+  https://github.com/EEWBot/eew-renderer/wiki#shapefile-%E5%85%A5%E6%89%8B%E5%85%88
+
+Or build with synthetic fixture assets instead:
+  cargo build --features fixture-assets  (or set EEW_RENDERER_FIXTURE_ASSETS=1)"#
             )
         };
 
@@ -144,22 +203,45 @@ Please follow:
     }
 }
 
-pub fn read() -> (
-    Vec<(f32, f32, u16)>, // vertices
-    Vec<u32>,             // indices
-    HashMap<u32, u16>,    // tsunami_area_code_to_internal_code
+pub fn read(
+    assets_dir: &Path,
+) -> (
+    Vec<(f32, f32, u16)>,                 // vertices
+    Vec<u32>,                             // indices
+    HashMap<u32, u16>,                    // tsunami_area_code_to_internal_code
+    HashMap<u32, String>,                 // tsunami_area_names
+    HashMap<u32, BoundingBox<GeoDegree>>, // tsunami_area_bounding_boxes
+    HashMap<u32, Vertex<GeoDegree>>,      // tsunami_area_markers
 ) {
     let shapefile = Shapefile::new(
-        "../assets/shapefile/tsunami_forecast/tsunami_forecast_simplified.shp",
-        "../assets/shapefile/tsunami_forecast/tsunami_forecast_simplified.dbf",
+        assets_dir.join("shapefile/tsunami_forecast/tsunami_forecast_simplified.shp"),
+        assets_dir.join("shapefile/tsunami_forecast/tsunami_forecast_simplified.dbf"),
     );
 
     let mut vertex_buffer = VertexBuffer::new();
     let mut lines = Vec::new();
     let mut tsunami_area_code_buffer = TsunamiAreaCodeBuffer::new();
+    let mut tsunami_area_names = HashMap::new();
+    let mut tsunami_area_bounding_boxes = HashMap::<u32, BoundingBox<GeoDegree>>::new();
+    let mut coastline_stats = HashMap::<u32, CoastlineStats>::new();
 
     for e in shapefile.entries {
+        if let Some(name) = e.name {
+            tsunami_area_names.insert(e.tsunami_area_code.0, name);
+        }
+
+        // 一つの予報区が複数のレコードに分かれていることがある
+        tsunami_area_bounding_boxes
+            .entry(e.tsunami_area_code.0)
+            .and_modify(|bbox| *bbox = bbox.merge_float(&e.bounding_box))
+            .or_insert(e.bounding_box);
+
         for line in e.lines {
+            coastline_stats
+                .entry(e.tsunami_area_code.0)
+                .or_default()
+                .add(&line);
+
             let line: Vec<u32> = line
                 .vertices
                 .into_iter()
@@ -179,9 +261,18 @@ pub fn read() -> (
         }
     }
 
+    let tsunami_area_markers = coastline_stats
+        .into_iter()
+        .filter(|(_, stats)| stats.vertex_count > 0 && stats.length < MARKER_COASTLINE_LENGTH)
+        .map(|(code, stats)| (code, stats.centroid()))
+        .collect();
+
     (
         vertex_buffer.into_buffer(),
         lines,
         tsunami_area_code_buffer.into_buffer(),
+        tsunami_area_names,
+        tsunami_area_bounding_boxes,
+        tsunami_area_markers,
     )
 }
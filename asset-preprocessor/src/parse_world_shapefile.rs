@@ -0,0 +1,86 @@
+//! 遠地地震の震央を示すための世界の陸地。
+//! 日本の範囲に収まる陸地は細分区域のshapefileの方で描くので含めない。
+//! Natural Earthのlandなど、陸地をポリゴンで持つshapefileであれば何でもよい。
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use itertools::Itertools;
+use shapefile::{Shape, ShapeReader};
+
+use renderer_types::{extent, lod, GeoDegree, Vertex};
+
+use crate::math::*;
+use crate::parse_shapefile::VertexBuffer;
+use crate::topology::Topology;
+
+/// shapefileが無ければ空の陸地を返す。
+pub fn read(
+    assets_dir: &Path,
+    lod_levels: &[lod::LodLevel],
+) -> (
+    Vec<(f32, f32)>,                 // vertices
+    Vec<Vec<u32>>,                   // triangles (LODごと)
+    Vec<(usize, Vertex<GeoDegree>)>, // dropped_rings (点の数, 位置)
+) {
+    let shp_file = assets_dir.join("shapefile/world_land/world_land_simplified.shp");
+    if !shp_file.exists() {
+        return (Vec::new(), vec![Vec::new(); lod_levels.len()], Vec::new());
+    }
+
+    let shape_reader = ShapeReader::new(std::fs::File::open(shp_file).unwrap()).unwrap();
+
+    let polygons = shape_reader
+        .read()
+        .unwrap()
+        .into_iter()
+        .filter_map(|shape| match shape {
+            Shape::Polygon(polygon) => Some(polygon),
+            _ => None,
+        })
+        .flat_map(|polygon| {
+            Polygon::assemble(
+                polygon
+                    .rings()
+                    .iter()
+                    .map(|ring| Ring::from(ring.points().to_vec()))
+                    .collect(),
+            )
+        })
+        .filter(|polygon| !is_inside_japan(polygon))
+        .collect_vec();
+
+    // 陸地同士で境界を共有することは無いので、リングをそのまま1本の弧として簡略化する
+    let topology = Topology::new(&polygons.iter().collect_vec(), &HashSet::new());
+    let mut vertex_buffer = VertexBuffer::new();
+
+    let triangles = lod_levels
+        .iter()
+        .map(|level| {
+            topology
+                .triangulate(&topology.simplify(level))
+                .into_iter()
+                .map(|p| vertex_buffer.insert(p.into()) as u32)
+                .collect()
+        })
+        .collect();
+
+    let dropped_rings = topology
+        .dropped_rings()
+        .into_iter()
+        .map(|ring| {
+            let location = Vertex::new(ring.location.longitude.0, ring.location.latitude.0);
+            (ring.points, location)
+        })
+        .collect();
+
+    (vertex_buffer.into_buffer(), triangles, dropped_rings)
+}
+
+fn is_inside_japan(polygon: &Polygon) -> bool {
+    polygon
+        .exterior
+        .points()
+        .iter()
+        .all(|p| extent::JAPAN.contains(&Vertex::<GeoDegree>::new(p.longitude.0, p.latitude.0)))
+}
@@ -0,0 +1,33 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use shapefile::dbase::FieldValue;
+
+use renderer_types::codes;
+
+/// 気象庁の「地震情報／都道府県等」のdbf。形状は使わないので、dbfだけを置けばよい
+pub const DBF_PATH: &str = "shapefile/earthquake_prefecture/earthquake_prefecture.dbf";
+
+/// dbfの`code`と`name`から、都道府県等のコードと名称の表を作る。
+/// コードはintensity_stations.jsonの`pref`と同じもの。読めないレコードは検査で報告済みなので飛ばす。
+pub fn read(assets_dir: &Path) -> HashMap<codes::地震情報都道府県等, String> {
+    let dbf_file = std::fs::File::open(assets_dir.join(DBF_PATH)).unwrap();
+    let mut reader = shapefile::dbase::Reader::new(dbf_file).unwrap();
+
+    reader
+        .read()
+        .unwrap()
+        .into_iter()
+        .filter_map(|record| {
+            let Some(FieldValue::Character(Some(code))) = record.get("code") else {
+                return None;
+            };
+            let Some(FieldValue::Character(Some(name))) = record.get("name") else {
+                return None;
+            };
+
+            let code = code.trim().parse().ok()?;
+            Some((codes::地震情報都道府県等(code), name.trim().to_string()))
+        })
+        .collect()
+}
@@ -0,0 +1,23 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+/// romanized_names.jsonの中身。キーはそれぞれのコード
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct RomanizedNames {
+    pub areas: HashMap<u32, String>,
+    pub tsunami_areas: HashMap<u32, String>,
+    pub prefectures: HashMap<u32, String>,
+}
+
+/// 地名のローマ字表記を読む。
+/// 一覧が無ければローマ字の名前の無いバンドルを作るので、空の表を返す。
+pub fn read(assets_dir: &Path) -> RomanizedNames {
+    let Ok(s) = std::fs::read_to_string(assets_dir.join("romanized_names.json")) else {
+        return RomanizedNames::default();
+    };
+
+    serde_json::from_str(&s).unwrap()
+}
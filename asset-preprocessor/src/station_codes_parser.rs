@@ -7,23 +7,23 @@ use serde::Deserialize;
 
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
-#[allow(dead_code)]
-struct JsonEntry {
+#[allow(dead_code)] // name, affi, city_code
+pub(crate) struct JsonEntry {
     #[serde(rename = "lat")]
-    latitude: String,
+    pub(crate) latitude: String,
     #[serde(rename = "lon")]
-    longitude: Lon,
-    name: String,
-    pref: String,
+    pub(crate) longitude: Lon,
+    pub(crate) name: String,
+    pub(crate) pref: String,
     affi: String,
-    area_code: String,
+    pub(crate) area_code: String,
     city_code: String,
-    station_code: String,
+    pub(crate) station_code: String,
 }
 
 #[derive(Deserialize)]
 #[serde(untagged)]
-enum Lon {
+pub(crate) enum Lon {
     Number(f32),
     String(String),
 }
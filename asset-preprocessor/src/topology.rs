@@ -0,0 +1,451 @@
+//! リングを、隣り合うリング同士で共有する弧(arc)に分けて扱う。
+//! 弧は一度だけ簡略化し、その結果を両側のリングの塗りつぶしと境界線の両方に使うので、
+//! LODを粗くしても隣の区域との間に隙間や重なりができず、境界線と塗りつぶしの縁もずれない。
+
+use std::collections::{HashMap, HashSet};
+
+use itertools::Itertools;
+use renderer_types::lod::LodLevel;
+
+use crate::math::*;
+
+/// 潰れたリングの弧の許容誤差を半分にしてやり直す回数の上限
+const MAXIMUM_REFINEMENT: usize = 16;
+
+/// 閉じたリングが面になるのに必要な点の数 (始点と終点は同じ点)
+const MINIMUM_RING_POINTS: usize = 4;
+
+/// 面にならず、三角形分割から除いたリング
+pub(crate) struct DroppedRing {
+    /// 重複を除いた点の数
+    pub(crate) points: usize,
+    /// リングの1点目
+    pub(crate) location: Point,
+}
+
+pub(crate) struct Topology {
+    arcs: Vec<Line>,
+    /// 各ポリゴンを構成するリングの番号。先頭が外周で、残りが穴。
+    polygons: Vec<Vec<usize>>,
+    /// 各リングを構成する(弧の番号, 逆向きに辿るか)の列
+    rings: Vec<Vec<(usize, bool)>>,
+    /// 各リングの元の面積(平方度)
+    areas: Vec<f64>,
+}
+
+impl Topology {
+    /// ポリゴンのリングをcut_pointsで弧に切り分ける。
+    /// cut_pointsを含まないリング(島や穴など)は、リング全体を閉じた1本の弧にする。
+    pub(crate) fn new(polygons: &[&Polygon], cut_points: &HashSet<Point>) -> Self {
+        let rings = polygons.iter().flat_map(|p| p.rings()).collect_vec();
+        let polygons = polygons
+            .iter()
+            .scan(0, |start, polygon| {
+                let len = 1 + polygon.holes.len();
+                *start += len;
+                Some((*start - len..*start).collect())
+            })
+            .collect();
+
+        let mut arcs = Vec::new();
+        let mut arc_indices: HashMap<Vec<Point>, usize> = HashMap::new();
+
+        let ring_arcs = rings
+            .iter()
+            .map(|ring| {
+                split_ring(ring.points(), cut_points)
+                    .into_iter()
+                    .map(|points| {
+                        let (points, reversed) = canonicalize(points);
+                        let index = *arc_indices.entry(points).or_insert_with_key(|points| {
+                            arcs.push(Line::new(points));
+                            arcs.len() - 1
+                        });
+                        (index, reversed)
+                    })
+                    .collect_vec()
+            })
+            .collect();
+
+        let areas = rings.iter().map(|ring| ring.signed_area().abs()).collect();
+
+        Self {
+            arcs,
+            polygons,
+            rings: ring_arcs,
+            areas,
+        }
+    }
+
+    pub(crate) fn arcs(&self) -> &[Line] {
+        &self.arcs
+    }
+
+    /// 2つ以上のリングが共有する弧、つまり区域同士の境界になる弧の番号を返す。
+    pub(crate) fn shared_arcs(&self) -> Vec<usize> {
+        self.rings
+            .iter()
+            .flatten()
+            .map(|(arc, _)| *arc)
+            .counts()
+            .into_iter()
+            .filter(|(_, c)| *c > 1)
+            .map(|(arc, _)| arc)
+            .sorted()
+            .collect()
+    }
+
+    /// 弧をlevelの許容誤差で簡略化する。
+    /// 簡略化で潰れたり小さくなりすぎたりしたリングは、そのリングの弧だけ許容誤差を下げてやり直す。
+    /// 弧は共有されているので、やり直した結果は隣のリングにもそのまま反映される。
+    /// やり直しても潰れるリングは、そのリングの弧を簡略化する前のものに戻す。
+    pub(crate) fn simplify(&self, level: &LodLevel) -> Vec<Line> {
+        let mut tolerances = vec![level.tolerance as f64; self.arcs.len()];
+        let mut simplified = self
+            .arcs
+            .iter()
+            .map(|arc| simplify_arc(arc, level.tolerance as f64))
+            .collect_vec();
+
+        for _ in 0..MAXIMUM_REFINEMENT {
+            let collapsed = self.collapsed_arcs(&simplified, level);
+            if collapsed.is_empty() {
+                return simplified;
+            }
+
+            for arc in collapsed {
+                tolerances[arc] /= 2.0;
+                simplified[arc] = simplify_arc(&self.arcs[arc], tolerances[arc]);
+            }
+        }
+
+        for arc in self.collapsed_arcs(&simplified, level) {
+            simplified[arc] = Line::new(&self.arcs[arc].vertices);
+        }
+
+        simplified
+    }
+
+    /// 形を保てなかったリングを構成する弧の番号
+    fn collapsed_arcs(&self, arcs: &[Line], level: &LodLevel) -> HashSet<usize> {
+        (0..self.rings.len())
+            .filter(|ring| !self.keeps_shape(*ring, arcs, level))
+            .flat_map(|ring| self.rings[ring].iter().map(|(arc, _)| *arc))
+            .collect()
+    }
+
+    /// simplifyで得た弧から全てのポリゴンを組み立て、穴を除いて三角形分割した頂点列を返す。
+    /// 面にならないリングは除く。除いたリングはdropped_ringsで分かる。
+    pub(crate) fn triangulate(&self, arcs: &[Line]) -> Vec<Point> {
+        self.polygons
+            .iter()
+            .filter_map(|rings| {
+                let mut rings = rings
+                    .iter()
+                    .map(|ring| Ring::new(self.ring_points(*ring, arcs)));
+
+                let exterior = rings.next()?;
+                if exterior.points().len() < MINIMUM_RING_POINTS {
+                    return None;
+                }
+                let holes = rings
+                    .filter(|ring| ring.points().len() >= MINIMUM_RING_POINTS)
+                    .collect();
+
+                Some(Polygon::new(exterior, holes))
+            })
+            .flat_map(|polygon| polygon.triangulate())
+            .collect()
+    }
+
+    /// triangulateで除かれるリング。
+    /// simplifyは潰れたリングを簡略化前の弧に戻すので、除かれるのは元から面になっていないリングだけで、LODによらない。
+    pub(crate) fn dropped_rings(&self) -> Vec<DroppedRing> {
+        (0..self.rings.len())
+            .filter_map(|ring| {
+                let points = self.ring_points(ring, &self.arcs).len();
+                if points >= MINIMUM_RING_POINTS {
+                    return None;
+                }
+
+                let (arc, _) = self.rings[ring].first()?;
+                Some(DroppedRing {
+                    points,
+                    location: *self.arcs[*arc].vertices.first()?,
+                })
+            })
+            .collect()
+    }
+
+    fn ring_points(&self, ring: usize, arcs: &[Line]) -> Vec<Point> {
+        let mut points: Vec<Point> = Vec::new();
+
+        for (arc, reversed) in &self.rings[ring] {
+            let vertices = &arcs[*arc].vertices;
+            let vertices: Box<dyn Iterator<Item = &Point>> = if *reversed {
+                Box::new(vertices.iter().rev())
+            } else {
+                Box::new(vertices.iter())
+            };
+
+            // 弧の始点は直前の弧の終点と同じ点
+            let skip = if points.is_empty() { 0 } else { 1 };
+            points.extend(vertices.skip(skip));
+        }
+
+        points.dedup();
+        points
+    }
+
+    /// 簡略化したリングが面として残り、元の面積の半分かlevelで見える大きさを保っているか
+    fn keeps_shape(&self, ring: usize, arcs: &[Line], level: &LodLevel) -> bool {
+        let points = self.ring_points(ring, arcs);
+        if points.len() < MINIMUM_RING_POINTS {
+            return false;
+        }
+
+        let required = f64::min(self.areas[ring] / 2.0, level.minimum_area as f64);
+        signed_area(&points).abs() >= required
+    }
+}
+
+/// 閉じたリングを、cut_pointsを端点とする弧に切り分ける。
+fn split_ring(points: &[Point], cut_points: &HashSet<Point>) -> Vec<Vec<Point>> {
+    let open = match points.split_last() {
+        Some((last, rest)) if Some(last) == points.first() => rest,
+        _ => points,
+    };
+
+    let Some(start) = open.iter().position(|p| cut_points.contains(p)) else {
+        let mut closed = open.to_vec();
+        closed.extend(open.first());
+        return vec![closed];
+    };
+
+    // cut_pointから始まるよう回して閉じ直す
+    let rotated = open[start..]
+        .iter()
+        .chain(&open[..start])
+        .chain(&open[start..=start])
+        .copied()
+        .collect_vec();
+
+    let mut arcs = Vec::new();
+    let mut begin = 0;
+    for (i, p) in rotated.iter().enumerate().skip(1) {
+        if i == rotated.len() - 1 || cut_points.contains(p) {
+            arcs.push(rotated[begin..=i].to_vec());
+            begin = i;
+        }
+    }
+
+    arcs
+}
+
+/// 同じ弧がどちらの向き・どの点から辿られても同じ点列になるよう揃える。
+/// 揃えるために逆向きにした場合はtrueを返す。
+fn canonicalize(mut points: Vec<Point>) -> (Vec<Point>, bool) {
+    let (first, last) = (points[0], points[points.len() - 1]);
+
+    if first != last {
+        let reversed = first > last;
+        if reversed {
+            points.reverse();
+        }
+        return (points, reversed);
+    }
+
+    // 閉じた弧は最小の点から始め、2点目が小さくなる向きに辿る
+    points.pop();
+    let start = points.iter().position_min().unwrap_or(0);
+    points.rotate_left(start);
+
+    let reversed = points.len() > 2 && points[1] > points[points.len() - 1];
+    if reversed {
+        points[1..].reverse();
+    }
+    points.extend(points.first().copied());
+
+    (points, reversed)
+}
+
+/// 弧が自身と交差しないよう、Visvalingam-Whyattで簡略化する。
+/// 閾値は三角形の面積なので、許容誤差を一辺とする正方形の面積に換算する。
+fn simplify_arc(arc: &Line, tolerance: f64) -> Line {
+    use geo::SimplifyVwPreserve;
+
+    let simplified = geo::LineString::from(arc).simplify_vw_preserve(tolerance * tolerance);
+
+    Line::new(
+        &simplified
+            .0
+            .iter()
+            .map(|c| Point::new(Of32::from(c.y as f32), Of32::from(c.x as f32)))
+            .collect_vec(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::*;
+    use crate::topology::*;
+
+    fn ring(coordinates: &[(f32, f32)]) -> Ring {
+        Ring::new(
+            coordinates
+                .iter()
+                .map(|(x, y)| Point::new(Of32::from(*y), Of32::from(*x)))
+                .collect(),
+        )
+    }
+
+    fn polygon(exterior: &[(f32, f32)], holes: &[&[(f32, f32)]]) -> Polygon {
+        Polygon::new(
+            ring(exterior),
+            holes.iter().map(|hole| ring(hole)).collect(),
+        )
+    }
+
+    fn level(tolerance: f32, minimum_area: f32) -> LodLevel {
+        LodLevel {
+            minimum_scale: 0.0,
+            tolerance,
+            minimum_area,
+        }
+    }
+
+    fn triangles_area(points: &[Point]) -> f64 {
+        points
+            .chunks(3)
+            .map(|triangle| {
+                let mut closed = triangle.to_vec();
+                closed.push(triangle[0]);
+                signed_area(&closed).abs()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_shared_arc_is_simplified_once() {
+        // x=1.0で接する2つの四角形。境界は細かく波打っている。
+        let west = polygon(
+            &[
+                (0.0, 0.0),
+                (0.0, 2.0),
+                (1.0, 2.0),
+                (1.01, 1.5),
+                (0.99, 1.0),
+                (1.01, 0.5),
+                (1.0, 0.0),
+                (0.0, 0.0),
+            ],
+            &[],
+        );
+        let east = polygon(
+            &[
+                (1.0, 0.0),
+                (1.01, 0.5),
+                (0.99, 1.0),
+                (1.01, 1.5),
+                (1.0, 2.0),
+                (2.0, 2.0),
+                (2.0, 0.0),
+                (1.0, 0.0),
+            ],
+            &[],
+        );
+        let cut_points = [(1.0, 0.0), (1.0, 2.0)]
+            .into_iter()
+            .map(|(x, y)| Point::new(Of32::from(y), Of32::from(x)))
+            .collect();
+
+        let topology = Topology::new(&[&west, &east], &cut_points);
+        let shared = topology.shared_arcs();
+        assert_eq!(shared.len(), 1);
+
+        let arcs = topology.simplify(&level(0.1, 0.0));
+        assert!(arcs[shared[0]].vertices.len() < topology.arcs()[shared[0]].vertices.len());
+
+        // 両側のリングが同じ簡略化済みの境界を使うので、面積の和が保たれる
+        let total = triangles_area(&topology.triangulate(&arcs));
+        assert!((total - 4.0).abs() < 1e-6, "{total}");
+    }
+
+    #[test]
+    fn test_small_island_is_kept() {
+        let island = polygon(
+            &[
+                (0.0, 0.0),
+                (0.0, 0.01),
+                (0.005, 0.012),
+                (0.01, 0.01),
+                (0.01, 0.0),
+                (0.0, 0.0),
+            ],
+            &[],
+        );
+
+        let topology = Topology::new(&[&island], &HashSet::new());
+        let arcs = topology.simplify(&level(1.0, 1.0));
+
+        assert!(topology.ring_points(0, &arcs).len() >= 4);
+        assert!(!topology.triangulate(&arcs).is_empty());
+        assert!(topology.dropped_rings().is_empty());
+    }
+
+    #[test]
+    fn test_collapsed_ring_falls_back_to_original() {
+        // 許容誤差を下げきっても潰れるほど細い島は、簡略化せずに残す
+        let sliver = polygon(
+            &[
+                (0.0, 0.0),
+                (0.0, 1e-4),
+                (1e-4, 1e-4),
+                (1e-4, 0.0),
+                (0.0, 0.0),
+            ],
+            &[],
+        );
+
+        let topology = Topology::new(&[&sliver], &HashSet::new());
+        let arcs = topology.simplify(&level(1e3, 1.0));
+
+        assert_eq!(arcs[0].vertices, topology.arcs()[0].vertices);
+        assert!(!topology.triangulate(&arcs).is_empty());
+        assert!(topology.dropped_rings().is_empty());
+    }
+
+    #[test]
+    fn test_degenerate_ring_is_reported() {
+        let degenerate = polygon(&[(0.0, 0.0), (1.0, 1.0), (0.0, 0.0)], &[]);
+
+        let topology = Topology::new(&[&degenerate], &HashSet::new());
+        let arcs = topology.simplify(&level(0.1, 0.0));
+
+        assert!(topology.triangulate(&arcs).is_empty());
+        let dropped = topology.dropped_rings();
+        assert_eq!(dropped.len(), 1);
+        assert_eq!(dropped[0].points, 3);
+    }
+
+    #[test]
+    fn test_hole_shares_arc_with_enclave() {
+        // 穴の部分に、別の区域(飛び地)がぴったり収まっている
+        let outer = polygon(
+            &[(0.0, 0.0), (0.0, 3.0), (3.0, 3.0), (3.0, 0.0), (0.0, 0.0)],
+            &[&[(2.0, 2.0), (1.0, 2.0), (1.0, 1.0), (2.0, 1.0), (2.0, 2.0)]],
+        );
+        let enclave = polygon(
+            &[(1.0, 1.0), (1.0, 2.0), (2.0, 2.0), (2.0, 1.0), (1.0, 1.0)],
+            &[],
+        );
+
+        let topology = Topology::new(&[&outer, &enclave], &HashSet::new());
+        assert_eq!(topology.arcs().len(), 2);
+        assert_eq!(topology.shared_arcs().len(), 1);
+
+        let arcs = topology.simplify(&level(0.1, 0.0));
+        let total = triangles_area(&topology.triangulate(&arcs));
+        assert!((total - 9.0).abs() < 1e-6, "{total}");
+    }
+}
@@ -0,0 +1,677 @@
+//! アセットの整合性検査。
+//! 途中で止まらずに全ての問題を集め、機械可読なレポートとして返す。
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::Path;
+
+use serde::Serialize;
+use shapefile::dbase::{FieldValue, Record};
+use shapefile::{Shape, ShapeReader};
+
+use crate::math::{self, Ring};
+use crate::station_codes_parser::{JsonEntry, Lon};
+use crate::{prefecture_names, romanized_names_parser};
+
+/// 点がこれより少ないリングは面を作れない (始点と終点は同じ点)
+const MINIMUM_RING_POINTS: usize = 4;
+
+/// 面積(平方度)がこれ以下のリングは潰れているとみなす
+const DEGENERATE_RING_AREA: f64 = 1e-12;
+
+/// 観測点が一つも無い細分区域の扱い
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum MissingStationFallback {
+    /// エラーとしてバンドルを作らない
+    #[default]
+    Abort,
+    /// 区域の重心を代表点にする
+    Centroid,
+    /// 区域を無効な区域として扱う
+    Skip,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BuildOptions {
+    pub missing_station: MissingStationFallback,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Issue {
+    UnreadableFile {
+        path: String,
+        reason: String,
+    },
+    InvalidAreaCode {
+        record: usize,
+        value: String,
+    },
+    InvalidTsunamiCode {
+        record: usize,
+        value: Option<String>,
+    },
+    InvalidStation {
+        index: usize,
+        field: &'static str,
+        value: String,
+    },
+    DuplicateAreaCode {
+        area_code: u32,
+        records: Vec<usize>,
+    },
+    DuplicateStationCode {
+        station_code: u32,
+        indices: Vec<usize>,
+    },
+    AreaWithoutStation {
+        area_code: u32,
+        fallback: MissingStationFallback,
+    },
+    StationWithoutArea {
+        station_code: u32,
+        area_code: u32,
+    },
+    DegenerateRing {
+        file: &'static str,
+        record: usize,
+        ring: usize,
+        points: usize,
+    },
+    UnenclosedHole {
+        file: &'static str,
+        record: usize,
+        ring: usize,
+    },
+    MissingWorldLand {
+        path: String,
+    },
+    InvalidPrefectureCode {
+        record: usize,
+        value: Option<String>,
+    },
+    PrefectureWithoutName {
+        pref_code: u32,
+    },
+    MissingRomanizedNames {
+        path: String,
+    },
+    DroppedRing {
+        file: &'static str,
+        points: usize,
+        longitude: f32,
+        latitude: f32,
+    },
+}
+
+impl Issue {
+    fn severity(&self) -> Severity {
+        match self {
+            Issue::AreaWithoutStation {
+                fallback: MissingStationFallback::Abort,
+                ..
+            } => Severity::Error,
+            Issue::AreaWithoutStation { .. }
+            | Issue::StationWithoutArea { .. }
+            | Issue::DegenerateRing { .. }
+            | Issue::UnenclosedHole { .. }
+            | Issue::MissingWorldLand { .. }
+            | Issue::PrefectureWithoutName { .. }
+            | Issue::MissingRomanizedNames { .. }
+            | Issue::DroppedRing { .. } => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::UnreadableFile { path, reason } => write!(f, "Cannot read {path}: {reason}"),
+            Issue::InvalidAreaCode { record, value } => {
+                write!(f, "Area record #{record} has an invalid code {value:?}")
+            }
+            Issue::InvalidTsunamiCode { record, value } => {
+                write!(f, "Tsunami record #{record} has an invalid code {value:?}")
+            }
+            Issue::InvalidStation {
+                index,
+                field,
+                value,
+            } => write!(f, "Station #{index} has an invalid {field} {value:?}"),
+            Issue::DuplicateAreaCode { area_code, records } => {
+                write!(f, "Area {area_code} appears in records {records:?}")
+            }
+            Issue::DuplicateStationCode {
+                station_code,
+                indices,
+            } => write!(f, "Station {station_code} appears at {indices:?}"),
+            Issue::AreaWithoutStation {
+                area_code,
+                fallback,
+            } => write!(
+                f,
+                "Area {area_code} has no intensity station (fallback: {fallback:?})"
+            ),
+            Issue::StationWithoutArea {
+                station_code,
+                area_code,
+            } => write!(
+                f,
+                "Station {station_code} belongs to area {area_code}, which has no polygon"
+            ),
+            Issue::DegenerateRing {
+                file,
+                record,
+                ring,
+                points,
+            } => write!(
+                f,
+                "Ring #{ring} of {file} record #{record} is degenerate ({points} points)"
+            ),
+            Issue::UnenclosedHole { file, record, ring } => write!(
+                f,
+                "Ring #{ring} of {file} record #{record} is wound as a hole but lies outside every exterior ring; treating it as an exterior"
+            ),
+            Issue::MissingWorldLand { path } => write!(
+                f,
+                "{path} is not found; distant epicenters are drawn without world land"
+            ),
+            Issue::InvalidPrefectureCode { record, value } => {
+                write!(f, "Prefecture record #{record} has an invalid code {value:?}")
+            }
+            Issue::PrefectureWithoutName { pref_code } => write!(
+                f,
+                "Prefecture {pref_code} has stations but no name; its label is not drawn"
+            ),
+            Issue::MissingRomanizedNames { path } => write!(
+                f,
+                "{path} is not found; romanized alt text falls back to codes"
+            ),
+            Issue::DroppedRing {
+                file,
+                points,
+                longitude,
+                latitude,
+            } => write!(
+                f,
+                "A ring of {file} at ({longitude}, {latitude}) has only {points} distinct points and is not drawn"
+            ),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    #[serde(flatten)]
+    pub issue: Issue,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{severity}: {}", self.message)
+    }
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct ValidationReport {
+    pub errors: usize,
+    pub warnings: usize,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ValidationReport {
+    pub(crate) fn push(&mut self, issue: Issue) {
+        let severity = issue.severity();
+        match severity {
+            Severity::Warning => self.warnings += 1,
+            Severity::Error => self.errors += 1,
+        }
+
+        self.diagnostics.push(Diagnostic {
+            severity,
+            message: issue.to_string(),
+            issue,
+        });
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.errors > 0
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for diagnostic in &self.diagnostics {
+            writeln!(f, "{diagnostic}")?;
+        }
+        write!(f, "{} error(s), {} warning(s)", self.errors, self.warnings)
+    }
+}
+
+/// assets_dir以下のshapefileと観測点一覧を検査する。
+pub fn validate(assets_dir: &Path, options: &BuildOptions) -> ValidationReport {
+    let mut report = ValidationReport::default();
+
+    let area_codes = validate_areas(assets_dir, &mut report);
+    validate_tsunami_areas(assets_dir, &mut report);
+    validate_world_land(assets_dir, &mut report);
+    let station_areas = validate_stations(assets_dir, &mut report);
+    let prefecture_codes = validate_prefectures(assets_dir, &mut report);
+    validate_romanized_names(assets_dir, &mut report);
+
+    if let (Some(prefecture_codes), Some((_, station_prefs))) = (&prefecture_codes, &station_areas)
+    {
+        station_prefs
+            .difference(prefecture_codes)
+            .for_each(|pref_code| {
+                report.push(Issue::PrefectureWithoutName {
+                    pref_code: *pref_code,
+                })
+            });
+    }
+
+    if let (Some(area_codes), Some((station_areas, _))) = (area_codes, station_areas) {
+        let areas_with_station: BTreeSet<u32> = station_areas.values().copied().collect();
+
+        area_codes
+            .difference(&areas_with_station)
+            .for_each(|area_code| {
+                report.push(Issue::AreaWithoutStation {
+                    area_code: *area_code,
+                    fallback: options.missing_station,
+                })
+            });
+
+        station_areas
+            .iter()
+            .filter(|(_, area_code)| !area_codes.contains(area_code))
+            .for_each(|(station_code, area_code)| {
+                report.push(Issue::StationWithoutArea {
+                    station_code: *station_code,
+                    area_code: *area_code,
+                })
+            });
+    }
+
+    report
+}
+
+fn read_records(
+    assets_dir: &Path,
+    name: &str,
+    report: &mut ValidationReport,
+) -> Option<Vec<(Shape, Record)>> {
+    let shp_path = assets_dir.join(format!("shapefile/{name}/{name}_simplified.shp"));
+    let dbf_path = assets_dir.join(format!("shapefile/{name}/{name}_simplified.dbf"));
+
+    let unreadable = |path: &Path, reason: String| Issue::UnreadableFile {
+        path: path.display().to_string(),
+        reason,
+    };
+
+    let shape_reader = std::fs::File::open(&shp_path)
+        .map_err(|e| e.to_string())
+        .and_then(|f| ShapeReader::new(f).map_err(|e| e.to_string()));
+    let dbf_reader = std::fs::File::open(&dbf_path)
+        .map_err(|e| e.to_string())
+        .and_then(|f| shapefile::dbase::Reader::new(f).map_err(|e| e.to_string()));
+
+    let (shape_reader, dbf_reader) = match (shape_reader, dbf_reader) {
+        (Ok(shape_reader), Ok(dbf_reader)) => (shape_reader, dbf_reader),
+        (shape_reader, dbf_reader) => {
+            if let Err(reason) = shape_reader {
+                report.push(unreadable(&shp_path, reason));
+            }
+            if let Err(reason) = dbf_reader {
+                report.push(unreadable(&dbf_path, reason));
+            }
+            return None;
+        }
+    };
+
+    let mut reader = shapefile::reader::Reader::new(shape_reader, dbf_reader);
+    match reader.iter_shapes_and_records().collect() {
+        Ok(records) => Some(records),
+        Err(e) => {
+            report.push(unreadable(&shp_path, e.to_string()));
+            None
+        }
+    }
+}
+
+fn validate_rings(file: &'static str, record: usize, shape: &Shape, report: &mut ValidationReport) {
+    let Shape::Polygon(polygon) = shape else {
+        return;
+    };
+
+    for (ring_index, ring) in polygon.rings().iter().enumerate() {
+        let points = ring.points();
+
+        let doubled_area: f64 = points
+            .windows(2)
+            .map(|w| w[0].x * w[1].y - w[1].x * w[0].y)
+            .sum();
+
+        if points.len() < MINIMUM_RING_POINTS || doubled_area.abs() / 2.0 <= DEGENERATE_RING_AREA {
+            report.push(Issue::DegenerateRing {
+                file,
+                record,
+                ring: ring_index,
+                points: points.len(),
+            });
+        }
+    }
+
+    let rings = polygon
+        .rings()
+        .iter()
+        .map(|ring| Ring::from(ring.points().to_vec()))
+        .collect::<Vec<_>>();
+
+    for (ring_index, exterior) in math::enclosing_exteriors(&rings).into_iter().enumerate() {
+        if exterior.is_none() {
+            report.push(Issue::UnenclosedHole {
+                file,
+                record,
+                ring: ring_index,
+            });
+        }
+    }
+}
+
+/// 番号の付いた細分区域のコードを返す
+fn validate_areas(assets_dir: &Path, report: &mut ValidationReport) -> Option<BTreeSet<u32>> {
+    let records = read_records(assets_dir, "earthquake_detailed", report)?;
+    let mut area_records: BTreeMap<u32, Vec<usize>> = BTreeMap::new();
+
+    for (i, (shape, record)) in records.iter().enumerate() {
+        validate_rings("earthquake_detailed", i, shape, report);
+
+        match record.get("code") {
+            Some(FieldValue::Character(Some(c))) => match c.parse() {
+                Ok(code) => area_records.entry(code).or_default().push(i),
+                Err(_) => report.push(Issue::InvalidAreaCode {
+                    record: i,
+                    value: c.clone(),
+                }),
+            },
+            // 北方領土・諸外国等は番号が無い
+            Some(FieldValue::Character(None)) => (),
+            value => report.push(Issue::InvalidAreaCode {
+                record: i,
+                value: format!("{value:?}"),
+            }),
+        }
+    }
+
+    for (area_code, records) in &area_records {
+        if records.len() > 1 {
+            report.push(Issue::DuplicateAreaCode {
+                area_code: *area_code,
+                records: records.clone(),
+            });
+        }
+    }
+
+    Some(area_records.into_keys().collect())
+}
+
+fn validate_tsunami_areas(assets_dir: &Path, report: &mut ValidationReport) {
+    let Some(records) = read_records(assets_dir, "tsunami_forecast", report) else {
+        return;
+    };
+
+    for (i, (_, record)) in records.iter().enumerate() {
+        match record.get("code") {
+            Some(FieldValue::Character(Some(c))) if c.parse::<u32>().is_ok() => (),
+            Some(FieldValue::Character(c)) => report.push(Issue::InvalidTsunamiCode {
+                record: i,
+                value: c.clone(),
+            }),
+            value => report.push(Issue::InvalidTsunamiCode {
+                record: i,
+                value: value.map(|v| format!("{v:?}")),
+            }),
+        }
+    }
+}
+
+/// 世界の陸地は無くても描画できるので、ファイルが無ければ警告に留める
+fn validate_world_land(assets_dir: &Path, report: &mut ValidationReport) {
+    let shp_path = assets_dir.join("shapefile/world_land/world_land_simplified.shp");
+    if !shp_path.exists() {
+        report.push(Issue::MissingWorldLand {
+            path: shp_path.display().to_string(),
+        });
+        return;
+    }
+
+    let Some(records) = read_records(assets_dir, "world_land", report) else {
+        return;
+    };
+
+    for (i, (shape, _)) in records.iter().enumerate() {
+        validate_rings("world_land", i, shape, report);
+    }
+}
+
+/// <StationCode, AreaCode>と、観測点の属する都道府県等のコードを返す
+fn validate_stations(
+    assets_dir: &Path,
+    report: &mut ValidationReport,
+) -> Option<(BTreeMap<u32, u32>, BTreeSet<u32>)> {
+    let path = assets_dir.join("intensity_stations.json");
+
+    let entries: Vec<JsonEntry> = match std::fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|s| serde_json::from_str(&s).map_err(|e| e.to_string()))
+    {
+        Ok(entries) => entries,
+        Err(reason) => {
+            report.push(Issue::UnreadableFile {
+                path: path.display().to_string(),
+                reason,
+            });
+            return None;
+        }
+    };
+
+    let mut station_indices: BTreeMap<u32, Vec<usize>> = BTreeMap::new();
+    let mut station_areas = BTreeMap::new();
+    let mut station_prefs = BTreeSet::new();
+
+    for (i, entry) in entries.iter().enumerate() {
+        let mut invalid = |field, value: &str| {
+            report.push(Issue::InvalidStation {
+                index: i,
+                field,
+                value: value.to_string(),
+            })
+        };
+
+        if entry.latitude.parse::<f32>().is_err() {
+            invalid("lat", &entry.latitude);
+        }
+        if let Lon::String(lon) = &entry.longitude {
+            if lon.parse::<f32>().is_err() {
+                invalid("lon", lon);
+            }
+        }
+        match entry.pref.parse::<u32>() {
+            Ok(pref_code) => {
+                station_prefs.insert(pref_code);
+            }
+            Err(_) => invalid("pref", &entry.pref),
+        }
+
+        let area_code = entry.area_code.parse::<u32>();
+        if area_code.is_err() {
+            invalid("areaCode", &entry.area_code);
+        }
+
+        let station_code = entry.station_code.parse::<u32>();
+        if station_code.is_err() {
+            invalid("stationCode", &entry.station_code);
+        }
+
+        if let (Ok(station_code), Ok(area_code)) = (station_code, area_code) {
+            station_indices.entry(station_code).or_default().push(i);
+            station_areas.insert(station_code, area_code);
+        }
+    }
+
+    for (station_code, indices) in station_indices {
+        if indices.len() > 1 {
+            report.push(Issue::DuplicateStationCode {
+                station_code,
+                indices,
+            });
+        }
+    }
+
+    Some((station_areas, station_prefs))
+}
+
+/// 名前の付いた都道府県等のコードを返す
+fn validate_prefectures(assets_dir: &Path, report: &mut ValidationReport) -> Option<BTreeSet<u32>> {
+    let path = assets_dir.join(prefecture_names::DBF_PATH);
+
+    let records = match std::fs::File::open(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|f| shapefile::dbase::Reader::new(f).map_err(|e| e.to_string()))
+        .and_then(|mut reader| reader.read().map_err(|e| e.to_string()))
+    {
+        Ok(records) => records,
+        Err(reason) => {
+            report.push(Issue::UnreadableFile {
+                path: path.display().to_string(),
+                reason,
+            });
+            return None;
+        }
+    };
+
+    let mut prefecture_codes = BTreeSet::new();
+
+    for (i, record) in records.iter().enumerate() {
+        let named = matches!(record.get("name"), Some(FieldValue::Character(Some(_))));
+
+        match record.get("code") {
+            Some(FieldValue::Character(Some(c))) => match c.trim().parse() {
+                Ok(code) => {
+                    if named {
+                        prefecture_codes.insert(code);
+                    }
+                }
+                Err(_) => report.push(Issue::InvalidPrefectureCode {
+                    record: i,
+                    value: Some(c.clone()),
+                }),
+            },
+            value => report.push(Issue::InvalidPrefectureCode {
+                record: i,
+                value: value.map(|v| format!("{v:?}")),
+            }),
+        }
+    }
+
+    Some(prefecture_codes)
+}
+
+/// ローマ字の名前が無くても描画できるので、ファイルが無ければ警告に留める
+fn validate_romanized_names(assets_dir: &Path, report: &mut ValidationReport) {
+    let path = assets_dir.join("romanized_names.json");
+    if !path.exists() {
+        report.push(Issue::MissingRomanizedNames {
+            path: path.display().to_string(),
+        });
+        return;
+    }
+
+    if let Err(reason) = std::fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|s| {
+            serde_json::from_str::<romanized_names_parser::RomanizedNames>(&s)
+                .map_err(|e| e.to_string())
+        })
+    {
+        report.push(Issue::UnreadableFile {
+            path: path.display().to_string(),
+            reason,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bundle;
+    use crate::fixture;
+    use crate::validate::*;
+
+    #[test]
+    fn test_report_all_issues() {
+        let dir =
+            std::env::temp_dir().join(format!("eew-renderer-validate-{}", std::process::id()));
+        fixture::write(&dir);
+
+        // 区域101の観測点を、ポリゴンの無い区域999に付け替える
+        let stations_path = dir.join("intensity_stations.json");
+        let mut stations: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&stations_path).unwrap()).unwrap();
+        for station in stations.as_array_mut().unwrap() {
+            if station["areaCode"] == "101" {
+                station["areaCode"] = "999".into();
+            }
+        }
+        std::fs::write(&stations_path, stations.to_string()).unwrap();
+
+        let report = validate(&dir, &BuildOptions::default());
+        let issues: Vec<_> = report.diagnostics.iter().map(|d| &d.issue).collect();
+
+        assert_eq!(report.errors, 1);
+        assert_eq!(report.warnings, 1);
+        assert!(issues.contains(&&Issue::AreaWithoutStation {
+            area_code: 101,
+            fallback: MissingStationFallback::Abort,
+        }));
+        assert!(issues.contains(&&Issue::StationWithoutArea {
+            station_code: 1000101,
+            area_code: 999,
+        }));
+        assert!(bundle::build(&dir, "test".to_string(), &BuildOptions::default()).is_err());
+
+        // 重心で代替すれば区域101も有効な区域になる
+        let options = BuildOptions {
+            missing_station: MissingStationFallback::Centroid,
+        };
+        let (bundle, report) = bundle::build(&dir, "test".to_string(), &options).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(report.errors, 0);
+        let (position_index, _) = bundle.areas[&101];
+        assert!(position_index >= bundle.station_codes.len());
+        assert_eq!(
+            bundle.intensity_station_positions[position_index],
+            (140.5, 38.0)
+        );
+    }
+}
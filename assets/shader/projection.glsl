@@ -0,0 +1,50 @@
+// 各頂点シェーダーの#versionの直後に差し込まれる。
+// renderer-types/src/projection.rs と同じ式で投影すること。
+
+/**
+ * 0 -> メルカトル図法
+ * 1 -> 正距円筒図法
+ * 2 -> ランベルト正角円錐図法
+ * 3 -> 正射図法
+**/
+uniform int projection;
+// 投影の原点 (経度, 緯度)
+uniform vec2 projection_origin;
+// ランベルト正角円錐図法の(n, F, ρ0)
+uniform vec3 conic_parameters;
+
+const float PI = 3.14159265358979323846264338327950288;
+const float e = 0.081819191042815791; // https://ja.wikipedia.org/wiki/GRS80
+
+vec2 project(vec2 coord) {
+    vec2 radianized = radians(coord);
+    vec2 origin = radians(projection_origin);
+    float lambda = radianized.x;
+    float phi = radianized.y;
+    vec2 projected;
+
+    if (projection == 1) {
+        projected = vec2(lambda, phi);
+    } else if (projection == 2) {
+        float n = conic_parameters.x;
+        float rho = conic_parameters.y / pow(tan(PI / 4.0 + phi / 2.0), n);
+        float theta = n * (mod(lambda - origin.x + PI, 2.0 * PI) - PI);
+        projected = vec2(rho * sin(theta), conic_parameters.z - rho * cos(theta));
+    } else if (projection == 3) {
+        float d_lambda = lambda - origin.x;
+        projected = vec2(
+            cos(phi) * sin(d_lambda),
+            cos(origin.y) * sin(phi) - sin(origin.y) * cos(phi) * cos(d_lambda)
+        );
+        float cos_c = sin(origin.y) * sin(phi) + cos(origin.y) * cos(phi) * cos(d_lambda);
+
+        // 裏側の点は地平線上に寄せる
+        if (cos_c < 0.0) {
+            projected /= max(length(projected), 1.19209290e-07);
+        }
+    } else {
+        projected = vec2(lambda, atanh(sin(phi)) - e * atanh(e * sin(phi)));
+    }
+
+    return projected / PI;
+}
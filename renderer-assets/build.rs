@@ -1,95 +1,54 @@
-#![allow(clippy::type_complexity)]
+use std::path::PathBuf;
 
-use std::collections::HashMap;
-
-use const_gen::*;
-use ordered_float::NotNan;
-
-mod station_codes_parser;
-use asset_preprocessor::{parse_lake_shapefile, parse_shapefile, parse_tsunami_shapefile};
-use renderer_types::{BoundingBox, GeoDegree, Vertex};
+use asset_preprocessor::validate::BuildOptions;
 
 fn main() {
-    let (tsunami_vertices, tsunami_indices, tsunami_area_code_to_internal_code) =
-        parse_tsunami_shapefile::read();
-
-    let (lake_vertices, lake_indices) = parse_lake_shapefile::read();
-
-    let s = std::fs::read_to_string("../assets/intensity_stations.json").unwrap();
-
-    #[allow(non_snake_case)]
-    let (
-        intensity_station_minimized,
-        area_code__intensity_station_range,
-        station_code__index,
-        area_code__pref_code,
-    ) = station_codes_parser::read(&s);
-
-    #[allow(non_snake_case)]
-    let (
-        area_code__bbox,
-        area_code__centers,
-        vertices,
-        indices,
-        area_lines,
-        pref_lines,
-        scale_level_map,
-    ) = parse_shapefile::read(&area_code__pref_code);
-
-    // <AreaCode, (StationIndex, (BBox))>
-    let areas: HashMap<u32, (usize, (f32, f32, f32, f32))> = area_code__bbox
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=../assets/shapefile");
+    println!("cargo:rerun-if-changed=../assets/intensity_stations.json");
+    println!("cargo:rerun-if-changed=../assets/romanized_names.json");
+    println!("cargo:rerun-if-env-changed=EEW_RENDERER_FIXTURE_ASSETS");
+
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").unwrap());
+
+    let use_fixture = std::env::var_os("CARGO_FEATURE_FIXTURE_ASSETS").is_some()
+        || std::env::var_os("EEW_RENDERER_FIXTURE_ASSETS").is_some();
+
+    let (assets_dir, version) = if use_fixture {
+        let fixture_dir = out_dir.join("fixture");
+        asset_preprocessor::fixture::write(&fixture_dir);
+
+        (
+            fixture_dir,
+            format!("fixture-{}", env!("CARGO_PKG_VERSION")),
+        )
+    } else {
+        (
+            PathBuf::from("../assets"),
+            format!("builtin-{}", env!("CARGO_PKG_VERSION")),
+        )
+    };
+
+    let (bundle, report) =
+        match asset_preprocessor::bundle::build(&assets_dir, version, &BuildOptions::default()) {
+            Ok(built) => built,
+            Err(report) => panic!(
+                r#"EEWBot Renderer requirements is not satisfied.
+
+{report}
+
+Run `cargo run -p asset-preprocessor -- validate` for a machine-readable report.
+Shapefiles can be obtained by following:
+  https://github.com/EEWBot/eew-renderer/wiki#shapefile-%E5%85%A5%E6%89%8B%E5%85%88
+Or build with synthetic fixture assets instead:
+  cargo build --features fixture-assets  (or set EEW_RENDERER_FIXTURE_ASSETS=1)"#
+            ),
+        };
+
+    report
+        .diagnostics
         .iter()
-        .map(|(code, bbox)| {
-            let area = area_code__intensity_station_range
-                .get(code)
-                .expect("地図上にあるareaだがintensity_stations.json上に無い");
-
-            let stations = &intensity_station_minimized[area.start_i..area.start_i + area.n];
-
-            let area_center = area_code__centers.get(code).unwrap();
-
-            let nearest_intensity_station_index = stations
-                .iter()
-                .enumerate()
-                .min_by_key(|(_i, &station)| {
-                    let station = Vertex::<GeoDegree>::new(station.0, station.1);
-                    NotNan::new(area_center.euclidean_distance(&station))
-                        .expect("なぁん…観測点距離が何故かNaN")
-                })
-                .map(|(offset, _station)| area.start_i + offset)
-                .expect("エリア内に一つも観測点がない");
-
-            (
-                code.0,
-                (nearest_intensity_station_index, bbox_to_tuple(bbox)),
-            )
-        })
-        .collect();
-
-    let const_declarations = [
-        const_declaration!(INTENSITY_STATION_POSITIONS = intensity_station_minimized),
-        const_declaration!(AREAS = areas),
-        const_declaration!(STATION_CODES = station_code__index),
-        const_declaration!(VERTICES = vertices),
-        const_declaration!(MAP_TRIANGLES = indices),
-        const_declaration!(AREA_LINES = area_lines),
-        const_declaration!(PREF_LINES = pref_lines),
-        const_declaration!(SCALE_LEVEL_MAP = scale_level_map),
-        const_declaration!(LAKE_VERTICES = lake_vertices),
-        const_declaration!(LAKE_INDICES = lake_indices),
-        const_declaration!(TSUNAMI_VERTICES = tsunami_vertices),
-        const_declaration!(TSUNAMI_INDICES = tsunami_indices),
-        const_declaration!(TSUNAMI_AREA_CODE_TO_INTERNAL_CODE = tsunami_area_code_to_internal_code),
-    ]
-    .join("\n");
-
-    std::fs::write(
-        format!("{}/data.rs", std::env::var("OUT_DIR").unwrap()),
-        const_declarations,
-    )
-    .unwrap();
-}
+        .for_each(|diagnostic| println!("cargo:warning={diagnostic}"));
 
-fn bbox_to_tuple(bb: &BoundingBox<GeoDegree>) -> (f32, f32, f32, f32) {
-    (bb.min.x(), bb.min.y(), bb.max.x(), bb.max.y())
+    std::fs::write(out_dir.join("assets.bin"), bundle.encode().unwrap()).unwrap();
 }
@@ -1,22 +1,61 @@
-#![allow(clippy::redundant_static_lifetimes)]
-#![allow(clippy::type_complexity)]
-
-include!(concat!(env!("OUT_DIR"), "/data.rs"));
+use std::path::Path;
+use std::sync::OnceLock;
 
+use renderer_types::bundle::{AssetBundle, BundleError};
 use renderer_types::*;
+use thiserror::Error;
+
+mod spatial;
+
+/// 最寄りの細分区域を探す範囲(セル数)
+const NEAREST_AREA_SEARCH_RADIUS: i32 = 10;
+
+/// ビルド時に生成される組み込みのアセットバンドル
+static BUILTIN_BUNDLE: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/assets.bin"));
+
+static BUILTIN: OnceLock<AssetBundle> = OnceLock::new();
+
+static LOADED: OnceLock<AssetBundle> = OnceLock::new();
+
+/// 使用中のバンドルを返す。外部のバンドルが読み込まれていなければ組み込みのものを使う。
+fn bundle() -> &'static AssetBundle {
+    LOADED.get().unwrap_or_else(|| {
+        BUILTIN.get_or_init(|| {
+            AssetBundle::decode(BUILTIN_BUNDLE).expect("組み込みのアセットバンドルが読めない")
+        })
+    })
+}
+
+#[derive(Error, Debug)]
+pub enum LoadBundleError {
+    #[error("Failed to read asset bundle: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Bundle(#[from] BundleError),
+
+    #[error("Another asset bundle is already loaded")]
+    AlreadyLoaded,
+
+    #[error("The builtin asset bundle is already in use")]
+    TooLate,
+}
 
 pub struct QueryInterface;
 
 pub struct Geometries {
     pub vertices: &'static [(f32, f32)],
-    pub map_triangles: &'static [u32],
-    pub area_lines: &'static [&'static [u32]],
-    pub pref_lines: &'static [&'static [u32]],
+    /// LODごとの陸地の三角形
+    pub map_triangles: &'static [Vec<u32>],
+    pub area_lines: &'static [Vec<u32>],
+    pub pref_lines: &'static [Vec<u32>],
 }
 
-pub struct LakeGeometries {
+/// 遠地地震のときに描く、日本の範囲外の陸地
+pub struct WorldGeometries {
     pub vertices: &'static [(f32, f32)],
-    pub indices: &'static [u32],
+    /// LODごとの陸地の三角形
+    pub triangles: &'static [Vec<u32>],
 }
 
 pub struct TsunamiGeometries {
@@ -24,76 +63,341 @@ pub struct TsunamiGeometries {
     pub indices: &'static [u32],
 }
 
+/// 地図上に地名を表示する位置
+#[derive(Clone, Copy, Debug)]
+pub struct PlaceLabel {
+    pub name: &'static str,
+    pub position: Vertex<GeoDegree>,
+    /// 大きいほど優先して表示する
+    pub weight: f32,
+}
+
 impl QueryInterface {
+    /// 外部のアセットバンドルを読み込み、以降の問い合わせに使う。読み込んだバンドルのバージョンを返す。
+    /// 組み込みのバンドルのデータを渡した後で差し替えると食い違うので、どの問い合わせよりも先に呼ぶ。
+    /// 読み込めるのは一度だけ。
+    pub fn load_bundle(path: &Path) -> Result<&'static str, LoadBundleError> {
+        if BUILTIN.get().is_some() {
+            return Err(LoadBundleError::TooLate);
+        }
+
+        let bundle = AssetBundle::decode(&std::fs::read(path)?)?;
+
+        LOADED
+            .set(bundle)
+            .map_err(|_| LoadBundleError::AlreadyLoaded)?;
+
+        Ok(Self::bundle_version())
+    }
+
+    pub fn bundle_version() -> &'static str {
+        &bundle().version
+    }
+
     pub fn geometries() -> Geometries {
+        let bundle = bundle();
+
         Geometries {
-            vertices: VERTICES,
-            map_triangles: MAP_TRIANGLES,
-            area_lines: AREA_LINES,
-            pref_lines: PREF_LINES,
+            vertices: &bundle.vertices,
+            map_triangles: &bundle.map_triangles,
+            area_lines: &bundle.area_lines,
+            pref_lines: &bundle.pref_lines,
         }
     }
 
-    pub fn lake_geometries() -> LakeGeometries {
-        LakeGeometries {
-            vertices: LAKE_VERTICES,
-            indices: LAKE_INDICES,
+    pub fn world_geometries() -> WorldGeometries {
+        let bundle = bundle();
+
+        WorldGeometries {
+            vertices: &bundle.world_vertices,
+            triangles: &bundle.world_triangles,
         }
     }
 
     pub fn tsunami_geometries() -> TsunamiGeometries {
+        let bundle = bundle();
+
         TsunamiGeometries {
-            vertices: TSUNAMI_VERTICES,
-            indices: TSUNAMI_INDICES,
+            vertices: &bundle.tsunami_vertices,
+            indices: &bundle.tsunami_indices,
         }
     }
 
     pub fn is_valid_earthquake_area_code(area_code: codes::地震情報細分区域) -> bool {
-        AREAS.contains_key(&area_code.0)
+        bundle().areas.contains_key(&area_code.0)
     }
 
     pub fn is_valid_tsunami_area_code(area_code: codes::津波予報区) -> bool {
-        TSUNAMI_AREA_CODE_TO_INTERNAL_CODE.contains_key(&area_code.0)
+        bundle()
+            .tsunami_area_code_to_internal_code
+            .contains_key(&area_code.0)
     }
 
     pub fn tsunami_area_code_to_internal_code(area_code: codes::津波予報区) -> Option<u16> {
-        TSUNAMI_AREA_CODE_TO_INTERNAL_CODE
+        bundle()
+            .tsunami_area_code_to_internal_code
             .get(&area_code.0)
             .copied()
     }
 
     pub fn tsunami_area_code_count() -> usize {
-        TSUNAMI_AREA_CODE_TO_INTERNAL_CODE.len()
+        bundle().tsunami_area_code_to_internal_code.len()
     }
 
     pub fn query_bounding_box_by_area(
         area_code: codes::地震情報細分区域,
     ) -> Option<BoundingBox<GeoDegree>> {
-        let tuple = AREAS.get(&area_code.0)?.1;
+        let tuple = bundle().areas.get(&area_code.0)?.1;
+        let min = Vertex::new(tuple.0, tuple.1);
+        let max = Vertex::new(tuple.2, tuple.3);
+        Some(BoundingBox::new(min, max))
+    }
+
+    pub fn query_bounding_box_by_tsunami_area(
+        area_code: codes::津波予報区,
+    ) -> Option<BoundingBox<GeoDegree>> {
+        let tuple = bundle().tsunami_area_bounding_boxes.get(&area_code.0)?;
         let min = Vertex::new(tuple.0, tuple.1);
         let max = Vertex::new(tuple.2, tuple.3);
         Some(BoundingBox::new(min, max))
     }
 
+    /// 震央が日本の範囲にあるかを判定するための範囲。細分区域全体に離島の範囲を合わせたもの。
+    pub fn query_domestic_extent() -> BoundingBox<GeoDegree> {
+        let tuple = bundle().map_extent;
+        let min = Vertex::new(tuple.0, tuple.1);
+        let max = Vertex::new(tuple.2, tuple.3);
+        extent::domestic(BoundingBox::new(min, max))
+    }
+
+    /// 線として見えないほど小さい津波予報区なら、印を描く位置を返す。
+    pub fn query_tsunami_marker_position(
+        area_code: codes::津波予報区,
+    ) -> Option<Vertex<GeoDegree>> {
+        let (lon, lat) = bundle().tsunami_area_markers.get(&area_code.0)?;
+        Some(Vertex::new(*lon, *lat))
+    }
+
     pub fn query_rendering_center_by_area(
         area_code: codes::地震情報細分区域,
     ) -> Option<Vertex<GeoDegree>> {
-        Some(INTENSITY_STATION_POSITIONS[AREAS.get(&area_code.0)?.0].into())
+        let bundle = bundle();
+        Some(bundle.intensity_station_positions[bundle.areas.get(&area_code.0)?.0].into())
     }
 
     pub fn query_position_by_station_code(
         intensity_station_code: codes::震度観測点,
     ) -> Option<Vertex<GeoDegree>> {
-        Some(INTENSITY_STATION_POSITIONS[*STATION_CODES.get(&intensity_station_code.0)?].into())
+        let bundle = bundle();
+        Some(
+            bundle.intensity_station_positions
+                [*bundle.station_codes.get(&intensity_station_code.0)?]
+            .into(),
+        )
     }
 
+    pub fn query_area_name(area_code: codes::地震情報細分区域) -> Option<&'static str> {
+        bundle().area_names.get(&area_code.0).map(String::as_str)
+    }
+
+    pub fn query_tsunami_area_name(area_code: codes::津波予報区) -> Option<&'static str> {
+        bundle()
+            .tsunami_area_names
+            .get(&area_code.0)
+            .map(String::as_str)
+    }
+
+    pub fn query_prefecture_name(
+        pref_code: codes::地震情報都道府県等
+    ) -> Option<&'static str> {
+        bundle()
+            .prefecture_names
+            .get(&pref_code.0)
+            .map(String::as_str)
+    }
+
+    /// 細分区域名のローマ字表記を返す。用意されていなければNoneを返す。
+    pub fn query_romanized_area_name(
+        area_code: codes::地震情報細分区域
+    ) -> Option<&'static str> {
+        bundle()
+            .romanized_area_names
+            .get(&area_code.0)
+            .map(String::as_str)
+    }
+
+    /// 津波予報区名のローマ字表記を返す。用意されていなければNoneを返す。
+    pub fn query_romanized_tsunami_area_name(
+        area_code: codes::津波予報区
+    ) -> Option<&'static str> {
+        bundle()
+            .romanized_tsunami_area_names
+            .get(&area_code.0)
+            .map(String::as_str)
+    }
+
+    /// 都道府県名のローマ字表記を返す。用意されていなければNoneを返す。
+    pub fn query_romanized_prefecture_name(
+        pref_code: codes::地震情報都道府県等,
+    ) -> Option<&'static str> {
+        bundle()
+            .romanized_prefecture_names
+            .get(&pref_code.0)
+            .map(String::as_str)
+    }
+
+    /// 点を含む細分区域を返す。海上など、どの区域にも含まれない場合はNoneを返す。
+    pub fn query_area_by_point(
+        point: Vertex<GeoDegree>,
+    ) -> Option<codes::地震情報細分区域> {
+        let bundle = bundle();
+        let candidates = bundle.area_grid.get(&GridCell::of(point).key())?;
+
+        // 穴を持つ区域もあるので、区域ごとに点を含むリングの数の偶奇で判定する
+        let mut parities: Vec<(u32, bool)> = Vec::new();
+
+        for ring_index in candidates.iter() {
+            let (area_code, start, len, bbox) = bundle.area_rings[*ring_index as usize];
+            let bbox = BoundingBox::<GeoDegree>::new(
+                Vertex::new(bbox.0, bbox.1),
+                Vertex::new(bbox.2, bbox.3),
+            );
+
+            if point.x() < bbox.min.x()
+                || bbox.max.x() < point.x()
+                || point.y() < bbox.min.y()
+                || bbox.max.y() < point.y()
+            {
+                continue;
+            }
+
+            let ring = &bundle.area_ring_vertices[start as usize..(start + len) as usize];
+            if !spatial::ring_contains(ring, point) {
+                continue;
+            }
+
+            match parities.iter_mut().find(|(code, _)| *code == area_code) {
+                Some((_, inside)) => *inside = !*inside,
+                None => parities.push((area_code, true)),
+            }
+        }
+
+        parities
+            .into_iter()
+            .find(|(_, inside)| *inside)
+            .map(|(code, _)| codes::地震情報細分区域(code))
+    }
+
+    /// 点に最も近い細分区域と、そこまでのおおよその距離(km)を返す。
+    /// 点がいずれかの区域に含まれる場合、距離は0になる。
+    pub fn query_nearest_area(
+        point: Vertex<GeoDegree>,
+    ) -> Option<(codes::地震情報細分区域, f32)> {
+        if let Some(area_code) = Self::query_area_by_point(point) {
+            return Some((area_code, 0.0));
+        }
+
+        let bundle = bundle();
+        let origin = GridCell::of(point);
+        let mut nearest: Option<(u32, f32)> = None;
+
+        for radius in 0..=NEAREST_AREA_SEARCH_RADIUS {
+            // これ以降のセルは、既に見つかったものより必ず遠い
+            let lower_bound =
+                spatial::minimum_distance_of_cells(radius - 1, GridCell::SIZE, point.y());
+            if nearest.is_some_and(|(_, distance)| distance <= lower_bound) {
+                break;
+            }
+
+            let ring_indices = origin
+                .ring(radius)
+                .into_iter()
+                .filter_map(|cell| bundle.area_grid.get(&cell.key()))
+                .flat_map(|indices| indices.iter());
+
+            for ring_index in ring_indices {
+                let (area_code, start, len, _) = bundle.area_rings[*ring_index as usize];
+                let ring = &bundle.area_ring_vertices[start as usize..(start + len) as usize];
+                let distance = spatial::distance_to_ring(ring, point);
+
+                if nearest.is_none_or(|(_, nearest_distance)| distance < nearest_distance) {
+                    nearest = Some((area_code, distance));
+                }
+            }
+        }
+
+        nearest.map(|(code, distance)| (codes::地震情報細分区域(code), distance))
+    }
+
+    /// 点を含む細分区域が属する都道府県を返す。
+    pub fn query_prefecture_by_point(
+        point: Vertex<GeoDegree>,
+    ) -> Option<codes::地震情報都道府県等> {
+        let area_code = Self::query_area_by_point(point)?;
+
+        bundle()
+            .area_prefectures
+            .get(&area_code.0)
+            .map(|code| codes::地震情報都道府県等(*code))
+    }
+
+    /// 拡大率に応じたLODを返す。境界線・陸地・世界の陸地で共通。
     pub fn query_lod_level_by_scale(scale: f32) -> Option<usize> {
-        SCALE_LEVEL_MAP
+        bundle()
+            .scale_level_map
             .iter()
             .find_map(|(s, l)| if *s <= scale { Some(*l) } else { None })
     }
 
     pub fn query_lod_level_count() -> usize {
-        AREA_LINES.len()
+        bundle().scale_level_map.len()
+    }
+
+    /// 都道府県名のラベルを優先度の高い順に返す。
+    pub fn prefecture_labels() -> impl Iterator<Item = PlaceLabel> {
+        bundle()
+            .pref_labels
+            .iter()
+            .map(|(name, lon, lat, weight)| PlaceLabel {
+                name: name.as_str(),
+                position: Vertex::new(*lon, *lat),
+                weight: *weight,
+            })
+    }
+
+    /// 細分区域名のラベルを優先度の高い順に返す。
+    pub fn area_labels() -> impl Iterator<Item = PlaceLabel> {
+        bundle()
+            .area_labels
+            .iter()
+            .filter_map(|(code, lon, lat, weight)| {
+                Some(PlaceLabel {
+                    name: Self::query_area_name(codes::地震情報細分区域(*code))?,
+                    position: Vertex::new(*lon, *lat),
+                    weight: *weight,
+                })
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_load_bundle_after_query() {
+        let builtin_version = QueryInterface::bundle_version();
+
+        let mut bundle = AssetBundle::decode(BUILTIN_BUNDLE).unwrap();
+        bundle.version = format!("{builtin_version}-loaded");
+        let path = std::env::temp_dir().join(format!("eew-renderer-bundle-{}", std::process::id()));
+        std::fs::write(&path, bundle.encode().unwrap()).unwrap();
+
+        // 組み込みのバンドルへの問い合わせの後では、差し替えずに断る
+        let loaded = QueryInterface::load_bundle(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(loaded, Err(LoadBundleError::TooLate)));
+        assert_eq!(QueryInterface::bundle_version(), builtin_version);
     }
 }
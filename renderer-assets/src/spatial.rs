@@ -0,0 +1,74 @@
+use renderer_types::{GeoDegree, Vertex};
+
+/// 緯度1度あたりのおおよその距離(km)
+const KM_PER_DEGREE: f32 = 111.32;
+
+/// 偶奇規則で、点がリングの内側にあるかを判定する。
+pub(crate) fn ring_contains(ring: &[(f32, f32)], point: Vertex<GeoDegree>) -> bool {
+    let (x, y) = (point.x(), point.y());
+
+    ring.iter()
+        .zip(ring.iter().cycle().skip(1))
+        .filter(|((x1, y1), (x2, y2))| {
+            (*y1 > y) != (*y2 > y) && x < (x2 - x1) * (y - y1) / (y2 - y1) + x1
+        })
+        .count()
+        % 2
+        == 1
+}
+
+/// 点からリングの辺までの最短距離をkm単位で近似して返す。
+/// 経度方向は点の緯度に応じて縮めた平面上で測る。
+pub(crate) fn distance_to_ring(ring: &[(f32, f32)], point: Vertex<GeoDegree>) -> f32 {
+    let x_scale = point.y().to_radians().cos();
+    let to_plane = |(x, y): (f32, f32)| ((x - point.x()) * x_scale, y - point.y());
+
+    ring.iter()
+        .zip(ring.iter().cycle().skip(1))
+        .map(|(a, b)| distance_to_segment(to_plane(*a), to_plane(*b)))
+        .fold(f32::INFINITY, f32::min)
+        * KM_PER_DEGREE
+}
+
+/// 原点から線分abまでの距離
+fn distance_to_segment(a: (f32, f32), b: (f32, f32)) -> f32 {
+    let ab = (b.0 - a.0, b.1 - a.1);
+    let length_squared = ab.0 * ab.0 + ab.1 * ab.1;
+
+    let t = if length_squared == 0.0 {
+        0.0
+    } else {
+        (-(a.0 * ab.0 + a.1 * ab.1) / length_squared).clamp(0.0, 1.0)
+    };
+
+    f32::hypot(a.0 + ab.0 * t, a.1 + ab.1 * t)
+}
+
+/// cellsだけ離れたセル内の点までの距離の下限をkm単位で返す。
+pub(crate) fn minimum_distance_of_cells(cells: i32, cell_size: f32, latitude: f32) -> f32 {
+    (cells.max(0) as f32) * cell_size * latitude.to_radians().cos() * KM_PER_DEGREE
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::spatial::{distance_to_ring, ring_contains};
+    use renderer_types::Vertex;
+
+    const SQUARE: [(f32, f32); 4] = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+
+    #[test]
+    fn test_ring_contains() {
+        assert!(ring_contains(&SQUARE, Vertex::new(0.5, 0.5)));
+        assert!(!ring_contains(&SQUARE, Vertex::new(1.5, 0.5)));
+        assert!(!ring_contains(&SQUARE, Vertex::new(0.5, -0.5)));
+    }
+
+    #[test]
+    fn test_distance_to_ring() {
+        let distance = distance_to_ring(&SQUARE, Vertex::new(0.5, -1.0));
+        assert!((distance - 111.32).abs() < 0.01);
+
+        let distance = distance_to_ring(&SQUARE, Vertex::new(0.5, 0.5));
+        assert!((distance - 0.5 * 111.32).abs() < 0.1);
+    }
+}
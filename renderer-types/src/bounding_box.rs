@@ -1,7 +1,8 @@
 use crate::{CoordType, Size, Vertex};
-use num_traits::{Bounded, Float, Zero};
-use std::ops::Div;
+use num_traits::{Bounded, Float, NumCast, One, Zero};
 
+/// x軸が周期的な座標系(経緯度)では、min.xから東へmax.xまでの範囲を表す。
+/// 日付変更線をまたぐ範囲ではmax.xが180度を超える。
 #[derive(PartialEq, Debug, Clone, Copy)]
 pub struct BoundingBox<Type: CoordType> {
     pub min: Vertex<Type>,
@@ -29,14 +30,17 @@ impl<Type: CoordType> BoundingBox<Type> {
         Vertex::new(self.max.x(), self.min.y())
     }
 
-    /// まって、これ原点またいだとき、どうなるの？
+    /// 周期的な座標系では、中心のxを-周期/2以上周期/2未満に収めて返す。
     pub fn center(&self) -> Vertex<Type>
     where
-        Type::InnerType: Div<f32, Output = Type::InnerType>,
+        Type::InnerType: Float,
     {
+        let two = Type::InnerType::one() + Type::InnerType::one();
+        let x = (self.min.x() + self.max.x()) / two;
+
         Vertex::new(
-            (self.min.x() + self.max.x()) / 2.0,
-            (self.min.y() + self.max.y()) / 2.0,
+            period::<Type>().map_or(x, |p| wrap(x, p)),
+            (self.min.y() + self.max.y()) / two,
         )
     }
 
@@ -56,19 +60,26 @@ impl<Type: CoordType> BoundingBox<Type> {
         }
     }
 
+    /// 周期的な座標系では、両方を覆う範囲のうちxの幅が短い方を選ぶ。
     pub fn merge_float(&self, other: &Self) -> Self
     where
         Type::InnerType: Float,
     {
-        Self {
-            min: Vertex::new(
-                Float::min(self.min.x(), other.min.x()),
-                Float::min(self.min.y(), other.min.y()),
+        let (min_x, max_x) = match period::<Type>() {
+            Some(p) => merge_periodic(
+                (self.min.x(), self.max.x()),
+                (other.min.x(), other.max.x()),
+                p,
             ),
-            max: Vertex::new(
+            None => (
+                Float::min(self.min.x(), other.min.x()),
                 Float::max(self.max.x(), other.max.x()),
-                Float::max(self.max.y(), other.max.y()),
             ),
+        };
+
+        Self {
+            min: Vertex::new(min_x, Float::min(self.min.y(), other.min.y())),
+            max: Vertex::new(max_x, Float::max(self.max.y(), other.max.y())),
         }
     }
 
@@ -92,16 +103,23 @@ impl<Type: CoordType> BoundingBox<Type> {
     where
         Type::InnerType: Float,
     {
-        Self {
-            min: Vertex::new(
-                Float::min(self.min.x(), vertex.x()),
-                Float::min(self.min.y(), vertex.y()),
-            ),
-            max: Vertex::new(
-                Float::max(self.max.x(), vertex.x()),
-                Float::max(self.max.y(), vertex.y()),
-            ),
-        }
+        self.merge_float(&Self::new(*vertex, *vertex))
+    }
+
+    /// 境界上の点も含む。周期的な座標系では、一周ずれた点も同じ点として扱う。
+    pub fn contains(&self, vertex: &Vertex<Type>) -> bool
+    where
+        Type::InnerType: Float,
+    {
+        let x = match period::<Type>() {
+            Some(p) => self.min.x() + rem_euclid(vertex.x() - self.min.x(), p),
+            None => vertex.x(),
+        };
+
+        self.min.x() <= x
+            && x <= self.max.x()
+            && self.min.y() <= vertex.y()
+            && vertex.y() <= self.max.y()
     }
 
     pub const fn gl_vertices(&self) -> [Vertex<Type>; 4] {
@@ -158,6 +176,61 @@ impl<Type: CoordType> BoundingBox<Type> {
     }
 }
 
+fn period<Type: CoordType>() -> Option<Type::InnerType>
+where
+    Type::InnerType: Float,
+{
+    Type::X_PERIOD.map(|p| <Type::InnerType as NumCast>::from(p).unwrap())
+}
+
+fn rem_euclid<T: Float>(x: T, period: T) -> T {
+    let r = x % period;
+    if r < T::zero() {
+        r + period
+    } else {
+        r
+    }
+}
+
+/// -period/2以上period/2未満に収める
+fn wrap<T: Float>(x: T, period: T) -> T {
+    let half = period / (T::one() + T::one());
+    rem_euclid(x + half, period) - half
+}
+
+/// 周期的な軸上の2つの範囲(始点, 終点)を両方覆う、最も短い範囲を返す。
+/// 終点が始点より小さい範囲は空とみなす。
+fn merge_periodic<T: Float>(a: (T, T), b: (T, T), period: T) -> (T, T) {
+    if a.1 < a.0 {
+        return b;
+    }
+    if b.1 < b.0 {
+        return a;
+    }
+
+    // fromの始点から東へ辿ってtoの終点まで覆う範囲
+    let eastward = |from: (T, T), to: (T, T)| {
+        let to_start = from.0 + rem_euclid(to.0 - from.0, period);
+        (from.0, Float::max(from.1, to_start + (to.1 - to.0)))
+    };
+
+    let (x, y) = (eastward(a, b), eastward(b, a));
+    let (min, max) = if x.1 - x.0 <= y.1 - y.0 { x } else { y };
+    let half = period / (T::one() + T::one());
+
+    if max - min >= period {
+        return (-half, half);
+    }
+
+    // 始点が-period/2以上period/2未満に収まるように一周分ずらす
+    let start = if min < -half || half <= min {
+        wrap(min, period)
+    } else {
+        min
+    };
+    (start, max + (start - min))
+}
+
 impl<Type: CoordType> Eq for BoundingBox<Type> where Vertex<Type>: Eq {}
 
 impl<Type: CoordType> From<(Vertex<Type>, Vertex<Type>)> for BoundingBox<Type> {
@@ -184,3 +257,107 @@ impl From<GenericBBox<Point>> for BoundingBox<crate::GeoDegree> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::bounding_box::*;
+    use crate::GeoDegree;
+    use proptest::prelude::*;
+    use rstest::*;
+
+    fn geo_box(min_x: f32, max_x: f32) -> BoundingBox<GeoDegree> {
+        BoundingBox::new(Vertex::new(min_x, 0.0), Vertex::new(max_x, 10.0))
+    }
+
+    /// 浮動小数点の誤差の分だけ広げる
+    fn grow(bbox: BoundingBox<GeoDegree>) -> BoundingBox<GeoDegree> {
+        let margin = Vertex::new(1e-3, 1e-3);
+        BoundingBox::new(bbox.min - margin, bbox.max + margin)
+    }
+
+    /// 始点が-180以上180未満で、幅が0以上180未満の範囲
+    fn arb_box() -> impl Strategy<Value = BoundingBox<GeoDegree>> {
+        (-180.0f32..180.0, 0.0f32..180.0).prop_map(|(min_x, width)| geo_box(min_x, min_x + width))
+    }
+
+    #[rstest]
+    #[case(geo_box(170.0, 175.0), geo_box(-175.0, -170.0), (170.0, 190.0))]
+    #[case(geo_box(-175.0, -170.0), geo_box(170.0, 175.0), (170.0, 190.0))]
+    #[case(geo_box(122.9, 148.9), geo_box(-72.0, -72.0), (122.9, 288.0))]
+    #[case(geo_box(130.0, 140.0), geo_box(135.0, 150.0), (130.0, 150.0))]
+    #[case(geo_box(-180.0, 180.0), geo_box(10.0, 20.0), (-180.0, 180.0))]
+    fn test_merge_picks_shorter_arc(
+        #[case] a: BoundingBox<GeoDegree>,
+        #[case] b: BoundingBox<GeoDegree>,
+        #[case] expected: (f32, f32),
+    ) {
+        let merged = a.merge_float(&b);
+
+        assert!((merged.min.x() - expected.0).abs() < 1e-3, "{merged:?}");
+        assert!((merged.max.x() - expected.1).abs() < 1e-3, "{merged:?}");
+    }
+
+    #[test]
+    fn test_center_across_antimeridian() {
+        let bbox = geo_box(170.0, 175.0).encapsulate_float(&Vertex::new(-165.0, 5.0));
+
+        assert!((bbox.size().x() - 25.0).abs() < 1e-3);
+        assert!((bbox.center().x() - -177.5).abs() < 1e-3, "{bbox:?}");
+        assert!(bbox.contains(&Vertex::new(179.0, 5.0)));
+        assert!(bbox.contains(&Vertex::new(-179.0, 5.0)));
+        assert!(!bbox.contains(&Vertex::new(0.0, 5.0)));
+    }
+
+    #[test]
+    fn test_empty_box_is_identity() {
+        let empty =
+            BoundingBox::<GeoDegree>::new(Vertex::new(180.0, 90.0), Vertex::new(-180.0, -90.0));
+        let merged = empty.merge_float(&geo_box(-175.0, -170.0));
+
+        assert_eq!(merged, geo_box(-175.0, -170.0));
+    }
+
+    proptest! {
+        #[test]
+        fn prop_merge_contains_both(a in arb_box(), b in arb_box()) {
+            let merged = grow(a.merge_float(&b));
+
+            for bbox in [a, b] {
+                for vertex in bbox.gl_vertices() {
+                    prop_assert!(merged.contains(&vertex), "{merged:?} {vertex:?}");
+                }
+            }
+        }
+
+        #[test]
+        fn prop_merge_is_shortest(a in arb_box(), b in arb_box()) {
+            let width = a.merge_float(&b).size().x();
+
+            // 両方を含む範囲は、どちらかの始点から東へ辿るものに限られる
+            prop_assert!((width - b.merge_float(&a).size().x()).abs() < 1e-3);
+            prop_assert!(width <= 360.0);
+            prop_assert!(width >= a.size().x().max(b.size().x()) - 1e-3);
+        }
+
+        #[test]
+        fn prop_merge_ignores_full_turns(a in arb_box(), b in arb_box(), turns in -2i32..=2) {
+            let shift = Vertex::new(360.0 * turns as f32, 0.0);
+            let shifted = BoundingBox::new(b.min + shift, b.max + shift);
+
+            let expected = a.merge_float(&b);
+            let merged = a.merge_float(&shifted);
+
+            prop_assert!((merged.size().x() - expected.size().x()).abs() < 1e-2);
+            prop_assert!(grow(merged).contains(&expected.center()));
+        }
+
+        #[test]
+        fn prop_center_is_normalized(a in arb_box(), b in arb_box()) {
+            let merged = a.merge_float(&b);
+            let center = merged.center();
+
+            prop_assert!((-180.0..180.0).contains(&center.x()), "{center:?}");
+            prop_assert!(grow(merged).contains(&center));
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+
+use bincode::{Decode, Encode};
+use thiserror::Error;
+
+/// アセットバンドルの先頭に置くマジックナンバー
+pub const ASSET_BUNDLE_MAGIC: [u8; 8] = *b"EEWASSET";
+
+/// バンドルの構造を変えたら上げる
+pub const ASSET_BUNDLE_FORMAT_VERSION: u32 = 10;
+
+const HEADER_LEN: usize = ASSET_BUNDLE_MAGIC.len() + size_of::<u32>();
+
+#[derive(Error, Debug)]
+pub enum BundleError {
+    #[error("Not an asset bundle")]
+    InvalidMagic,
+
+    #[error(
+        "Unsupported asset bundle format version: {0} (expected {ASSET_BUNDLE_FORMAT_VERSION})"
+    )]
+    UnsupportedFormatVersion(u32),
+
+    #[error("Failed to decode asset bundle: {0}")]
+    Decode(#[from] bincode::error::DecodeError),
+
+    #[error("Failed to encode asset bundle: {0}")]
+    Encode(#[from] bincode::error::EncodeError),
+}
+
+/// asset-preprocessorが書き出し、renderer-assetsが起動時に読み込む描画用データ一式
+#[derive(Encode, Decode, Debug)]
+#[allow(clippy::type_complexity)]
+pub struct AssetBundle {
+    /// バンドルの内容を識別するための任意の文字列
+    pub version: String,
+
+    pub intensity_station_positions: Vec<(f32, f32)>,
+    /// <AreaCode, (StationIndex, (BBox))>
+    pub areas: HashMap<u32, (usize, (f32, f32, f32, f32))>,
+    /// <StationCode, StationIndex>
+    pub station_codes: HashMap<u32, usize>,
+
+    pub vertices: Vec<(f32, f32)>,
+    /// LODごとの陸地の三角形。湖などの穴は含まない。
+    pub map_triangles: Vec<Vec<u32>>,
+    pub area_lines: Vec<Vec<u32>>,
+    pub pref_lines: Vec<Vec<u32>>,
+    /// (MinimumScale, LodLevel) 拡大率の高い順
+    pub scale_level_map: Vec<(f32, usize)>,
+    /// 番号の無い北方領土なども含む、細分区域のshapefileの全てのポリゴンを囲む範囲 (BBox)
+    pub map_extent: (f32, f32, f32, f32),
+
+    /// (Name, Lon, Lat, Weight)
+    pub pref_labels: Vec<(String, f32, f32, f32)>,
+    /// (AreaCode, Lon, Lat, Weight)
+    pub area_labels: Vec<(u32, f32, f32, f32)>,
+    pub area_names: HashMap<u32, String>,
+    pub tsunami_area_names: HashMap<u32, String>,
+    /// <PrefCode, Name>
+    pub prefecture_names: HashMap<u32, String>,
+    /// ローマ字の名前。用意されていないものは含まない。
+    pub romanized_area_names: HashMap<u32, String>,
+    pub romanized_tsunami_area_names: HashMap<u32, String>,
+    pub romanized_prefecture_names: HashMap<u32, String>,
+    /// <AreaCode, PrefCode>
+    pub area_prefectures: HashMap<u32, u32>,
+
+    pub area_ring_vertices: Vec<(f32, f32)>,
+    /// (AreaCode, Start, Len, (BBox))
+    pub area_rings: Vec<(u32, u32, u32, (f32, f32, f32, f32))>,
+    /// <GridCell::key(), [RingIndex]>
+    pub area_grid: HashMap<u64, Vec<u32>>,
+
+    pub tsunami_vertices: Vec<(f32, f32, u16)>,
+    pub tsunami_indices: Vec<u32>,
+    pub tsunami_area_code_to_internal_code: HashMap<u32, u16>,
+    /// <TsunamiAreaCode, (BBox)>
+    pub tsunami_area_bounding_boxes: HashMap<u32, (f32, f32, f32, f32)>,
+    /// 線として見えないほど小さい津波予報区を印で描く位置
+    /// <TsunamiAreaCode, (Lon, Lat)>
+    pub tsunami_area_markers: HashMap<u32, (f32, f32)>,
+
+    pub world_vertices: Vec<(f32, f32)>,
+    /// LODごとの世界の陸地の三角形。日本の範囲に収まる陸地は含まない。
+    pub world_triangles: Vec<Vec<u32>>,
+}
+
+impl AssetBundle {
+    /// マジックナンバーとフォーマットのバージョンを付けてバイト列にする。
+    pub fn encode(&self) -> Result<Vec<u8>, BundleError> {
+        let mut bytes = Vec::from(ASSET_BUNDLE_MAGIC);
+        bytes.extend_from_slice(&ASSET_BUNDLE_FORMAT_VERSION.to_le_bytes());
+        bincode::encode_into_std_write(self, &mut bytes, bincode::config::standard())?;
+        Ok(bytes)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, BundleError> {
+        if bytes.len() < HEADER_LEN || bytes[..ASSET_BUNDLE_MAGIC.len()] != ASSET_BUNDLE_MAGIC {
+            return Err(BundleError::InvalidMagic);
+        }
+
+        let format_version = u32::from_le_bytes(
+            bytes[ASSET_BUNDLE_MAGIC.len()..HEADER_LEN]
+                .try_into()
+                .unwrap(),
+        );
+        if format_version != ASSET_BUNDLE_FORMAT_VERSION {
+            return Err(BundleError::UnsupportedFormatVersion(format_version));
+        }
+
+        let (bundle, _) =
+            bincode::decode_from_slice(&bytes[HEADER_LEN..], bincode::config::standard())?;
+        Ok(bundle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bundle::*;
+
+    fn sample_bundle() -> AssetBundle {
+        AssetBundle {
+            version: "test".to_string(),
+            intensity_station_positions: vec![(135.0, 35.0)],
+            areas: HashMap::from([(100, (0, (134.0, 34.0, 136.0, 36.0)))]),
+            station_codes: HashMap::new(),
+            vertices: vec![],
+            map_triangles: vec![],
+            area_lines: vec![vec![0, 1, 2]],
+            pref_lines: vec![],
+            scale_level_map: vec![(1.0, 0)],
+            map_extent: (134.0, 34.0, 136.0, 36.0),
+            pref_labels: vec![],
+            area_labels: vec![],
+            area_names: HashMap::from([(100, "石狩地方北部".to_string())]),
+            tsunami_area_names: HashMap::new(),
+            prefecture_names: HashMap::new(),
+            romanized_area_names: HashMap::from([(100, "Ishikari Chiho Hokubu".to_string())]),
+            romanized_tsunami_area_names: HashMap::new(),
+            romanized_prefecture_names: HashMap::new(),
+            area_prefectures: HashMap::new(),
+            area_ring_vertices: vec![],
+            area_rings: vec![],
+            area_grid: HashMap::new(),
+            tsunami_vertices: vec![],
+            tsunami_indices: vec![],
+            tsunami_area_code_to_internal_code: HashMap::new(),
+            tsunami_area_bounding_boxes: HashMap::new(),
+            tsunami_area_markers: HashMap::new(),
+            world_vertices: vec![],
+            world_triangles: vec![],
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let bytes = sample_bundle().encode().unwrap();
+        let bundle = AssetBundle::decode(&bytes).unwrap();
+
+        assert_eq!(bundle.version, "test");
+        assert_eq!(bundle.area_lines, vec![vec![0, 1, 2]]);
+        assert_eq!(bundle.area_names[&100], "石狩地方北部");
+        assert_eq!(bundle.romanized_area_names[&100], "Ishikari Chiho Hokubu");
+    }
+
+    #[test]
+    fn test_rejects_unknown_header() {
+        assert!(matches!(
+            AssetBundle::decode(b"NOTASSET\x01\x00\x00\x00"),
+            Err(BundleError::InvalidMagic)
+        ));
+
+        let mut bytes = sample_bundle().encode().unwrap();
+        bytes[ASSET_BUNDLE_MAGIC.len()] = 0xff;
+        assert!(matches!(
+            AssetBundle::decode(&bytes),
+            Err(BundleError::UnsupportedFormatVersion(0xff))
+        ));
+    }
+}
@@ -3,6 +3,9 @@ use std::fmt::Debug;
 
 pub trait CoordType: Clone + Copy + Eq + PartialEq + Debug {
     type InnerType: NumOps + PartialEq + Copy + Clone + Debug;
+
+    /// x軸が周期的な座標系ならその周期。経度は一周すると同じ位置に戻る。
+    const X_PERIOD: Option<f64> = None;
 }
 
 /// 度数法での経緯度
@@ -10,6 +13,7 @@ pub trait CoordType: Clone + Copy + Eq + PartialEq + Debug {
 pub struct GeoDegree;
 impl CoordType for GeoDegree {
     type InnerType = f32;
+    const X_PERIOD: Option<f64> = Some(360.0);
 }
 
 /// 弧度法での経緯度
@@ -17,12 +21,13 @@ impl CoordType for GeoDegree {
 pub struct GeoRadian;
 impl CoordType for GeoRadian {
     type InnerType = f32;
+    const X_PERIOD: Option<f64> = Some(std::f64::consts::TAU);
 }
 
-/// 経緯度に楕円体補正をしてメルカトル図法に直した座標
+/// 経緯度をProjectionで地図平面に投影した座標
 #[derive(Clone, Copy, Eq, PartialEq, Debug)]
-pub struct Mercator;
-impl CoordType for Mercator {
+pub struct Projected;
+impl CoordType for Projected {
     type InnerType = f32;
 }
 
@@ -0,0 +1,84 @@
+//! 地図の描画範囲の基準になる範囲。
+
+use crate::{BoundingBox, GeoDegree, Vertex};
+
+/// 日本の主な陸地と沿岸の津波予報区を含む範囲。日本全体を描くときに使う。
+/// 遠地地震かどうかは、南鳥島などの離島も含むdomesticで判定する。
+pub const JAPAN: BoundingBox<GeoDegree> =
+    BoundingBox::new(Vertex::new(122.9, 24.0), Vertex::new(148.9, 45.5));
+
+/// 本土から離れた島々。本土と同時に描くと全体が縮んでしまうので、差し込み図に分けて描く。
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct OutlyingIslands {
+    pub name: &'static str,
+    pub extent: BoundingBox<GeoDegree>,
+}
+
+pub const OUTLYING_ISLANDS: [OutlyingIslands; 3] = [
+    OutlyingIslands {
+        name: "小笠原",
+        extent: BoundingBox::new(Vertex::new(140.0, 24.0), Vertex::new(154.5, 28.0)),
+    },
+    OutlyingIslands {
+        name: "沖縄",
+        extent: BoundingBox::new(Vertex::new(122.5, 23.8), Vertex::new(128.5, 27.9)),
+    },
+    OutlyingIslands {
+        name: "大東",
+        extent: BoundingBox::new(Vertex::new(130.8, 24.3), Vertex::new(131.5, 26.2)),
+    },
+];
+
+/// 震央がこの範囲の外にあれば遠地地震として、世界の陸地を加えた広域図にする。
+/// map_extentはバンドルの細分区域全体を囲む範囲で、それに離島の範囲を合わせる。
+pub fn domestic(map_extent: BoundingBox<GeoDegree>) -> BoundingBox<GeoDegree> {
+    OUTLYING_ISLANDS.iter().fold(map_extent, |extent, islands| {
+        extent.merge_float(&islands.extent)
+    })
+}
+
+/// 位置が含まれる離島を返す。本土側であればNone。
+pub fn outlying_islands_of(position: &Vertex<GeoDegree>) -> Option<&'static OutlyingIslands> {
+    OUTLYING_ISLANDS
+        .iter()
+        .find(|islands| islands.extent.contains(position))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::extent::*;
+
+    #[test]
+    fn test_japan_contains_main_islands() {
+        assert!(JAPAN.contains(&Vertex::new(139.7, 35.7)));
+        assert!(JAPAN.contains(&Vertex::new(127.7, 26.2)));
+        assert!(!JAPAN.contains(&Vertex::new(-72.0, -35.0)));
+        assert!(!JAPAN.contains(&Vertex::new(160.0, 53.0)));
+    }
+
+    #[test]
+    fn test_domestic_contains_remote_islands() {
+        let map_extent = BoundingBox::new(Vertex::new(122.9, 20.4), Vertex::new(148.9, 45.6));
+        let domestic = domestic(map_extent);
+
+        // 択捉島、南鳥島、沖ノ鳥島
+        assert!(domestic.contains(&Vertex::new(148.8, 45.5)));
+        assert!(domestic.contains(&Vertex::new(153.98, 24.28)));
+        assert!(domestic.contains(&Vertex::new(136.08, 20.42)));
+        assert!(!domestic.contains(&Vertex::new(160.0, 53.0)));
+    }
+
+    #[test]
+    fn test_outlying_islands_of() {
+        let name = |x, y| outlying_islands_of(&Vertex::new(x, y)).map(|islands| islands.name);
+
+        // 父島、那覇、南大東島
+        assert_eq!(name(142.2, 27.1), Some("小笠原"));
+        assert_eq!(name(127.7, 26.2), Some("沖縄"));
+        assert_eq!(name(131.2, 25.8), Some("大東"));
+        // 東京、八丈島、奄美大島は本土側
+        assert_eq!(name(139.7, 35.7), None);
+        assert_eq!(name(139.8, 33.1), None);
+        assert_eq!(name(129.5, 28.4), None);
+    }
+}
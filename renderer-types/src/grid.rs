@@ -0,0 +1,93 @@
+use crate::{BoundingBox, GeoDegree, Vertex};
+
+/// 経緯度を一辺`GridCell::SIZE`度の格子で区切ったときのセル。
+/// アセット生成時の空間索引と、実行時の検索とで同じ区切り方を使うためのもの。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GridCell {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl GridCell {
+    pub const SIZE: f32 = 0.5;
+
+    pub const fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+
+    pub fn of(vertex: Vertex<GeoDegree>) -> Self {
+        Self::new(
+            (vertex.x() / Self::SIZE).floor() as i32,
+            (vertex.y() / Self::SIZE).floor() as i32,
+        )
+    }
+
+    /// bounding_boxと重なるセルを全て返す。
+    pub fn covering(bounding_box: &BoundingBox<GeoDegree>) -> impl Iterator<Item = Self> {
+        let min = Self::of(bounding_box.min);
+        let max = Self::of(bounding_box.max);
+
+        (min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| Self::new(x, y)))
+    }
+
+    /// 自身からチェビシェフ距離でちょうどradius離れたセルを返す。
+    pub fn ring(&self, radius: i32) -> Vec<Self> {
+        if radius == 0 {
+            return vec![*self];
+        }
+
+        (-radius..=radius)
+            .flat_map(|dy| (-radius..=radius).map(move |dx| (dx, dy)))
+            .filter(|(dx, dy)| dx.abs() == radius || dy.abs() == radius)
+            .map(|(dx, dy)| Self::new(self.x + dx, self.y + dy))
+            .collect()
+    }
+
+    /// phfのキーとして使うための値
+    pub const fn key(&self) -> u64 {
+        ((self.x as u32 as u64) << 32) | self.y as u32 as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BoundingBox, GeoDegree, GridCell, Vertex};
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(Vertex::new(139.75, 35.68), GridCell::new(279, 71))]
+    #[case(Vertex::new(140.0, 35.5), GridCell::new(280, 71))]
+    #[case(Vertex::new(-0.1, -0.1), GridCell::new(-1, -1))]
+    fn cell_of(#[case] vertex: Vertex<GeoDegree>, #[case] cell: GridCell) {
+        assert_eq!(GridCell::of(vertex), cell);
+    }
+
+    #[test]
+    fn covering() {
+        let bounding_box = BoundingBox::new(Vertex::new(139.2, 35.1), Vertex::new(140.3, 35.6));
+        let cells: Vec<_> = GridCell::covering(&bounding_box).collect();
+
+        assert_eq!(cells.len(), 3 * 2);
+        assert!(cells.contains(&GridCell::new(278, 70)));
+        assert!(cells.contains(&GridCell::new(280, 71)));
+    }
+
+    #[rstest]
+    #[case(0, 1)]
+    #[case(1, 8)]
+    #[case(2, 16)]
+    fn ring(#[case] radius: i32, #[case] count: usize) {
+        let ring = GridCell::new(0, 0).ring(radius);
+
+        assert_eq!(ring.len(), count);
+        assert!(ring
+            .iter()
+            .all(|cell| cell.x.abs().max(cell.y.abs()) == radius));
+    }
+
+    #[test]
+    fn key_is_unique_for_negative_cells() {
+        assert_ne!(GridCell::new(-1, 0).key(), GridCell::new(0, -1).key());
+        assert_ne!(GridCell::new(1, 2).key(), GridCell::new(2, 1).key());
+    }
+}
@@ -12,4 +12,16 @@ pub use vertex::Vertex;
 mod coord_types;
 pub use coord_types::*;
 
+mod grid;
+pub use grid::GridCell;
+
+pub mod extent;
+
+pub mod lod;
+
+pub mod projection;
+
 pub mod codes;
+
+#[cfg(feature = "bundle")]
+pub mod bundle;
@@ -0,0 +1,170 @@
+//! 描画時の拡大率と、アセットの簡略化の度合い(LOD)を対応付けるための定義。
+//! asset-preprocessorとrendererの両方から使う。
+
+use crate::projection::Projection;
+use crate::{BoundingBox, GeoDegree, Vertex};
+
+/// 出力する画像の大きさ
+pub const DIMENSION: (u32, u32) = (1024, 768);
+
+/// 地図の拡大率の上限
+pub const MAXIMUM_SCALE: f32 = 100.0;
+
+/// 描画範囲の周囲に取る余白の比
+pub const SCALE_FACTOR: f32 = 1.2;
+
+/// これより小さい拡大率では最も粗いLODを使う (世界全体が収まる程度)
+pub const MINIMUM_SCALE: f32 = 1.0;
+
+/// 簡略化による誤差を画面上でこのピクセル数以内に収める
+pub const SCREEN_SPACE_ERROR: f32 = 0.5;
+
+/// 島などの小さな面は、簡略化しても最低限このピクセル数四方の大きさを保つ
+pub const MINIMUM_VISIBLE_SIZE: f32 = 2.0;
+
+/// 隣り合うLODの間の拡大率の比
+const LEVEL_SCALE_RATIO: f32 = 1.5;
+
+/// LODで描く範囲。細分区域のある沖ノ鳥島から択捉島、与那国島から南鳥島まで
+const LOD_EXTENT: BoundingBox<GeoDegree> =
+    BoundingBox::new(Vertex::new(122.0, 20.0), Vertex::new(154.0, 46.0));
+
+/// LOD_EXTENTで地図の拡大のされ方を調べる、1辺あたりの分割数
+const LOD_EXTENT_SUBDIVISION: usize = 16;
+
+/// 地図の拡大のされ方を調べるときに進める経緯度(度)
+const DIFFERENTIAL_DEGREE: f32 = 0.1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LodLevel {
+    /// 拡大率がこれ以上のときにこのLODを使う
+    pub minimum_scale: f32,
+    /// 簡略化で許す誤差(度)
+    pub tolerance: f32,
+    /// このLODで見える大きさとして保つ面積(平方度)
+    pub minimum_area: f32,
+}
+
+/// 実際に描画に使われる拡大率の上限
+pub fn maximum_effective_scale() -> f32 {
+    MAXIMUM_SCALE / SCALE_FACTOR
+}
+
+/// 拡大率scaleで描画したときの、経緯度1度あたりのおおよそのピクセル数。
+/// LODはアセットを作るときに決まり、どの投影法でも同じものを使うので、最も拡大される場合で見積もる。
+pub fn pixels_per_degree(scale: f32) -> f32 {
+    // スクリーン座標の1が画像の幅の半分になる
+    scale * DIMENSION.0 as f32 / 2.0 * maximum_projected_length_per_degree()
+}
+
+/// 全ての投影法について、LOD_EXTENTの中で経度か緯度を1度進めたときの地図平面上の長さの最大値。
+/// 投影の原点は、その点を描画範囲の中心としたときのものを使う。
+fn maximum_projected_length_per_degree() -> f32 {
+    let (min, max) = (LOD_EXTENT.min, LOD_EXTENT.max);
+    let t = |i: usize| i as f32 / LOD_EXTENT_SUBDIVISION as f32;
+
+    let vertices = (0..=LOD_EXTENT_SUBDIVISION).flat_map(|i| {
+        (0..=LOD_EXTENT_SUBDIVISION).map(move |j| {
+            Vertex::<GeoDegree>::new(
+                min.x() + (max.x() - min.x()) * t(i),
+                min.y() + (max.y() - min.y()) * t(j),
+            )
+        })
+    });
+
+    vertices
+        .flat_map(|vertex| {
+            Projection::ALL.into_iter().flat_map(move |projection| {
+                let origin = projection.origin(vertex);
+                let projected = projection.project(vertex, origin);
+
+                [
+                    Vertex::new(vertex.x() + DIFFERENTIAL_DEGREE, vertex.y()),
+                    Vertex::new(vertex.x(), vertex.y() + DIFFERENTIAL_DEGREE),
+                ]
+                .map(|moved| {
+                    let moved = projection.project(moved, origin);
+                    f32::hypot(moved.x() - projected.x(), moved.y() - projected.y())
+                        / DIFFERENTIAL_DEGREE
+                })
+            })
+        })
+        .fold(0.0, f32::max)
+}
+
+/// 画面上の誤差がscreen_space_error以内に収まるよう、拡大率の高い順にLODを並べる。
+/// 最後のLODはMINIMUM_SCALE未満を含む全ての拡大率を受け持つ。
+pub fn levels(screen_space_error: f32) -> Vec<LodLevel> {
+    let mut levels = Vec::new();
+    let mut upper_scale = maximum_effective_scale();
+
+    loop {
+        // このLODを使う拡大率の上限で誤差が収まるようにする
+        let tolerance = screen_space_error / pixels_per_degree(upper_scale);
+        let lower_scale = upper_scale / LEVEL_SCALE_RATIO;
+        // このLODを使う拡大率の下限でも見えるようにする
+        let minimum_area =
+            (MINIMUM_VISIBLE_SIZE / pixels_per_degree(lower_scale.max(MINIMUM_SCALE))).powi(2);
+
+        if lower_scale <= MINIMUM_SCALE {
+            levels.push(LodLevel {
+                minimum_scale: 0.0,
+                tolerance,
+                minimum_area,
+            });
+            return levels;
+        }
+
+        levels.push(LodLevel {
+            minimum_scale: lower_scale,
+            tolerance,
+            minimum_area,
+        });
+        upper_scale = lower_scale;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lod::*;
+    use rstest::*;
+
+    #[test]
+    fn test_levels_are_ordered() {
+        let levels = levels(SCREEN_SPACE_ERROR);
+
+        assert!(levels.len() > 1);
+        assert!(levels
+            .windows(2)
+            .all(|w| w[0].minimum_scale > w[1].minimum_scale
+                && w[0].tolerance < w[1].tolerance
+                && w[0].minimum_area < w[1].minimum_area));
+        assert_eq!(levels.last().unwrap().minimum_scale, 0.0);
+    }
+
+    #[test]
+    fn test_mercator_north_is_covered() {
+        // メルカトル図法では、日本の北端付近で緯度方向に最も拡大される
+        let mercator = DIMENSION.0 as f32 / 360.0 / 46.0_f32.to_radians().cos();
+        assert!(
+            pixels_per_degree(1.0) >= mercator * 0.99,
+            "{}",
+            pixels_per_degree(1.0)
+        );
+    }
+
+    #[rstest]
+    #[case(maximum_effective_scale())]
+    #[case(20.0)]
+    #[case(3.0)]
+    #[case(MINIMUM_SCALE)]
+    fn test_error_within_target(#[case] scale: f32) {
+        let level = levels(SCREEN_SPACE_ERROR)
+            .into_iter()
+            .find(|level| level.minimum_scale <= scale)
+            .unwrap();
+
+        let error = level.tolerance * pixels_per_degree(scale);
+        assert!(error <= SCREEN_SPACE_ERROR * 1.0001, "{error}");
+    }
+}
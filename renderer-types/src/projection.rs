@@ -0,0 +1,241 @@
+//! 経緯度から地図平面への投影法。
+//! シェーダー(assets/shader/projection.glsl)も同じ式で投影するので、式を変えるときは両方を直すこと。
+//! いずれの投影法も、経度180度が地図平面の1になるおおよその縮尺に揃えてある。
+
+use crate::{BoundingBox, GeoDegree, Projected, Vertex};
+use std::f32::consts::{FRAC_PI_4, PI};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// GRS80楕円体の離心率
+const E: f32 = 0.081819191042815791;
+
+/// ランベルト正角円錐図法の標準緯線
+const CONIC_STANDARD_PARALLELS: (f32, f32) = (30.0, 45.0);
+
+/// ランベルト正角円錐図法の原点。日本全体が歪みにくいように置く。
+const CONIC_ORIGIN: Vertex<GeoDegree> = Vertex::new(137.0, 37.0);
+
+/// 描画範囲の外周を投影するときの、1辺あたりの分割数
+const BOUNDING_BOX_SUBDIVISION: usize = 16;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Projection {
+    /// 楕円体補正をしたメルカトル図法
+    #[default]
+    Mercator,
+    /// 正距円筒図法
+    Equirectangular,
+    /// 日本向けのランベルト正角円錐図法
+    LambertConformalConic,
+    /// 描画範囲の中心から見た正射図法。遠地地震向け
+    Orthographic,
+}
+
+impl Projection {
+    pub const ALL: [Projection; 4] = [
+        Projection::Mercator,
+        Projection::Equirectangular,
+        Projection::LambertConformalConic,
+        Projection::Orthographic,
+    ];
+
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Projection::Mercator => "mercator",
+            Projection::Equirectangular => "equirectangular",
+            Projection::LambertConformalConic => "lambert_conformal_conic",
+            Projection::Orthographic => "orthographic",
+        }
+    }
+
+    /// シェーダーのprojection uniformに渡す番号
+    pub const fn shader_id(&self) -> i32 {
+        match self {
+            Projection::Mercator => 0,
+            Projection::Equirectangular => 1,
+            Projection::LambertConformalConic => 2,
+            Projection::Orthographic => 3,
+        }
+    }
+
+    /// 地図平面のx方向の周期。円筒図法では経度360度ごとに同じ地図が並ぶ。
+    pub const fn period(&self) -> Option<f32> {
+        match self {
+            Projection::Mercator | Projection::Equirectangular => Some(2.0),
+            Projection::LambertConformalConic | Projection::Orthographic => None,
+        }
+    }
+
+    /// 投影の原点。view_centerは描画範囲の中心。
+    pub fn origin(&self, view_center: Vertex<GeoDegree>) -> Vertex<GeoDegree> {
+        match self {
+            Projection::Mercator | Projection::Equirectangular => Vertex::new(0.0, 0.0),
+            Projection::LambertConformalConic => CONIC_ORIGIN,
+            Projection::Orthographic => view_center,
+        }
+    }
+
+    pub fn project(
+        &self,
+        vertex: Vertex<GeoDegree>,
+        origin: Vertex<GeoDegree>,
+    ) -> Vertex<Projected> {
+        let (lambda, phi) = (vertex.x().to_radians(), vertex.y().to_radians());
+        let (lambda0, phi0) = (origin.x().to_radians(), origin.y().to_radians());
+
+        let (x, y) = match self {
+            Projection::Mercator => (lambda, phi.sin().atanh() - E * (E * phi.sin()).atanh()),
+            Projection::Equirectangular => (lambda, phi),
+            Projection::LambertConformalConic => {
+                let [n, f, rho0] = conic_parameters();
+                let rho = f / (FRAC_PI_4 + phi / 2.0).tan().powf(n);
+                let theta = n * wrap_radian(lambda - lambda0);
+                (rho * theta.sin(), rho0 - rho * theta.cos())
+            }
+            Projection::Orthographic => {
+                let d_lambda = lambda - lambda0;
+                let x = phi.cos() * d_lambda.sin();
+                let y = phi0.cos() * phi.sin() - phi0.sin() * phi.cos() * d_lambda.cos();
+                let cos_c = phi0.sin() * phi.sin() + phi0.cos() * phi.cos() * d_lambda.cos();
+
+                // 裏側の点は地平線上に寄せる
+                if cos_c < 0.0 {
+                    let length = f32::hypot(x, y).max(f32::EPSILON);
+                    (x / length, y / length)
+                } else {
+                    (x, y)
+                }
+            }
+        };
+
+        Vertex::new(x / PI, y / PI)
+    }
+
+    /// 経緯度の矩形を投影し、それを囲む矩形を返す。
+    /// 円錐図法などでは辺が曲がるので、角だけでなく辺の途中の点も投影する。
+    pub fn project_bounding_box(
+        &self,
+        bounding_box: &BoundingBox<GeoDegree>,
+        origin: Vertex<GeoDegree>,
+    ) -> BoundingBox<Projected> {
+        let (min, max) = (bounding_box.min, bounding_box.max);
+
+        let vertices: Vec<_> = (0..=BOUNDING_BOX_SUBDIVISION)
+            .map(|i| i as f32 / BOUNDING_BOX_SUBDIVISION as f32)
+            .flat_map(|t| {
+                let x = min.x() + (max.x() - min.x()) * t;
+                let y = min.y() + (max.y() - min.y()) * t;
+                [
+                    Vertex::new(x, min.y()),
+                    Vertex::new(x, max.y()),
+                    Vertex::new(min.x(), y),
+                    Vertex::new(max.x(), y),
+                ]
+            })
+            .map(|v| self.project(v, origin))
+            .collect();
+
+        BoundingBox::from_vertices_float(&vertices)
+    }
+}
+
+/// ランベルト正角円錐図法の(n, F, ρ0)。シェーダーにもこの値を渡す。
+pub fn conic_parameters() -> [f32; 3] {
+    let (phi1, phi2) = (
+        CONIC_STANDARD_PARALLELS.0.to_radians(),
+        CONIC_STANDARD_PARALLELS.1.to_radians(),
+    );
+    let t = |phi: f32| (FRAC_PI_4 + phi / 2.0).tan();
+
+    let n = (phi1.cos() / phi2.cos()).ln() / (t(phi2) / t(phi1)).ln();
+    let f = phi1.cos() * t(phi1).powf(n) / n;
+    let rho0 = f / t(CONIC_ORIGIN.y().to_radians()).powf(n);
+
+    [n, f, rho0]
+}
+
+/// -π..πに収める
+fn wrap_radian(radian: f32) -> f32 {
+    (radian + PI).rem_euclid(2.0 * PI) - PI
+}
+
+impl Display for Projection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct UnknownProjection(pub String);
+
+impl Display for UnknownProjection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unknown projection: {}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownProjection {}
+
+impl FromStr for Projection {
+    type Err = UnknownProjection;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Projection::ALL
+            .into_iter()
+            .find(|p| p.name() == s)
+            .ok_or_else(|| UnknownProjection(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::projection::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case(Projection::Mercator)]
+    #[case(Projection::Equirectangular)]
+    #[case(Projection::LambertConformalConic)]
+    #[case(Projection::Orthographic)]
+    fn test_name_round_trip(#[case] projection: Projection) {
+        assert_eq!(projection.name().parse(), Ok(projection));
+    }
+
+    #[test]
+    fn test_mercator_keeps_longitude_scale() {
+        let v = Projection::Mercator.project(Vertex::new(90.0, 0.0), Vertex::new(0.0, 0.0));
+        assert!((v.x() - 0.5).abs() < 1e-6);
+        assert!(v.y().abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_conic_origin_is_on_axis() {
+        let projection = Projection::LambertConformalConic;
+        let origin = projection.origin(Vertex::new(0.0, 0.0));
+        let v = projection.project(origin, origin);
+
+        assert!(v.x().abs() < 1e-6 && v.y().abs() < 1e-6, "{v:?}");
+    }
+
+    #[test]
+    fn test_orthographic_far_side_stays_on_horizon() {
+        let origin = Vertex::new(140.0, 35.0);
+        let v = Projection::Orthographic.project(Vertex::new(-30.0, 10.0), origin);
+
+        assert!((f32::hypot(v.x(), v.y()) - 1.0 / PI).abs() < 1e-3, "{v:?}");
+    }
+
+    #[test]
+    fn test_bounding_box_includes_curved_edge() {
+        // 円錐図法では緯線が南に膨らんだ弧になるので、下辺の中央が角より下に来る
+        let projection = Projection::LambertConformalConic;
+        let origin = projection.origin(Vertex::new(0.0, 0.0));
+        let bbox = BoundingBox::new(Vertex::new(122.0, 24.0), Vertex::new(152.0, 46.0));
+
+        let projected = projection.project_bounding_box(&bbox, origin);
+        let corner = projection.project(bbox.min, origin);
+
+        assert!(projected.min.y() < corner.y());
+    }
+}
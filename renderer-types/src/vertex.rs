@@ -1,6 +1,5 @@
-use crate::{CoordType, GeoDegree, GeoRadian, Mercator, Pixel, Screen, Size};
+use crate::{CoordType, GeoDegree, GeoRadian, Pixel, Projected, Screen, Size};
 use num_traits::AsPrimitive;
-use std::f32::consts::PI;
 use std::fmt::Debug;
 
 #[derive(PartialEq, Copy, Clone, Debug)]
@@ -37,10 +36,6 @@ impl Vertex<GeoDegree> {
         let y = self.y.to_radians();
         Vertex::new(x, y)
     }
-
-    pub fn to_mercator(&self) -> Vertex<Mercator> {
-        self.to_geo_radian().to_mercator()
-    }
 }
 
 impl Vertex<GeoRadian> {
@@ -49,17 +44,10 @@ impl Vertex<GeoRadian> {
         let y = self.y.to_degrees();
         Vertex::new(x, y)
     }
-
-    pub fn to_mercator(&self) -> Vertex<Mercator> {
-        const E: f32 = 0.081819191042815791;
-        let x = self.x / PI;
-        let y = (self.y.sin().atanh() - E * (E * self.y.sin()).atanh()) / PI;
-        Vertex::new(x, y)
-    }
 }
 
-impl Vertex<Mercator> {
-    pub fn to_screen(&self, offset: Vertex<Mercator>, scale: f32) -> Vertex<Screen> {
+impl Vertex<Projected> {
+    pub fn to_screen(&self, offset: Vertex<Projected>, scale: f32) -> Vertex<Screen> {
         let vertex = (*self + offset) * scale;
         Vertex::new(vertex.x, vertex.y)
     }
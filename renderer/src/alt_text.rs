@@ -0,0 +1,172 @@
+//! 画像の代替テキスト。画像を見られない利用者向けに、描いた内容を文章にする。
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz::Japan;
+
+use crate::model::{津波情報, 震度};
+use crate::place_names::{self, Script};
+use crate::rendering_context::{EarthquakePayload, RenderingPayload, TsunamiPayload};
+
+pub fn describe(payload: &RenderingPayload, script: Script) -> String {
+    let sentences = match payload {
+        RenderingPayload::Earthquake(earthquake) => describe_earthquake(earthquake, script),
+        RenderingPayload::Tsunami(tsunami) => describe_tsunami(tsunami, script),
+    };
+
+    match script {
+        Script::Japanese => sentences.iter().map(|s| format!("{s}。")).collect(),
+        Script::Romanized => format!("{}.", sentences.join(". ")),
+    }
+}
+
+/// 発生時刻、震央、震度の大きい順に各震度の細分区域を並べる
+fn describe_earthquake(earthquake: &EarthquakePayload, script: Script) -> Vec<String> {
+    let mut sentences = vec![match script {
+        Script::Japanese => format!("{}発生の地震", time_text(earthquake.time, script)),
+        Script::Romanized => format!(
+            "Earthquake occurred around {}",
+            time_text(earthquake.time, script)
+        ),
+    }];
+
+    sentences.extend(
+        earthquake
+            .epicenter
+            .iter()
+            .filter_map(|epicenter| place_names::hypocenter(*epicenter, script))
+            .map(|name| match script {
+                Script::Japanese => format!("震央は{name}"),
+                Script::Romanized => format!("Epicenter: {name}"),
+            }),
+    );
+
+    sentences.extend(
+        earthquake
+            .area_intensities
+            .iter()
+            .rev()
+            .filter(|(_, areas)| !areas.is_empty())
+            .map(|(intensity, areas)| {
+                let names: Vec<_> = areas
+                    .iter()
+                    .map(|area| place_names::area(*area, script))
+                    .collect();
+                format!(
+                    "{}: {}",
+                    intensity_text(intensity, script),
+                    join(&names, script)
+                )
+            }),
+    );
+
+    sentences
+}
+
+/// 発表時刻、重い情報の順に各情報の津波予報区を並べる
+fn describe_tsunami(tsunami: &TsunamiPayload, script: Script) -> Vec<String> {
+    let mut sentences = vec![match script {
+        Script::Japanese => format!("{}発表の津波情報", time_text(tsunami.time, script)),
+        Script::Romanized => format!(
+            "Tsunami information issued around {}",
+            time_text(tsunami.time, script)
+        ),
+    }];
+
+    sentences.extend(
+        tsunami
+            .forecast_levels
+            .iter()
+            .rev()
+            .filter(|(_, areas)| !areas.is_empty())
+            .map(|(level, areas)| {
+                let names: Vec<_> = areas
+                    .iter()
+                    .map(|area| place_names::tsunami_area(*area, script))
+                    .collect();
+                format!("{}: {}", tsunami_text(level, script), join(&names, script))
+            }),
+    );
+
+    sentences
+}
+
+fn time_text(time: DateTime<Utc>, script: Script) -> String {
+    let format = match script {
+        Script::Japanese => "%Y年%m月%d日 %H時%M分頃",
+        Script::Romanized => "%Y-%m-%d %H:%M JST",
+    };
+
+    time.with_timezone(&Japan).format(format).to_string()
+}
+
+fn intensity_text(intensity: 震度, script: Script) -> String {
+    let scale = match intensity {
+        震度::震度1 => "1",
+        震度::震度2 => "2",
+        震度::震度3 => "3",
+        震度::震度4 => "4",
+        震度::震度5弱 => "5-",
+        震度::震度5強 => "5+",
+        震度::震度6弱 => "6-",
+        震度::震度6強 => "6+",
+        震度::震度7 => "7",
+    };
+
+    match script {
+        Script::Japanese => format!("{intensity:?}"),
+        Script::Romanized => format!("Shindo {scale}"),
+    }
+}
+
+fn tsunami_text(level: 津波情報, script: Script) -> String {
+    match script {
+        Script::Japanese => level.to_string(),
+        Script::Romanized => match level {
+            津波情報::津波予報 => "Tsunami Forecast (slight sea level change)",
+            津波情報::津波注意報 => "Tsunami Advisory",
+            津波情報::津波警報 => "Tsunami Warning",
+            津波情報::大津波警報 => "Major Tsunami Warning",
+        }
+        .to_string(),
+    }
+}
+
+fn join(names: &[String], script: Script) -> String {
+    match script {
+        Script::Japanese => names.join("、"),
+        Script::Romanized => names.join(", "),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::alt_text::*;
+    use enum_map::EnumMap;
+    use renderer_types::codes;
+
+    #[test]
+    fn test_describe_earthquake() {
+        // 存在しない区域はコードで表される
+        let mut area_intensities: EnumMap<震度, Vec<codes::地震情報細分区域>> = EnumMap::default();
+        area_intensities[震度::震度3] = vec![codes::地震情報細分区域(999998)];
+        area_intensities[震度::震度5弱] = vec![
+            codes::地震情報細分区域(999999),
+            codes::地震情報細分区域(999997),
+        ];
+
+        let payload = RenderingPayload::Earthquake(EarthquakePayload {
+            time: DateTime::from_timestamp(1704093060, 0).unwrap(),
+            epicenter: vec![],
+            area_intensities,
+        });
+
+        assert_eq!(
+            describe(&payload, Script::Japanese),
+            "2024年01月01日 16時11分頃発生の地震。震度5弱: #999999、#999997。震度3: #999998。"
+        );
+        assert_eq!(
+            describe(&payload, Script::Romanized),
+            "Earthquake occurred around 2024-01-01 16:11 JST. Shindo 5-: #999999, #999997. Shindo 3: #999998."
+        );
+    }
+}
@@ -2,6 +2,7 @@ use crate::model::{津波情報, 震度, TimeKind};
 use chrono::{DateTime, Utc};
 use enum_map::EnumMap;
 use renderer_types::codes;
+use renderer_types::projection::Projection;
 use renderer_types::{GeoDegree, Vertex};
 
 pub trait HasEpicenter {
@@ -17,13 +18,47 @@ pub trait HasTsunamiForecastLevels {
     fn forecast_levels(&self) -> &EnumMap<津波情報, Vec<codes::津波予報区>>;
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct EarthquakePayload {
     pub time: DateTime<Utc>,
     pub epicenter: Vec<Vertex<GeoDegree>>,
     pub area_intensities: EnumMap<震度, Vec<codes::地震情報細分区域>>,
 }
 
+impl EarthquakePayload {
+    /// 条件を満たす位置にある震央と地域だけを残したものを作る。
+    /// 地域の位置は震度アイコンを描く位置で判定する。
+    pub fn filter(&self, predicate: impl Fn(&Vertex<GeoDegree>) -> bool) -> Self {
+        let epicenter = self
+            .epicenter
+            .iter()
+            .filter(|epicenter| predicate(epicenter))
+            .copied()
+            .collect();
+
+        let area_intensities = EnumMap::from_fn(|intensity| {
+            self.area_intensities[intensity]
+                .iter()
+                .filter(|code| {
+                    renderer_assets::QueryInterface::query_rendering_center_by_area(**code)
+                        .is_some_and(|center| predicate(&center))
+                })
+                .copied()
+                .collect()
+        });
+
+        Self {
+            time: self.time,
+            epicenter,
+            area_intensities,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.epicenter.is_empty() && self.area_intensities.values().all(Vec::is_empty)
+    }
+}
+
 impl HasTime for EarthquakePayload {
     fn time(&self) -> DateTime<Utc> {
         self.time
@@ -40,14 +75,18 @@ impl HasEpicenter for EarthquakePayload {
     }
 }
 
-#[derive(Debug)]
-pub struct TsunamiFirstPayload {
+#[derive(Clone, Debug)]
+pub struct TsunamiPayload {
     pub time: DateTime<Utc>,
     pub epicenter: Vec<Vertex<GeoDegree>>,
     pub forecast_levels: EnumMap<津波情報, Vec<codes::津波予報区>>,
+    /// 点滅アニメーションの何フレーム目か。Noneなら静止画として全ての線を描く
+    pub blink_frame: Option<usize>,
+    /// 凡例の代わりに、発表されている津波予報区の一覧を描くか
+    pub region_list: bool,
 }
 
-impl HasTime for TsunamiFirstPayload {
+impl HasTime for TsunamiPayload {
     fn time(&self) -> DateTime<Utc> {
         self.time
     }
@@ -57,56 +96,48 @@ impl HasTime for TsunamiFirstPayload {
     }
 }
 
-impl HasEpicenter for TsunamiFirstPayload {
+impl HasEpicenter for TsunamiPayload {
     fn epicenter(&self) -> &[Vertex<GeoDegree>] {
         &self.epicenter
     }
 }
 
-impl HasTsunamiForecastLevels for TsunamiFirstPayload {
+impl HasTsunamiForecastLevels for TsunamiPayload {
     fn forecast_levels(&self) -> &EnumMap<津波情報, Vec<codes::津波予報区>> {
         &self.forecast_levels
     }
 }
 
-#[derive(Debug)]
-pub struct TsunamiSecondPayload {
-    pub time: DateTime<Utc>,
-    pub epicenter: Vec<Vertex<GeoDegree>>,
-    pub forecast_levels: EnumMap<津波情報, Vec<codes::津波予報区>>,
+#[derive(Clone, Debug)]
+pub enum FramePayload {
+    Earthquake(EarthquakePayload),
+    Tsunami(TsunamiPayload),
 }
 
-impl HasTime for TsunamiSecondPayload {
-    fn time(&self) -> DateTime<Utc> {
-        self.time
+impl FramePayload {
+    pub fn epicenter(&self) -> &[Vertex<GeoDegree>] {
+        match self {
+            FramePayload::Earthquake(payload) => payload.epicenter(),
+            FramePayload::Tsunami(payload) => payload.epicenter(),
+        }
     }
 
-    fn time_kind(&self) -> TimeKind {
-        TimeKind::発表
-    }
-}
+    /// 震央が日本の範囲外にある遠地地震か
+    pub fn is_distant(&self) -> bool {
+        let domestic = renderer_assets::QueryInterface::query_domestic_extent();
 
-impl HasEpicenter for TsunamiSecondPayload {
-    fn epicenter(&self) -> &[Vertex<GeoDegree>] {
-        &self.epicenter
+        self.epicenter()
+            .iter()
+            .any(|epicenter| !domestic.contains(epicenter))
     }
 }
 
-impl HasTsunamiForecastLevels for TsunamiSecondPayload {
-    fn forecast_levels(&self) -> &EnumMap<津波情報, Vec<codes::津波予報区>> {
-        &self.forecast_levels
-    }
-}
-
-#[derive(Debug)]
-pub enum FramePayload {
-    Earthquake(EarthquakePayload),
-    TsunamiFirst(TsunamiFirstPayload),
-    TsunamiSecond(TsunamiSecondPayload),
-}
-
 #[derive(Debug)]
 pub struct FrameContext {
     pub payload: FramePayload,
+    /// Noneならテーマの投影法を使う
+    pub projection: Option<Projection>,
+    /// 津波情報を予報区の周辺だけに絞って描く段階。Noneならテーマの設定に従う
+    pub regional_tsunami_below: Option<Option<津波情報>>,
     pub request_identity: String,
 }
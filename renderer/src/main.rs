@@ -2,15 +2,19 @@ pub mod proto {
     include!(concat!(env!("OUT_DIR"), "/net.eewbot.rs"));
 }
 
+mod alt_text;
 mod frame_context;
 mod model;
 mod namesgenerator;
+mod place_names;
 mod rendering_context;
 mod web;
 mod worker;
 
 use std::error::Error;
 use std::net::SocketAddr;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
 
 use clap::Parser;
 
@@ -42,6 +46,19 @@ struct Cli {
     #[clap(long, env)]
     #[clap(default_value_t = 512)]
     image_cache_capacity: u64,
+
+    /// Number of render workers. Each worker owns its own GL context.
+    #[clap(long, env)]
+    #[clap(default_value = "1")]
+    render_workers: NonZeroUsize,
+
+    #[clap(long, env, value_enum)]
+    #[clap(default_value_t = worker::pool::Dispatch::LeastLoaded)]
+    render_dispatch: worker::pool::Dispatch,
+
+    /// Asset bundle written by asset-preprocessor. Uses the builtin bundle if not specified.
+    #[clap(long, env)]
+    asset_bundle: Option<PathBuf>,
 }
 
 #[tokio::main]
@@ -50,9 +67,23 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     let cli = Cli::parse();
 
+    if let Some(path) = &cli.asset_bundle {
+        renderer_assets::QueryInterface::load_bundle(path)
+            .map_err(|e| format!("Failed to load asset bundle {}: {e}", path.display()))?;
+    }
+
     tracing::info!("Instance Name: {}", cli.instance_name);
+    tracing::info!(
+        "Asset Bundle: {}",
+        renderer_assets::QueryInterface::bundle_version()
+    );
     tracing::info!("ClientIP from: {:?}", cli.client_ip_source);
     tracing::info!("Image Cache Capacity: {}", cli.image_cache_capacity);
+    tracing::info!(
+        "Render Workers: {} ({:?})",
+        cli.render_workers,
+        cli.render_dispatch
+    );
     tracing::info!(
         "Minimum Response Interval: {}",
         cli.minimum_response_interval
@@ -63,6 +94,9 @@ async fn main() -> Result<(), Box<dyn Error>> {
         tracing::warn!("[SECURITY NOTICE] DO NOT USE THIS OPTION IN PRODUCTION!!");
     }
 
+    let render_workers = cli.render_workers;
+    let render_dispatch = cli.render_dispatch;
+
     let (webe_tx, webe_rx) = tokio::sync::oneshot::channel::<anyhow::Result<()>>();
     let (tx, rx) = tokio::sync::mpsc::channel::<Message>(16);
 
@@ -83,7 +117,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     });
 
     tokio::select! {
-        e = worker::run(rx) => {
+        e = worker::run(rx, render_workers, render_dispatch) => {
             tracing::error!("UNRECOVERABLE ERROR (Worker): {e:?}");
         }
         e = webe_rx => {
@@ -2,10 +2,77 @@ use std::fmt::{Display, Formatter};
 
 use crate::worker::image_buffer::RGBAImageData;
 use enum_map::Enum;
+use renderer_types::codes;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
-pub enum RenderingError {}
+pub enum RenderingError {
+    #[error("Failed to determine the bounding box")]
+    InvalidBoundingBox,
+
+    #[error("No geometry is mapped to the earthquake area {0:?}")]
+    UnknownEarthquakeArea(codes::地震情報細分区域),
+
+    #[error("No geometry is mapped to the tsunami area {0:?}")]
+    UnknownTsunamiArea(codes::津波予報区),
+
+    #[error("No LOD level is available for the scale {0}")]
+    NoLodLevel(f32),
+
+    #[error("GL resource failure: {0}")]
+    GlResource(#[from] GlResourceError),
+
+    #[error("Font cache overflowed: {0}")]
+    FontCacheOverflow(rusttype::gpu_cache::CacheWriteErr),
+
+    #[error("Failed to encode the image: {0}")]
+    Encode(String),
+
+    #[error("Render worker is unavailable")]
+    WorkerUnavailable,
+}
+
+#[derive(Error, Debug)]
+pub enum GlResourceError {
+    #[error(transparent)]
+    Draw(#[from] glium::DrawError),
+
+    #[error(transparent)]
+    VertexBuffer(#[from] glium::vertex::BufferCreationError),
+
+    #[error(transparent)]
+    IndexBuffer(#[from] glium::index::BufferCreationError),
+
+    #[error(transparent)]
+    Texture(#[from] glium::texture::TextureCreationError),
+
+    #[error(transparent)]
+    FrameBuffer(#[from] glium::framebuffer::ValidationError),
+
+    #[error("The render target cannot be read back as an image")]
+    Readback,
+}
+
+/// gliumのエラーを`?`で直接`RenderingError`に変換できるようにする
+macro_rules! impl_from_gl_error {
+    ($($error:ty),* $(,)?) => {
+        $(
+            impl From<$error> for RenderingError {
+                fn from(e: $error) -> Self {
+                    Self::GlResource(e.into())
+                }
+            }
+        )*
+    };
+}
+
+impl_from_gl_error!(
+    glium::DrawError,
+    glium::vertex::BufferCreationError,
+    glium::index::BufferCreationError,
+    glium::texture::TextureCreationError,
+    glium::framebuffer::ValidationError,
+);
 
 #[allow(clippy::type_complexity)]
 #[derive(Debug)]
@@ -19,7 +86,7 @@ pub enum Message {
 }
 
 #[allow(clippy::enum_variant_names)]
-#[derive(Enum, Clone, Copy, Debug)]
+#[derive(Enum, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Debug)]
 #[repr(u8)]
 pub enum 震度 {
     震度1,
@@ -34,7 +101,7 @@ pub enum 震度 {
 }
 
 #[allow(clippy::enum_variant_names)]
-#[derive(Enum, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Debug)]
+#[derive(Enum, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[repr(u8)]
 pub enum 津波情報 {
     津波予報 = 1,
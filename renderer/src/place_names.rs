@@ -0,0 +1,119 @@
+//! 地名の表記。画像に描く地名と代替テキストの地名を、同じ表から引く。
+
+use renderer_assets::QueryInterface;
+use renderer_types::{codes, GeoDegree, Vertex};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Script {
+    Japanese,
+    /// ローマ字の名前が用意されていなければ、コードで表す
+    Romanized,
+}
+
+pub fn area(area_code: codes::地震情報細分区域, script: Script) -> String {
+    let name = match script {
+        Script::Japanese => QueryInterface::query_area_name(area_code),
+        Script::Romanized => QueryInterface::query_romanized_area_name(area_code),
+    };
+
+    name.map(str::to_string)
+        .unwrap_or_else(|| format!("#{}", area_code.0))
+}
+
+pub fn tsunami_area(area_code: codes::津波予報区, script: Script) -> String {
+    let name = match script {
+        Script::Japanese => QueryInterface::query_tsunami_area_name(area_code),
+        Script::Romanized => QueryInterface::query_romanized_tsunami_area_name(area_code),
+    };
+
+    name.map(str::to_string)
+        .unwrap_or_else(|| format!("#{}", area_code.0))
+}
+
+pub fn prefecture(pref_code: codes::地震情報都道府県等, script: Script) -> String {
+    let name = match script {
+        Script::Japanese => QueryInterface::query_prefecture_name(pref_code),
+        Script::Romanized => QueryInterface::query_romanized_prefecture_name(pref_code),
+    };
+
+    name.map(str::to_string)
+        .unwrap_or_else(|| format!("#{}", pref_code.0))
+}
+
+/// 震央を含む細分区域の名前を、その都道府県名とともに返す。
+/// 海域なら、最も近い細分区域までの距離を添える。近くに細分区域が無ければNoneを返す。
+pub fn hypocenter(epicenter: Vertex<GeoDegree>, script: Script) -> Option<String> {
+    let (area_code, distance) = QueryInterface::query_nearest_area(epicenter)?;
+    let name = area(area_code, script);
+
+    let name = match QueryInterface::query_prefecture_by_point(epicenter) {
+        Some(pref_code) => with_prefecture(&name, &prefecture(pref_code, script), script),
+        None => name,
+    };
+
+    Some(describe_hypocenter(&name, distance, script))
+}
+
+/// 「石狩地方北部」のように都道府県名を含まない区域名には、都道府県名を補う
+fn with_prefecture(area: &str, prefecture: &str, script: Script) -> String {
+    match script {
+        Script::Japanese if area.starts_with(prefecture) => area.to_string(),
+        Script::Japanese => format!("{prefecture}{area}"),
+        Script::Romanized => format!("{area}, {prefecture}"),
+    }
+}
+
+/// 距離は10km単位に丸め、丸めて0になるほど近ければ付近とする
+fn describe_hypocenter(name: &str, distance: f32, script: Script) -> String {
+    if distance <= 0.0 {
+        return name.to_string();
+    }
+
+    match ((distance / 10.0).round() as u32 * 10, script) {
+        (0, Script::Japanese) => format!("{name}付近"),
+        (0, Script::Romanized) => format!("near {name}"),
+        (distance, Script::Japanese) => format!("{name}の沖 約{distance}km"),
+        (distance, Script::Romanized) => format!("about {distance} km off {name}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::place_names::*;
+
+    #[test]
+    fn test_with_prefecture() {
+        assert_eq!(
+            with_prefecture("石狩地方北部", "北海道", Script::Japanese),
+            "北海道石狩地方北部"
+        );
+        assert_eq!(
+            with_prefecture("石川県能登", "石川県", Script::Japanese),
+            "石川県能登"
+        );
+        assert_eq!(
+            with_prefecture("Northern Ishikari", "Hokkaido", Script::Romanized),
+            "Northern Ishikari, Hokkaido"
+        );
+    }
+
+    #[test]
+    fn test_describe_hypocenter() {
+        assert_eq!(
+            describe_hypocenter("石川県能登", 0.0, Script::Japanese),
+            "石川県能登"
+        );
+        assert_eq!(
+            describe_hypocenter("石川県能登", 4.0, Script::Japanese),
+            "石川県能登付近"
+        );
+        assert_eq!(
+            describe_hypocenter("石川県能登", 36.0, Script::Japanese),
+            "石川県能登の沖 約40km"
+        );
+        assert_eq!(
+            describe_hypocenter("Ishikawa Noto", 36.0, Script::Romanized),
+            "about 40 km off Ishikawa Noto"
+        );
+    }
+}
@@ -5,6 +5,7 @@ use enum_map::enum_map;
 use enum_map::EnumMap;
 use renderer_assets::QueryInterface;
 use renderer_types::codes;
+use renderer_types::projection::Projection;
 use renderer_types::{GeoDegree, Vertex};
 
 #[derive(Debug)]
@@ -32,23 +33,30 @@ pub struct TsunamiPayload {
 }
 
 impl TsunamiPayload {
-    pub fn into_frame_payloads(self) -> [crate::frame_context::FramePayload; 2] {
-        [
-            crate::frame_context::FramePayload::TsunamiFirst(
-                crate::frame_context::TsunamiFirstPayload {
+    /// 点滅アニメーションの各フレームの描画内容を作る。
+    /// frame_countがNoneなら、全ての線を描いた静止画の1枚だけを作る。
+    pub fn into_frame_payloads(
+        self,
+        frame_count: Option<usize>,
+        region_list: bool,
+    ) -> Vec<crate::frame_context::FramePayload> {
+        let blink_frames: Vec<_> = match frame_count {
+            Some(frame_count) => (0..frame_count).map(Some).collect(),
+            None => vec![None],
+        };
+
+        blink_frames
+            .into_iter()
+            .map(|blink_frame| {
+                crate::frame_context::FramePayload::Tsunami(crate::frame_context::TsunamiPayload {
                     time: self.time,
-                    forecast_levels: self.forecast_levels.clone(),
                     epicenter: self.epicenter.clone(),
-                },
-            ),
-            crate::frame_context::FramePayload::TsunamiSecond(
-                crate::frame_context::TsunamiSecondPayload {
-                    time: self.time,
-                    forecast_levels: self.forecast_levels,
-                    epicenter: self.epicenter,
-                },
-            ),
-        ]
+                    forecast_levels: self.forecast_levels.clone(),
+                    blink_frame,
+                    region_list,
+                })
+            })
+            .collect()
     }
 }
 
@@ -61,6 +69,13 @@ pub enum RenderingPayload {
 #[derive(Debug)]
 pub struct RenderingContext {
     pub payload: RenderingPayload,
+    pub projection: Option<Projection>,
+    /// falseなら津波情報もアニメーションさせずに静止画にする
+    pub animated: bool,
+    /// 津波情報で、凡例の代わりに津波予報区の一覧を描くか
+    pub region_list: bool,
+    /// 津波情報を予報区の周辺だけに絞って描く段階。Noneならテーマの設定に従う
+    pub regional_tsunami_below: Option<Option<津波情報>>,
     pub request_identity: String,
 }
 
@@ -71,6 +86,13 @@ pub enum PayloadError {
 
     #[error("AreaCode or epicenter were not provided")]
     AreaCodeOrEpicenterWereNotProvided,
+
+    #[error("Invalid time is provided")]
+    InvalidTimeIsProvided,
+}
+
+fn timestamp(seconds: i64) -> Result<DateTime<Utc>, PayloadError> {
+    DateTime::from_timestamp(seconds, 0).ok_or(PayloadError::InvalidTimeIsProvided)
 }
 
 impl TryFrom<proto::QuakePrefectureV0> for RenderingPayload {
@@ -102,7 +124,7 @@ impl TryFrom<proto::QuakePrefectureV0> for RenderingPayload {
         }
 
         Ok(Self::Earthquake(EarthquakePayload {
-            time: DateTime::from_timestamp(data.time as i64, 0).unwrap(),
+            time: timestamp(data.time as i64)?,
             epicenter: data
                 .epicenter
                 .into_iter()
@@ -135,7 +157,7 @@ impl TryFrom<proto::TsunamiForecastV0> for RenderingPayload {
         }
 
         Ok(Self::Tsunami(TsunamiPayload {
-            time: DateTime::from_timestamp(data.time as i64, 0).unwrap(),
+            time: timestamp(data.time as i64)?,
             epicenter: data
                 .epicenter
                 .into_iter()
@@ -168,7 +190,7 @@ impl TryFrom<proto::TsunamiForecastV1> for RenderingPayload {
         }
 
         Ok(Self::Tsunami(TsunamiPayload {
-            time: DateTime::from_timestamp(data.time as i64, 0).unwrap(),
+            time: timestamp(data.time as i64)?,
             epicenter: data
                 .epicenter
                 .into_iter()
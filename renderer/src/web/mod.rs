@@ -15,10 +15,12 @@ use axum_extra::TypedHeader;
 use headers::UserAgent;
 use hmac::{Hmac, KeyInit, Mac};
 use prost::Message;
+use renderer_types::projection::Projection;
 
 use image::{DynamicImage, RgbaImage};
 
 use crate::model::*;
+use crate::place_names::Script;
 use crate::rendering_context::{RenderingContext, RenderingPayload};
 
 mod rate_limiter;
@@ -43,7 +45,16 @@ pub struct AppState {
     instance_name: Arc<String>,
     response_limiter: ResponseRateLimiter,
     security_rules: SecurityRules,
-    cache: moka::future::Cache<Sha1Bytes, bytes::Bytes>,
+    cache: moka::future::Cache<
+        (
+            Sha1Bytes,
+            Option<Projection>,
+            bool,
+            bool,
+            Option<Option<津波情報>>,
+        ),
+        bytes::Bytes,
+    >,
 }
 
 async fn composite_image(
@@ -51,92 +62,205 @@ async fn composite_image(
     request_channel: &tokio::sync::mpsc::Sender<crate::model::Message>,
 ) -> Result<bytes::Bytes, RenderingError> {
     let request_identity = &rendering_context.request_identity;
+    let projection = rendering_context.projection;
+    let regional_tsunami_below = rendering_context.regional_tsunami_below;
 
-    match rendering_context.payload {
+    let payloads = match rendering_context.payload {
         RenderingPayload::Earthquake(rendering_payload) => {
-            let payload = rendering_payload.into_frame_payload();
-            let (tx, rx) = tokio::sync::oneshot::channel();
-
-            request_channel
-                .send(crate::Message::FrameRequest((
-                    crate::frame_context::FrameContext {
-                        payload,
-                        request_identity: request_identity.to_string(),
-                    },
-                    tx,
-                )))
-                .await
-                .unwrap();
+            vec![rendering_payload.into_frame_payload()]
+        }
+        RenderingPayload::Tsunami(rendering_payload) => {
+            let animation = crate::worker::tsunami_animation();
+            let frame_count = (rendering_context.animated && animation.frame_count() > 1)
+                .then(|| animation.frame_count());
+            rendering_payload.into_frame_payloads(frame_count, rendering_context.region_list)
+        }
+    };
 
-            let image = rx.await.unwrap()?;
+    // 先に全フレームを依頼しておき、複数のワーカーで並行して描画させる
+    let mut receivers = vec![];
+    for payload in payloads {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+
+        request_channel
+            .send(crate::Message::FrameRequest((
+                crate::frame_context::FrameContext {
+                    payload,
+                    projection,
+                    regional_tsunami_below,
+                    request_identity: request_identity.to_string(),
+                },
+                tx,
+            )))
+            .await
+            .map_err(|_| RenderingError::WorkerUnavailable)?;
+
+        receivers.push(rx);
+    }
 
-            let start_at = Instant::now();
-            let image = RgbaImage::from_raw(image.width, image.height, image.data).unwrap();
+    let mut frames = vec![];
+    for rx in receivers {
+        let image = rx.await.map_err(|_| RenderingError::WorkerUnavailable)??;
 
-            let mut image = DynamicImage::ImageRgba8(image);
-            image.apply_orientation(image::metadata::Orientation::FlipVertical);
+        let image = RgbaImage::from_raw(image.width, image.height, image.data)
+            .ok_or_else(|| RenderingError::Encode("Invalid image buffer".to_string()))?;
+        let image = DynamicImage::ImageRgba8(image);
 
-            let encoder = webp::Encoder::from_image(&image).unwrap();
-            let bin = encoder.encode_lossless().to_vec();
+        frames.push(image);
+    }
 
-            let encode_time = Instant::now() - start_at;
+    let start_at = Instant::now();
 
-            tracing::info!("Encode: {:?} ({request_identity})", encode_time);
+    let (bin, kind) = match frames.as_slice() {
+        [] => return Err(RenderingError::Encode("No frame is rendered".to_string())),
+        [image] => {
+            let encoder = webp::Encoder::from_image(image)
+                .map_err(|e| RenderingError::Encode(e.to_string()))?;
 
-            Ok(bytes::Bytes::from_owner(bin))
+            (encoder.encode_lossless().to_vec(), "Encode")
         }
-        RenderingPayload::Tsunami(rendering_payload) => {
-            let payloads = rendering_payload.into_frame_payloads();
-
-            let mut frames = vec![];
-            for payload in payloads {
-                let (tx, rx) = tokio::sync::oneshot::channel();
-
-                request_channel
-                    .send(crate::Message::FrameRequest((
-                        crate::frame_context::FrameContext {
-                            payload,
-                            request_identity: request_identity.to_string(),
-                        },
-                        tx,
-                    )))
-                    .await
-                    .unwrap();
-
-                let image = rx.await.unwrap()?;
-
-                let image = RgbaImage::from_raw(image.width, image.height, image.data).unwrap();
-                let mut image = DynamicImage::ImageRgba8(image);
-                image.apply_orientation(image::metadata::Orientation::FlipVertical);
-
-                frames.push(image);
-            }
-
-            let start_at = Instant::now();
-
-            let first_frame = frames.first().unwrap();
+        [first_frame, ..] => {
+            let animation = crate::worker::tsunami_animation();
+            let (timestamps, end_timestamp) = animation.timestamps();
 
             let mut encoder = webp_animation::Encoder::new_with_options(
                 (first_frame.width(), first_frame.height()),
                 Default::default(),
             )
-            .unwrap();
+            .map_err(|e| RenderingError::Encode(e.to_string()))?;
 
-            for (n, frame) in frames.iter().enumerate() {
+            for (frame, timestamp) in frames.iter().zip(timestamps) {
                 encoder
-                    .add_frame(frame.as_bytes(), (n * 2250 / 2) as i32)
-                    .unwrap();
+                    .add_frame(frame.as_bytes(), timestamp as i32)
+                    .map_err(|e| RenderingError::Encode(e.to_string()))?;
             }
 
             let bin = encoder
-                .finalize(((frames.len() - 1) as i32 * 2250 + 750) / 2)
-                .unwrap();
+                .finalize(end_timestamp as i32)
+                .map_err(|e| RenderingError::Encode(e.to_string()))?;
+
+            (bin.to_vec(), "AnimEncode")
+        }
+    };
+
+    let encode_time = Instant::now() - start_at;
+
+    tracing::info!("{kind}: {:?} ({request_identity})", encode_time);
+
+    Ok(bytes::Bytes::from_owner(bin))
+}
+
+/// 描画の失敗を、リクエスト側の問題かサーバー側の問題かでステータスコードに振り分ける
+fn rendering_error_status(e: &RenderingError) -> StatusCode {
+    match e {
+        RenderingError::InvalidBoundingBox
+        | RenderingError::UnknownEarthquakeArea(_)
+        | RenderingError::UnknownTsunamiArea(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        RenderingError::NoLodLevel(_)
+        | RenderingError::GlResource(_)
+        | RenderingError::FontCacheOverflow(_)
+        | RenderingError::Encode(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        RenderingError::WorkerUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+    }
+}
+
+/// クエリの`projection=<名前>`で投影法を指定できる。指定が無ければNoneを返す。
+fn requested_projection(
+    query: Option<&str>,
+) -> Result<Option<Projection>, renderer_types::projection::UnknownProjection> {
+    query
+        .into_iter()
+        .flat_map(|query| query.split('&'))
+        .filter_map(|pair| pair.strip_prefix("projection="))
+        .last()
+        .map(str::parse)
+        .transpose()
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("Unknown animation mode {0:?} (expected \"animated\" or \"static\")")]
+struct UnknownAnimationMode(String);
+
+/// クエリの`animation=static`でアニメーションしない静止画を要求できる。指定が無ければアニメーションさせる。
+fn requested_animation(query: Option<&str>) -> Result<bool, UnknownAnimationMode> {
+    match query
+        .into_iter()
+        .flat_map(|query| query.split('&'))
+        .filter_map(|pair| pair.strip_prefix("animation="))
+        .last()
+    {
+        None | Some("animated") => Ok(true),
+        Some("static") => Ok(false),
+        Some(mode) => Err(UnknownAnimationMode(mode.to_string())),
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("Unknown sidebar mode {0:?} (expected \"true\" or \"false\")")]
+struct UnknownSidebarMode(String);
+
+/// クエリの`sidebar=true`で、津波情報の凡例の代わりに津波予報区の一覧を要求できる。指定が無ければ描かない。
+fn requested_sidebar(query: Option<&str>) -> Result<bool, UnknownSidebarMode> {
+    match query
+        .into_iter()
+        .flat_map(|query| query.split('&'))
+        .filter_map(|pair| pair.strip_prefix("sidebar="))
+        .last()
+    {
+        None | Some("false") => Ok(false),
+        Some("true") => Ok(true),
+        Some(mode) => Err(UnknownSidebarMode(mode.to_string())),
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("Unknown tsunami level {0:?} (expected \"forecast\", \"advisory\", \"warning\", \"major_warning\" or \"none\")")]
+struct UnknownTsunamiLevel(String);
+
+/// クエリの`regional_tsunami_below=<段階>`で、津波情報をこの段階未満のものだけなら予報区の周辺に絞って描くよう指定できる。
+/// `none`なら常に日本全体を描く。指定が無ければNoneを返し、テーマの設定に従う。
+fn requested_regional_tsunami_below(
+    query: Option<&str>,
+) -> Result<Option<Option<津波情報>>, UnknownTsunamiLevel> {
+    let Some(level) = query
+        .into_iter()
+        .flat_map(|query| query.split('&'))
+        .filter_map(|pair| pair.strip_prefix("regional_tsunami_below="))
+        .last()
+    else {
+        return Ok(None);
+    };
 
-            let encode_time = Instant::now() - start_at;
+    let level = match level {
+        "forecast" => Some(津波情報::津波予報),
+        "advisory" => Some(津波情報::津波注意報),
+        "warning" => Some(津波情報::津波警報),
+        "major_warning" => Some(津波情報::大津波警報),
+        "none" => None,
+        level => return Err(UnknownTsunamiLevel(level.to_string())),
+    };
+
+    Ok(Some(level))
+}
 
-            tracing::info!("AnimEncode: {:?} ({request_identity})", encode_time);
+/// 津波情報にしか効かないクエリの指定
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct TsunamiOptions {
+    animated: bool,
+    region_list: bool,
+    regional_tsunami_below: Option<Option<津波情報>>,
+}
 
-            Ok(bytes::Bytes::from_owner(bin))
+impl TsunamiOptions {
+    /// 地震情報では指定を無視して既定の値にそろえ、同じ画像でキャッシュを分けないようにする。
+    fn for_payload(self, payload: &RenderingPayload) -> Self {
+        match payload {
+            RenderingPayload::Earthquake(_) => Self {
+                animated: true,
+                region_list: false,
+                regional_tsunami_below: None,
+            },
+            RenderingPayload::Tsunami(_) => self,
         }
     }
 }
@@ -151,6 +275,26 @@ async fn render_handler(
 
     let bin = &req.uri().path()[1..];
 
+    let projection = match requested_projection(req.uri().query()) {
+        Ok(projection) => projection,
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+
+    let animated = match requested_animation(req.uri().query()) {
+        Ok(animated) => animated,
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+
+    let region_list = match requested_sidebar(req.uri().query()) {
+        Ok(region_list) => region_list,
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+
+    let regional_tsunami_below = match requested_regional_tsunami_below(req.uri().query()) {
+        Ok(regional_tsunami_below) => regional_tsunami_below,
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+
     let Ok(bin) = urlencoding::decode(bin) else {
         return (StatusCode::BAD_REQUEST, "Failed to UTF-8 parsing").into_response();
     };
@@ -308,23 +452,57 @@ async fn render_handler(
         }
     };
 
+    let TsunamiOptions {
+        animated,
+        region_list,
+        regional_tsunami_below,
+    } = TsunamiOptions {
+        animated,
+        region_list,
+        regional_tsunami_below,
+    }
+    .for_payload(&rendering_payload);
+
+    // ヘッダーにはASCIIしか入れられないので、パーセントエンコードする
+    let alt_text = urlencoding::encode(&crate::alt_text::describe(
+        &rendering_payload,
+        Script::Japanese,
+    ))
+    .into_owned();
+    let romanized_alt_text = urlencoding::encode(&crate::alt_text::describe(
+        &rendering_payload,
+        Script::Romanized,
+    ))
+    .into_owned();
+
     let rendering_context = RenderingContext {
         payload: rendering_payload,
+        projection,
+        animated,
+        region_list,
+        regional_tsunami_below,
         request_identity: request_identity.clone(),
     };
 
     let image_binary = app
         .cache
-        .try_get_with::<_, RenderingError>(calculated_sha1.into(), async move {
-            composite_image(rendering_context, &app.request_channel).await
-        })
+        .try_get_with::<_, RenderingError>(
+            (
+                calculated_sha1.into(),
+                projection,
+                animated,
+                region_list,
+                regional_tsunami_below,
+            ),
+            async move { composite_image(rendering_context, &app.request_channel).await },
+        )
         .await;
 
     let image_binary = match image_binary {
         Ok(image_binary) => image_binary,
         Err(e) => {
-            tracing::error!("Request {short_hash} is errored. Code: {e}");
-            return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+            tracing::error!("{e} ({request_identity})");
+            return (rendering_error_status(&e), e.to_string()).into_response();
         }
     };
 
@@ -341,6 +519,14 @@ async fn render_handler(
                 HeaderName::from_bytes(b"X-Instance-Name").unwrap(),
                 HeaderValue::from_str(&app.instance_name).unwrap(),
             ),
+            (
+                HeaderName::from_bytes(b"X-Alt-Text").unwrap(),
+                HeaderValue::from_str(&alt_text).unwrap(),
+            ),
+            (
+                HeaderName::from_bytes(b"X-Alt-Text-Romanized").unwrap(),
+                HeaderValue::from_str(&romanized_alt_text).unwrap(),
+            ),
         ],
         image_binary,
     )
@@ -351,8 +537,10 @@ async fn root_handler(State(app): State<AppState>, ClientIp(_client_ip): ClientI
     (
         [(CONTENT_TYPE, HeaderValue::from_str("text/html").unwrap())],
         format!(
-            "<h1>EEW Renderer</h1><p>Instance Name: {}</p><p>Bypass HMAC: {}</p>",
-            app.instance_name, app.security_rules.bypass_hmac,
+            "<h1>EEW Renderer</h1><p>Instance Name: {}</p><p>Asset Bundle: {}</p><p>Bypass HMAC: {}</p>",
+            app.instance_name,
+            renderer_assets::QueryInterface::bundle_version(),
+            app.security_rules.bypass_hmac,
         ),
     )
         .into_response()
@@ -406,3 +594,72 @@ pub async fn run(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::model::津波情報;
+    use crate::rendering_context::{EarthquakePayload, RenderingPayload, TsunamiPayload};
+    use crate::web::{requested_animation, requested_regional_tsunami_below, TsunamiOptions};
+    use chrono::Utc;
+
+    #[test]
+    fn test_requested_animation() {
+        assert!(requested_animation(None).unwrap());
+        assert!(requested_animation(Some("projection=mercator")).unwrap());
+        assert!(requested_animation(Some("animation=animated")).unwrap());
+        assert!(!requested_animation(Some("animation=static")).unwrap());
+        assert!(!requested_animation(Some("animation=animated&animation=static")).unwrap());
+        assert!(requested_animation(Some("animation=gif")).is_err());
+    }
+
+    #[test]
+    fn test_tsunami_options_for_earthquake() {
+        let options = TsunamiOptions {
+            animated: false,
+            region_list: true,
+            regional_tsunami_below: Some(None),
+        };
+        let earthquake = RenderingPayload::Earthquake(EarthquakePayload {
+            time: Utc::now(),
+            epicenter: vec![],
+            area_intensities: Default::default(),
+        });
+        let tsunami = RenderingPayload::Tsunami(TsunamiPayload {
+            time: Utc::now(),
+            epicenter: vec![],
+            forecast_levels: Default::default(),
+        });
+
+        // 地震情報では、静止画の指定も既定と同じ画像としてキャッシュする
+        assert_eq!(
+            options.for_payload(&earthquake),
+            TsunamiOptions {
+                animated: true,
+                region_list: false,
+                regional_tsunami_below: None,
+            }
+        );
+        assert_eq!(options.for_payload(&tsunami), options);
+    }
+
+    #[test]
+    fn test_requested_regional_tsunami_below() {
+        assert_eq!(requested_regional_tsunami_below(None).unwrap(), None);
+        assert_eq!(
+            requested_regional_tsunami_below(Some("projection=mercator")).unwrap(),
+            None
+        );
+        assert_eq!(
+            requested_regional_tsunami_below(Some("regional_tsunami_below=advisory")).unwrap(),
+            Some(Some(津波情報::津波注意報))
+        );
+        assert_eq!(
+            requested_regional_tsunami_below(Some(
+                "regional_tsunami_below=warning&regional_tsunami_below=none"
+            ))
+            .unwrap(),
+            Some(None)
+        );
+        assert!(requested_regional_tsunami_below(Some("regional_tsunami_below=3")).is_err());
+    }
+}
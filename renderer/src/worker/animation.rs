@@ -0,0 +1,91 @@
+use crate::model::津波情報;
+
+/// 津波の線を点滅させるアニメーションの設定
+#[derive(Clone, Copy, Debug)]
+pub struct TsunamiAnimation {
+    /// 各フレームの表示時間(ミリ秒)。この長さがフレーム数になる
+    pub frame_durations: &'static [u32],
+    pub patterns: BlinkPatterns,
+}
+
+/// 津波情報の種類ごとに、各フレームで線を描くかどうか。
+/// フレーム数より短ければ繰り返し、空なら点滅させずに常に描く。
+#[derive(Clone, Copy, Debug)]
+pub struct BlinkPatterns {
+    pub forecast: &'static [bool],
+    pub advisory: &'static [bool],
+    pub warning: &'static [bool],
+    pub major_warning: &'static [bool],
+}
+
+impl TsunamiAnimation {
+    /// 大津波警報は速く点滅させ、津波予報は点滅させない
+    pub const DEFAULT: Self = Self {
+        frame_durations: &[375, 375, 375, 375],
+        patterns: BlinkPatterns {
+            forecast: &[],
+            advisory: &[true, true, true, false],
+            warning: &[true, true, true, false],
+            major_warning: &[true, false],
+        },
+    };
+
+    pub fn frame_count(&self) -> usize {
+        self.frame_durations.len()
+    }
+
+    /// 各フレームの表示を始める時刻(ミリ秒)と、最後のフレームの表示を終える時刻
+    pub fn timestamps(&self) -> (Vec<u32>, u32) {
+        let starts = self
+            .frame_durations
+            .iter()
+            .scan(0, |elapsed, duration| {
+                let start = *elapsed;
+                *elapsed += duration;
+                Some(start)
+            })
+            .collect();
+
+        (starts, self.frame_durations.iter().sum())
+    }
+
+    /// frame番目のフレームでlevelの線を描くか。静止画(frameがNone)では常に描く。
+    pub fn is_lit(&self, level: 津波情報, frame: Option<usize>) -> bool {
+        let pattern = match level {
+            津波情報::津波予報 => self.patterns.forecast,
+            津波情報::津波注意報 => self.patterns.advisory,
+            津波情報::津波警報 => self.patterns.warning,
+            津波情報::大津波警報 => self.patterns.major_warning,
+        };
+
+        match frame {
+            Some(n) if !pattern.is_empty() => pattern[n % pattern.len()],
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::津波情報;
+    use crate::worker::animation::TsunamiAnimation;
+
+    #[test]
+    fn test_blink_patterns() {
+        let animation = TsunamiAnimation::DEFAULT;
+
+        assert_eq!(animation.timestamps(), (vec![0, 375, 750, 1125], 1500));
+
+        let lit = |level| {
+            (0..animation.frame_count())
+                .map(|n| animation.is_lit(level, Some(n)))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(lit(津波情報::津波予報), [true, true, true, true]);
+        assert_eq!(lit(津波情報::津波注意報), [true, true, true, false]);
+        assert_eq!(lit(津波情報::津波警報), [true, true, true, false]);
+        assert_eq!(lit(津波情報::大津波警報), [true, false, true, false]);
+
+        assert!(animation.is_lit(津波情報::大津波警報, None));
+    }
+}
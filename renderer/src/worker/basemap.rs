@@ -0,0 +1,161 @@
+use crate::model::RenderingError;
+use crate::worker::collision::ScreenRect;
+use crate::worker::framing::Frame;
+use crate::worker::{drawer_map, FrameContext};
+use glium::backend::Facade;
+use glium::framebuffer::SimpleFrameBuffer;
+use glium::uniforms::MagnifySamplerFilter;
+use glium::{BlitTarget, DrawParameters, Surface, Texture2d};
+use renderer_types::Size;
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::ops::Deref;
+use std::rc::Rc;
+
+const BASEMAP_CACHE_CAPACITY: usize = 8;
+
+/// 同じ描画範囲・投影法・テーマであれば陸地や境界線は毎回同じになるので、
+/// それらを描き込んだテクスチャを使い回すためのキー
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct BasemapKey {
+    offset: (u32, u32),
+    scale: u32,
+    projection: i32,
+    projection_origin: (u32, u32),
+    size: (u32, u32),
+    theme: &'static str,
+    has_saibunkuiki: bool,
+    has_world: bool,
+}
+
+#[derive(Default)]
+pub struct BasemapCache {
+    textures: HashMap<BasemapKey, Texture2d>,
+    order: VecDeque<BasemapKey>,
+}
+
+impl BasemapCache {
+    /// 背景(海・世界の陸地・陸地・境界線)を描画する。
+    /// キャッシュに無ければ新たに描画して保持し、あればそれを転写するだけで済ませる。
+    /// 差し込み図では、枠と同じ大きさのテクスチャに枠の中だけを描く。
+    pub fn draw<F: ?Sized + Facade, S: Surface>(
+        &mut self,
+        frame_context: &FrameContext<F, S>,
+        has_saibunkuiki: bool,
+        has_world: bool,
+    ) -> Result<(), RenderingError> {
+        let scissor = frame_context.draw_parameters.scissor;
+        let (frame, size) = match scissor {
+            Some(rect) => {
+                let top = frame_context.image_size.y() - rect.bottom - rect.height;
+                let rect_on_image = ScreenRect {
+                    left: rect.left as f32,
+                    top: top as f32,
+                    right: (rect.left + rect.width) as f32,
+                    bottom: (top + rect.height) as f32,
+                };
+                (
+                    frame_context
+                        .frame
+                        .localize(rect_on_image, frame_context.image_size),
+                    Size::new(rect.width, rect.height),
+                )
+            }
+            None => (frame_context.frame, frame_context.image_size),
+        };
+
+        let key = BasemapKey {
+            offset: (frame.offset.x().to_bits(), frame.offset.y().to_bits()),
+            scale: frame.scale.to_bits(),
+            projection: frame.projection.shader_id(),
+            projection_origin: (
+                frame.projection_origin.x().to_bits(),
+                frame.projection_origin.y().to_bits(),
+            ),
+            size: size.into(),
+            theme: frame_context.theme.name,
+            has_saibunkuiki,
+            has_world,
+        };
+
+        if !self.textures.contains_key(&key) {
+            let texture = render_basemap(frame_context, frame, size, has_saibunkuiki, has_world)?;
+            self.insert(key, texture);
+        }
+
+        let texture = &self.textures[&key];
+        match scissor {
+            Some(rect) => texture.as_surface().blit_whole_color_to(
+                frame_context.surface.borrow().deref(),
+                &BlitTarget {
+                    left: rect.left,
+                    bottom: rect.bottom,
+                    width: rect.width as i32,
+                    height: rect.height as i32,
+                },
+                MagnifySamplerFilter::Nearest,
+            ),
+            None => texture.as_surface().fill(
+                frame_context.surface.borrow().deref(),
+                MagnifySamplerFilter::Nearest,
+            ),
+        }
+
+        Ok(())
+    }
+
+    fn insert(&mut self, key: BasemapKey, texture: Texture2d) {
+        if self.order.len() >= BASEMAP_CACHE_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.textures.remove(&oldest);
+            }
+        }
+
+        self.order.push_back(key);
+        self.textures.insert(key, texture);
+    }
+}
+
+fn render_basemap<F: ?Sized + Facade, S: ?Sized + Surface>(
+    frame_context: &FrameContext<F, S>,
+    frame: Frame,
+    image_size: Size<u32>,
+    has_saibunkuiki: bool,
+    has_world: bool,
+) -> Result<Texture2d, RenderingError> {
+    let facade = frame_context.facade;
+
+    let texture = Texture2d::empty(facade, image_size.x(), image_size.y())?;
+
+    {
+        let frame_buffer = SimpleFrameBuffer::new(facade, &texture)?;
+        let frame_buffer = Rc::new(RefCell::new(frame_buffer));
+
+        let basemap_context = FrameContext {
+            facade,
+            surface: frame_buffer.clone(),
+            image_size,
+            theme: frame_context.theme,
+            resources: frame_context.resources,
+            font_manager: frame_context.font_manager.clone(),
+            draw_parameters: DrawParameters {
+                scissor: None,
+                ..frame_context.draw_parameters.clone()
+            },
+            frame,
+            viewport: ScreenRect::full(image_size),
+        };
+
+        let clear_color = basemap_context.theme.clear_color;
+        frame_buffer.borrow_mut().clear_color(
+            clear_color[0],
+            clear_color[1],
+            clear_color[2],
+            clear_color[3],
+        );
+
+        drawer_map::draw(&basemap_context, has_saibunkuiki, has_world)?;
+    }
+
+    Ok(texture)
+}
@@ -0,0 +1,153 @@
+use renderer_types::Size;
+
+/// 画面上で何かが占有している矩形。左上原点のピクセル座標で表す。
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ScreenRect {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl ScreenRect {
+    /// 画像全体
+    pub fn full(image_size: Size<u32>) -> Self {
+        let image_size = image_size.to_f32();
+
+        Self {
+            left: 0.0,
+            top: 0.0,
+            right: image_size.x(),
+            bottom: image_size.y(),
+        }
+    }
+
+    pub fn from_center(center: (f32, f32), size: (f32, f32)) -> Self {
+        Self {
+            left: center.0 - size.0 / 2.0,
+            top: center.1 - size.1 / 2.0,
+            right: center.0 + size.0 / 2.0,
+            bottom: center.1 + size.1 / 2.0,
+        }
+    }
+
+    /// スクリーン空間(中央原点、上が正)の頂点群を囲む矩形を作る。
+    pub fn from_screen_vertices(vertices: &[[f32; 2]], image_size: Size<u32>) -> Self {
+        let image_size = image_size.to_f32();
+        let to_pixel = |v: &[f32; 2]| {
+            (
+                (v[0] + 1.0) * 0.5 * image_size.x(),
+                (1.0 - v[1]) * 0.5 * image_size.y(),
+            )
+        };
+
+        vertices.iter().map(to_pixel).fold(
+            Self {
+                left: f32::MAX,
+                top: f32::MAX,
+                right: f32::MIN,
+                bottom: f32::MIN,
+            },
+            |rect, (x, y)| Self {
+                left: rect.left.min(x),
+                top: rect.top.min(y),
+                right: rect.right.max(x),
+                bottom: rect.bottom.max(y),
+            },
+        )
+    }
+
+    pub fn center(&self) -> (f32, f32) {
+        (
+            (self.left + self.right) / 2.0,
+            (self.top + self.bottom) / 2.0,
+        )
+    }
+
+    pub fn size(&self) -> (f32, f32) {
+        (self.right - self.left, self.bottom - self.top)
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.left < other.right
+            && other.left < self.right
+            && self.top < other.bottom
+            && other.top < self.bottom
+    }
+
+    pub fn is_within(&self, image_size: Size<u32>) -> bool {
+        let image_size = image_size.to_f32();
+
+        0.0 <= self.left
+            && 0.0 <= self.top
+            && self.right <= image_size.x()
+            && self.bottom <= image_size.y()
+    }
+
+    pub fn is_inside(&self, other: &Self) -> bool {
+        other.left <= self.left
+            && other.top <= self.top
+            && self.right <= other.right
+            && self.bottom <= other.bottom
+    }
+}
+
+/// 先に確保されたものを優先して、重ならないように画面上の領域を割り当てる
+#[derive(Default)]
+pub struct CollisionMap {
+    occupied: Vec<ScreenRect>,
+}
+
+impl CollisionMap {
+    /// 他との重なりに関わらず領域を確保する。
+    pub fn reserve(&mut self, rect: ScreenRect) {
+        self.occupied.push(rect);
+    }
+
+    /// 既に確保された領域と重ならなければ確保してtrueを返す。
+    pub fn try_place(&mut self, rect: ScreenRect) -> bool {
+        if self.occupied.iter().any(|r| r.intersects(&rect)) {
+            return false;
+        }
+
+        self.occupied.push(rect);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::worker::collision::{CollisionMap, ScreenRect};
+    use renderer_types::Size;
+
+    #[test]
+    fn test_try_place() {
+        let mut map = CollisionMap::default();
+        map.reserve(ScreenRect::from_center((50.0, 50.0), (20.0, 20.0)));
+
+        assert!(!map.try_place(ScreenRect::from_center((55.0, 55.0), (20.0, 20.0))));
+        assert!(map.try_place(ScreenRect::from_center((80.0, 50.0), (20.0, 20.0))));
+        // 接しているだけなら重なりとはみなさない
+        assert!(map.try_place(ScreenRect::from_center((50.0, 70.0), (20.0, 20.0))));
+        assert!(!map.try_place(ScreenRect::from_center((80.0, 55.0), (10.0, 10.0))));
+    }
+
+    #[test]
+    fn test_from_screen_vertices() {
+        let rect = ScreenRect::from_screen_vertices(
+            &[[-1.0, 1.0], [0.0, 1.0], [-1.0, 0.0], [0.0, 0.0]],
+            Size::new(100, 50),
+        );
+
+        assert_eq!(
+            rect,
+            ScreenRect {
+                left: 0.0,
+                top: 0.0,
+                right: 50.0,
+                bottom: 25.0,
+            }
+        );
+        assert!(rect.is_within(Size::new(100, 50)));
+    }
+}
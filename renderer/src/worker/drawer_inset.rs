@@ -0,0 +1,123 @@
+use crate::model::RenderingError;
+use crate::worker::collision::ScreenRect;
+use crate::worker::fonts::{Font, Offset, Origin};
+use crate::worker::vertex::{ShapeUniform, ShapeVertex};
+use crate::worker::FrameContext;
+use glium::backend::Facade;
+use glium::index::{NoIndices, PrimitiveType};
+use glium::{Surface, VertexBuffer};
+use renderer_types::Size;
+use rusttype::Scale;
+use std::ops::DerefMut;
+
+const NAME_TEXT_SCALE: f32 = 18.0;
+/// 枠の内側から名前までの余白
+const NAME_PADDING: f32 = 6.0;
+
+/// 差し込み図の枠と名前を描画する。
+pub fn draw<F: ?Sized + Facade, S: ?Sized + Surface>(
+    frame_context: &FrameContext<F, S>,
+    name: &str,
+) -> Result<(), RenderingError> {
+    let facade = frame_context.facade;
+    let resources = frame_context.resources;
+    let draw_parameters = &frame_context.draw_parameters;
+    let theme = frame_context.theme;
+    let rect = frame_context.viewport;
+
+    let border =
+        calculate_border_vertices(rect, theme.inset_border_width, frame_context.image_size);
+    let border = VertexBuffer::dynamic(facade, &border)?;
+    let color = theme.inset_border_color;
+
+    resources.shader.shape.draw(
+        frame_context.surface.borrow_mut().deref_mut(),
+        &border,
+        NoIndices(PrimitiveType::TriangleStrip),
+        &ShapeUniform {
+            color: [color[0], color[1], color[2], 1.0],
+        },
+        draw_parameters,
+    )?;
+
+    frame_context
+        .font_manager
+        .borrow_mut()
+        .deref_mut()
+        .draw_text(
+            name,
+            Font::BizUDPGothicBold,
+            &theme.inset_label_text,
+            Scale::uniform(NAME_TEXT_SCALE),
+            Offset::new(
+                Origin::LeftUp,
+                Origin::LeftUp,
+                (rect.left + theme.inset_border_width + NAME_PADDING).round() as i32,
+                (rect.top + theme.inset_border_width + NAME_PADDING).round() as i32,
+            ),
+            frame_context.image_size.into(),
+            resources,
+            facade,
+            frame_context.surface.borrow_mut().deref_mut(),
+            draw_parameters,
+        )
+}
+
+/// 差し込み図の名前が画面上で占める領域
+pub fn name_rect<F: ?Sized + Facade, S: ?Sized + Surface>(
+    frame_context: &FrameContext<F, S>,
+    name: &str,
+) -> ScreenRect {
+    let rect = frame_context.viewport;
+    let (width, height) = frame_context.font_manager.borrow().measure_text(
+        name,
+        Font::BizUDPGothicBold,
+        Scale::uniform(NAME_TEXT_SCALE),
+    );
+    let left = rect.left + frame_context.theme.inset_border_width + NAME_PADDING;
+    let top = rect.top + frame_context.theme.inset_border_width + NAME_PADDING;
+
+    ScreenRect {
+        left,
+        top,
+        right: left + width,
+        bottom: top + height,
+    }
+}
+
+/// 枠の外周と内周を交互に結んで、一周する帯にする。
+fn calculate_border_vertices(
+    rect: ScreenRect,
+    width: f32,
+    image_size: Size<u32>,
+) -> [ShapeVertex; 10] {
+    let image_size = image_size.to_f32();
+    let to_screen = |x: f32, y: f32| ShapeVertex {
+        position: [
+            x / image_size.x() * 2.0 - 1.0,
+            1.0 - y / image_size.y() * 2.0,
+        ],
+    };
+
+    let outer = [
+        (rect.left, rect.top),
+        (rect.right, rect.top),
+        (rect.right, rect.bottom),
+        (rect.left, rect.bottom),
+    ];
+    let inner = [
+        (rect.left + width, rect.top + width),
+        (rect.right - width, rect.top + width),
+        (rect.right - width, rect.bottom - width),
+        (rect.left + width, rect.bottom - width),
+    ];
+
+    let mut vertices = [ShapeVertex { position: [0.0; 2] }; 10];
+    for i in 0..5 {
+        let (outer, inner) = (outer[i % 4], inner[i % 4]);
+        vertices[i * 2] = to_screen(outer.0, outer.1);
+        vertices[i * 2 + 1] = to_screen(inner.0, inner.1);
+    }
+
+    vertices
+}
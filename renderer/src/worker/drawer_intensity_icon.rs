@@ -1,3 +1,5 @@
+use crate::model::RenderingError;
+use crate::worker::collision::ScreenRect;
 use crate::worker::vertex::{IntensityIconUniform, IntensityIconVertex};
 use crate::worker::FrameContext;
 use array_const_fn_init::array_const_fn_init;
@@ -28,49 +30,62 @@ const 震度_TO_UV_OFFSET: [[f32; 2]; 9] = array_const_fn_init![震度_to_uv_off
 pub fn draw_all<F: ?Sized + Facade, S: ?Sized + Surface>(
     frame_context: &FrameContext<F, S>,
     earthquake_payload: &crate::frame_context::EarthquakePayload,
-) {
+) -> Result<(), RenderingError> {
     let facade = frame_context.facade;
     let resources = frame_context.resources;
     let aspect_ratio = frame_context.image_size.aspect_ratio();
-    let offset = frame_context.offset;
-    let scale = frame_context.scale;
-    let draw_parameters = frame_context.draw_parameters;
+    let offset = frame_context.frame.offset;
+    let scale = frame_context.frame.scale;
+    let draw_parameters = &frame_context.draw_parameters;
 
-    let per_icon_data: Vec<_> = earthquake_payload
+    let per_icon_data = earthquake_payload
         .area_intensities
         .iter()
         .flat_map(|(震度, area_codes)| {
             let uv_offset = &震度_TO_UV_OFFSET[震度 as usize];
 
-            area_codes.iter().filter_map(|code| {
+            area_codes.iter().map(|code| {
                 let nearest_station_coord =
-                    renderer_assets::QueryInterface::query_rendering_center_by_area(*code)?;
+                    renderer_assets::QueryInterface::query_rendering_center_by_area(*code)
+                        .ok_or(RenderingError::UnknownEarthquakeArea(*code))?;
 
-                Some(IntensityIconVertex {
-                    position: nearest_station_coord.into(),
+                Ok(IntensityIconVertex {
+                    position: frame_context.unwrap(nearest_station_coord).into(),
                     uv_offset: uv_offset.to_owned(),
                 })
             })
         })
-        .collect();
+        .collect::<Result<Vec<_>, RenderingError>>()?;
 
-    let per_icon_data = VertexBuffer::dynamic(facade, &per_icon_data).unwrap();
+    let per_icon_data = VertexBuffer::dynamic(facade, &per_icon_data)?;
 
-    resources
-        .shader
-        .intensity_icon
-        .draw(
-            frame_context.surface.borrow_mut().deref_mut(),
-            &per_icon_data,
-            NoIndices(PrimitiveType::Points),
-            &IntensityIconUniform {
-                aspect_ratio,
-                offset: offset.into(),
-                zoom: scale,
-                icon_ratio_in_y_axis: super::ICON_RATIO_IN_Y_AXIS,
-                texture_sampler: &resources.texture.intensity,
-            },
-            draw_parameters,
-        )
-        .unwrap();
+    resources.shader.intensity_icon.draw(
+        frame_context.surface.borrow_mut().deref_mut(),
+        &per_icon_data,
+        NoIndices(PrimitiveType::Points),
+        &IntensityIconUniform {
+            aspect_ratio,
+            offset: offset.into(),
+            zoom: scale,
+            projection: frame_context.projection_uniform(),
+            icon_ratio_in_y_axis: super::ICON_RATIO_IN_Y_AXIS,
+            texture_sampler: &resources.texture.intensity,
+        },
+        draw_parameters,
+    )?;
+
+    Ok(())
+}
+
+pub fn occupied_rects<F: ?Sized + Facade, S: ?Sized + Surface>(
+    frame_context: &FrameContext<F, S>,
+    earthquake_payload: &crate::frame_context::EarthquakePayload,
+) -> Vec<ScreenRect> {
+    earthquake_payload
+        .area_intensities
+        .values()
+        .flatten()
+        .filter_map(|code| renderer_assets::QueryInterface::query_rendering_center_by_area(*code))
+        .map(|center| frame_context.icon_rect(center))
+        .collect()
 }
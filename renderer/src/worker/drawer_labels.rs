@@ -0,0 +1,160 @@
+use crate::frame_context::HasEpicenter;
+use crate::model::RenderingError;
+use crate::place_names::{self, Script};
+use crate::worker::collision::{CollisionMap, ScreenRect};
+use crate::worker::fonts::{Font, Offset, Origin, TextStyle};
+use crate::worker::FrameContext;
+use glium::backend::Facade;
+use glium::Surface;
+use renderer_assets::{PlaceLabel, QueryInterface};
+use rusttype::Scale;
+use std::ops::DerefMut;
+
+const HYPOCENTER_LABEL_SCALE: f32 = 20.0;
+const PREFECTURE_LABEL_SCALE: f32 = 18.0;
+const AREA_LABEL_SCALE: f32 = 13.0;
+/// この倍率以上に拡大している場合のみ細分区域名を表示する
+const AREA_LABEL_MIN_SCALE: f32 = 30.0;
+/// ラベル同士が詰まりすぎないように取る余白
+const LABEL_PADDING: f32 = 3.0;
+
+/// 震央付近の地名と、都道府県・細分区域の地名を描画する。
+/// 優先度の高いものから順に、obstaclesや既に置いたラベルと重ならない位置を探して配置し、
+/// どこにも置けないものは表示しない。
+pub fn draw<F: ?Sized + Facade, S: ?Sized + Surface, C: HasEpicenter>(
+    frame_context: &FrameContext<F, S>,
+    rendering_context: &C,
+    obstacles: &[ScreenRect],
+) -> Result<(), RenderingError> {
+    let theme = frame_context.theme;
+
+    let mut collision_map = CollisionMap::default();
+    obstacles
+        .iter()
+        .for_each(|rect| collision_map.reserve(*rect));
+
+    // 震央の地名は震央の印に添えて、他の地名より先に置く
+    for epicenter in rendering_context.epicenter() {
+        let Some(name) = place_names::hypocenter(*epicenter, Script::Japanese) else {
+            continue;
+        };
+        let icon = frame_context.icon_rect(*epicenter);
+        let center = icon.center();
+        let (icon_width, icon_height) = (icon.right - icon.left, icon.bottom - icon.top);
+
+        draw_label(
+            frame_context,
+            &mut collision_map,
+            &name,
+            Scale::uniform(HYPOCENTER_LABEL_SCALE),
+            &theme.hypocenter_label_text,
+            |(width, height)| {
+                [
+                    ((icon_width + width) / 2.0, 0.0),
+                    (-(icon_width + width) / 2.0, 0.0),
+                    (0.0, -(icon_height + height) / 2.0),
+                    (0.0, (icon_height + height) / 2.0),
+                ]
+                .map(|(dx, dy)| (center.0 + dx, center.1 + dy))
+                .to_vec()
+            },
+        )?;
+    }
+
+    let prefecture_labels = QueryInterface::prefecture_labels().map(|label| {
+        (
+            label,
+            Scale::uniform(PREFECTURE_LABEL_SCALE),
+            &theme.prefecture_label_text,
+        )
+    });
+
+    let area_labels = QueryInterface::area_labels()
+        .filter(|_| frame_context.frame.scale >= AREA_LABEL_MIN_SCALE)
+        .map(|label| {
+            (
+                label,
+                Scale::uniform(AREA_LABEL_SCALE),
+                &theme.area_label_text,
+            )
+        });
+
+    for (label, scale, style) in prefecture_labels.chain(area_labels) {
+        let PlaceLabel { name, position, .. } = label;
+        let anchor = frame_context.geo_to_pixel(position);
+
+        // 基準点の上に置けなければ、上下左右にずらして試す
+        draw_label(
+            frame_context,
+            &mut collision_map,
+            name,
+            scale,
+            style,
+            |(width, height)| {
+                [
+                    (0.0, 0.0),
+                    (0.0, -height),
+                    (0.0, height),
+                    (width, 0.0),
+                    (-width, 0.0),
+                ]
+                .map(|(dx, dy)| (anchor.0 + dx, anchor.1 + dy))
+                .to_vec()
+            },
+        )?;
+    }
+
+    Ok(())
+}
+
+/// candidatesに余白込みのラベルの大きさを渡して得られる中心の候補を順に試し、
+/// 最初に置けた位置に描く。
+fn draw_label<F: ?Sized + Facade, S: ?Sized + Surface>(
+    frame_context: &FrameContext<F, S>,
+    collision_map: &mut CollisionMap,
+    text: &str,
+    scale: Scale,
+    style: &TextStyle,
+    candidates: impl FnOnce((f32, f32)) -> Vec<(f32, f32)>,
+) -> Result<(), RenderingError> {
+    let font = Font::BizUDPGothicBold;
+
+    let (width, height) = frame_context
+        .font_manager
+        .borrow()
+        .measure_text(text, font, scale);
+    let size = (width + LABEL_PADDING * 2.0, height + LABEL_PADDING * 2.0);
+
+    let placed = candidates(size)
+        .into_iter()
+        .map(|center| ScreenRect::from_center(center, size))
+        .find(|rect| rect.is_inside(&frame_context.viewport) && collision_map.try_place(*rect));
+
+    let Some(rect) = placed else {
+        return Ok(());
+    };
+
+    let center = rect.center();
+
+    frame_context
+        .font_manager
+        .borrow_mut()
+        .deref_mut()
+        .draw_text(
+            text,
+            font,
+            style,
+            scale,
+            Offset::new(
+                Origin::LeftUp,
+                Origin::Center,
+                center.0.round() as i32,
+                center.1.round() as i32,
+            ),
+            frame_context.image_size.into(),
+            frame_context.resources,
+            frame_context.facade,
+            frame_context.surface.borrow_mut().deref_mut(),
+            &frame_context.draw_parameters,
+        )
+}
@@ -1,94 +1,112 @@
+use crate::model::RenderingError;
 use crate::worker::vertex::{BorderLineUniform, MapUniform};
 use crate::worker::FrameContext;
 use glium::backend::Facade;
 use glium::Surface;
+use renderer_types::lod::DIMENSION;
 use std::ops::DerefMut;
 
 pub fn draw<F: ?Sized + Facade, S: ?Sized + Surface>(
     frame_context: &FrameContext<F, S>,
     has_saibunkuiki: bool,
-) {
+    has_world: bool,
+) -> Result<(), RenderingError> {
     let theme = frame_context.theme;
-    let params = frame_context.draw_parameters;
+    let params = &frame_context.draw_parameters;
     let resources = frame_context.resources;
-    let scale = frame_context.scale;
+    let scale = frame_context.frame.scale;
+    // LODは画像の幅がDIMENSIONのときの拡大率で決めてあるので、差し込み図などの小さな画像では換算する
+    let lod_scale = scale * frame_context.image_size.x() as f32 / DIMENSION.0 as f32;
     let aspect_ratio = frame_context.image_size.aspect_ratio();
-    let offset = frame_context.offset.into();
     let image_size: [f32; 2] = frame_context.image_size.to_f32().into();
 
-    resources
-        .shader
-        .map
-        .draw(
-            frame_context.surface.borrow_mut().deref_mut(),
-            &resources.buffer.map_vertex,
-            &resources.buffer.map,
-            &MapUniform {
-                aspect_ratio,
-                offset,
-                zoom: scale,
-                color: theme.ground_color,
-            },
-            params,
+    let world = if has_world {
+        let world = resources
+            .buffer
+            .get_world_by_scale(lod_scale)
+            .ok_or(RenderingError::NoLodLevel(lod_scale))?;
+        Some(world).filter(|world| world.len() > 0)
+    } else {
+        None
+    };
+    let map = resources
+        .buffer
+        .get_map_by_scale(lod_scale)
+        .ok_or(RenderingError::NoLodLevel(lod_scale))?;
+    let area_line = if has_saibunkuiki {
+        Some(
+            resources
+                .buffer
+                .get_area_line_by_scale(lod_scale)
+                .ok_or(RenderingError::NoLodLevel(lod_scale))?,
         )
-        .unwrap();
+    } else {
+        None
+    };
+    let pref_line = resources
+        .buffer
+        .get_pref_line_by_scale(lod_scale)
+        .ok_or(RenderingError::NoLodLevel(lod_scale))?;
+
+    for offset in frame_context.wrapped_offsets() {
+        let land_uniform = MapUniform {
+            aspect_ratio,
+            offset,
+            zoom: scale,
+            projection: frame_context.projection_uniform(),
+            color: theme.ground_color,
+        };
+
+        if let Some(world) = world {
+            resources.shader.map.draw(
+                frame_context.surface.borrow_mut().deref_mut(),
+                &resources.buffer.world_vertex,
+                world,
+                &land_uniform,
+                params,
+            )?;
+        }
 
-    resources
-        .shader
-        .map
-        .draw(
+        resources.shader.map.draw(
             frame_context.surface.borrow_mut().deref_mut(),
-            &resources.lake.vertex,
-            &resources.lake.index,
-            &MapUniform {
-                aspect_ratio,
-                offset,
-                zoom: scale,
-                color: [
-                    theme.clear_color[0],
-                    theme.clear_color[1],
-                    theme.clear_color[2],
-                ],
-            },
+            &resources.buffer.map_vertex,
+            map,
+            &land_uniform,
             params,
-        )
-        .unwrap();
+        )?;
 
-    if has_saibunkuiki {
-        resources
-            .shader
-            .border_line
-            .draw(
+        if let Some(area_line) = area_line {
+            resources.shader.border_line.draw(
                 frame_context.surface.borrow_mut().deref_mut(),
                 &resources.buffer.map_vertex,
-                resources.buffer.get_area_line_by_scale(scale).unwrap(),
+                area_line,
                 &BorderLineUniform {
                     dimension: image_size,
                     offset,
                     zoom: scale,
+                    projection: frame_context.projection_uniform(),
                     line_width: theme.area_border_width,
                     color: theme.area_border_color,
                 },
                 params,
-            )
-            .unwrap();
-    }
+            )?;
+        }
 
-    resources
-        .shader
-        .border_line
-        .draw(
+        resources.shader.border_line.draw(
             frame_context.surface.borrow_mut().deref_mut(),
             &resources.buffer.map_vertex,
-            resources.buffer.get_pref_line_by_scale(scale).unwrap(),
+            pref_line,
             &BorderLineUniform {
                 dimension: image_size,
                 offset,
                 zoom: scale,
+                projection: frame_context.projection_uniform(),
                 line_width: theme.prefectural_border_width,
                 color: theme.prefectural_border_color,
             },
             params,
-        )
-        .unwrap();
+        )?;
+    }
+
+    Ok(())
 }
@@ -1,5 +1,7 @@
 use super::vertex::{TexturedUniform, TexturedVertex};
 use crate::frame_context::HasTime;
+use crate::model::RenderingError;
+use crate::worker::collision::ScreenRect;
 use crate::worker::fonts::{Font, Offset, Origin};
 use crate::worker::FrameContext;
 use chrono_tz::Tz::Japan;
@@ -13,14 +15,18 @@ use std::ops::DerefMut;
 const OVERLAY_OFFSET_PIXELS: u16 = 10;
 const RIGHTS_NOTATION_RATIO_IN_Y_AXIS: f32 = 0.16;
 const WATERMARK_RATIO_IN_Y_AXIS: f32 = 0.12;
+const TIME_TEXT_OFFSET: (i32, i32) = (-20, -20);
+const TIME_TEXT_SCALE: f32 = 20.0; // TODO: calculate from dimension
+/// 時刻の数字によって文字列の幅が変わらないよう、等幅のフォントで描く
+const TIME_FONT: Font = Font::BizUDGothicBold;
 
 pub fn draw<F: ?Sized + Facade, S: ?Sized + Surface, C: HasTime>(
     frame_context: &FrameContext<F, S>,
     rendering_context: &C,
-) {
+) -> Result<(), RenderingError> {
     let facade = frame_context.facade;
     let resources = frame_context.resources;
-    let draw_parameters = frame_context.draw_parameters;
+    let draw_parameters = &frame_context.draw_parameters;
     let theme = frame_context.theme;
 
     let rights_position = calculate_rights_notation_position(frame_context.image_size);
@@ -62,46 +68,81 @@ pub fn draw<F: ?Sized + Facade, S: ?Sized + Surface, C: HasTime>(
     ];
     let indices = [0_u32, 1, 2, 3, 3, 4, 4, 5, 6, 7];
 
-    let vertex_buffer = VertexBuffer::dynamic(facade, &vertices).unwrap();
-    let index_buffer =
-        IndexBuffer::dynamic(facade, PrimitiveType::TriangleStrip, &indices).unwrap();
+    let vertex_buffer = VertexBuffer::dynamic(facade, &vertices)?;
+    let index_buffer = IndexBuffer::dynamic(facade, PrimitiveType::TriangleStrip, &indices)?;
 
-    resources
-        .shader
-        .textured
-        .draw(
-            frame_context.surface.borrow_mut().deref_mut(),
-            &vertex_buffer,
-            &index_buffer,
-            &TexturedUniform {
-                texture_sampler: &resources.texture.overlay,
-            },
-            draw_parameters,
-        )
-        .unwrap();
+    resources.shader.textured.draw(
+        frame_context.surface.borrow_mut().deref_mut(),
+        &vertex_buffer,
+        &index_buffer,
+        &TexturedUniform {
+            texture_sampler: &resources.texture.overlay,
+        },
+        draw_parameters,
+    )?;
 
-    let mut time_text = rendering_context
-        .time()
-        .with_timezone(&Japan)
-        .format("%Y年%m月%d日 %H時%M分頃")
-        .to_string();
-    time_text.push_str(&rendering_context.time_kind().to_string());
+    let time_text = time_text(rendering_context);
     frame_context
         .font_manager
         .borrow_mut()
         .deref_mut()
         .draw_text(
             &time_text,
-            Font::BizUDPGothicBold,
-            theme.occurrence_time_color,
-            Scale::uniform(20.0), // TODO: calculate from dimension
-            Offset::new(Origin::RightDown, Origin::RightDown, -20, -20),
+            TIME_FONT,
+            &theme.occurrence_time_text,
+            Scale::uniform(TIME_TEXT_SCALE),
+            Offset::new(
+                Origin::RightDown,
+                Origin::RightDown,
+                TIME_TEXT_OFFSET.0,
+                TIME_TEXT_OFFSET.1,
+            ),
             frame_context.image_size.into(),
             resources,
             facade,
             frame_context.surface.borrow_mut().deref_mut(),
             draw_parameters,
-        );
+        )
+}
+
+/// 権利表記・透かし・発生時刻が画面上で占める領域
+pub fn occupied_rects<F: ?Sized + Facade, S: ?Sized + Surface, C: HasTime>(
+    frame_context: &FrameContext<F, S>,
+    rendering_context: &C,
+) -> Vec<ScreenRect> {
+    let image_size = frame_context.image_size;
+
+    let (text_width, text_height) = frame_context.font_manager.borrow().measure_text(
+        &time_text(rendering_context),
+        TIME_FONT,
+        Scale::uniform(TIME_TEXT_SCALE),
+    );
+    let text_right = image_size.x() as f32 + TIME_TEXT_OFFSET.0 as f32;
+    let text_bottom = image_size.y() as f32 + TIME_TEXT_OFFSET.1 as f32;
+
+    vec![
+        ScreenRect::from_screen_vertices(
+            &calculate_rights_notation_position(image_size),
+            image_size,
+        ),
+        ScreenRect::from_screen_vertices(&calculate_watermark_position(image_size), image_size),
+        ScreenRect {
+            left: text_right - text_width,
+            top: text_bottom - text_height,
+            right: text_right,
+            bottom: text_bottom,
+        },
+    ]
+}
+
+fn time_text<C: HasTime>(rendering_context: &C) -> String {
+    let mut time_text = rendering_context
+        .time()
+        .with_timezone(&Japan)
+        .format("%Y年%m月%d日 %H時%M分頃")
+        .to_string();
+    time_text.push_str(&rendering_context.time_kind().to_string());
+    time_text
 }
 
 fn calculate_rights_notation_position(image_size: Size<u32>) -> [[f32; 2]; 4] {
@@ -1,4 +1,5 @@
 use crate::model::{津波情報, RenderingError};
+use crate::worker::collision::ScreenRect;
 use crate::worker::fonts::{Font, Offset, Origin};
 use crate::worker::vertex::{ShapeUniform, ShapeVertex};
 use crate::worker::FrameContext;
@@ -18,72 +19,107 @@ where
 {
     let facade = frame_context.facade;
     let resources = frame_context.resources;
-    let draw_parameters = frame_context.draw_parameters;
+    let draw_parameters = &frame_context.draw_parameters;
     let theme = frame_context.theme;
 
-    let mut forecast_levels = tsunami_payload.forecast_levels().iter().fold(
-        Vec::<津波情報>::new(),
-        |mut v, (level, entries)| {
-            if !entries.is_empty() {
-                v.push(level)
+    for (i, (forecast_level, count)) in visible_forecast_levels(tsunami_payload)
+        .into_iter()
+        .enumerate()
+    {
+        let (shape, text_origin) = calculate_legend_position(frame_context.image_size, i);
+        let shape = VertexBuffer::dynamic(facade, &shape)?;
+
+        let color = theme.tsunami_colors.of(forecast_level);
+        let color = [color[0], color[1], color[2], 1.0];
+
+        frame_context.resources.shader.shape.draw(
+            frame_context.surface.borrow_mut().deref_mut(),
+            &shape,
+            NoIndices(PrimitiveType::TriangleStrip),
+            &ShapeUniform { color },
+            draw_parameters,
+        )?;
+
+        frame_context
+            .font_manager
+            .borrow_mut()
+            .deref_mut()
+            .draw_text(
+                &legend_text(forecast_level, count),
+                Font::BizUDPGothicBold,
+                &theme.tsunami_legend_text,
+                Scale::uniform(22.0),
+                Offset::new(
+                    Origin::RightDown,
+                    Origin::LeftUp,
+                    text_origin.0,
+                    text_origin.1,
+                ),
+                frame_context.image_size.into(),
+                resources,
+                facade,
+                frame_context.surface.borrow_mut().deref_mut(),
+                draw_parameters,
+            )?;
+    }
+
+    Ok(())
+}
+
+/// 凡例の各行(色見本と文字)が画面上で占める領域
+pub fn occupied_rects<F: ?Sized + Facade, S: ?Sized + Surface, T>(
+    frame_context: &FrameContext<F, S>,
+    tsunami_payload: &T,
+) -> Vec<ScreenRect>
+where
+    T: crate::frame_context::HasTsunamiForecastLevels,
+{
+    let image_size = frame_context.image_size;
+    let font_manager = frame_context.font_manager.borrow();
+
+    visible_forecast_levels(tsunami_payload)
+        .into_iter()
+        .enumerate()
+        .map(|(i, (forecast_level, count))| {
+            let (shape, text_origin) = calculate_legend_position(image_size, i);
+            let shape =
+                ScreenRect::from_screen_vertices(&shape.map(|vertex| vertex.position), image_size);
+
+            let (text_width, text_height) = font_manager.measure_text(
+                &legend_text(forecast_level, count),
+                Font::BizUDPGothicBold,
+                Scale::uniform(22.0),
+            );
+            let text_left = image_size.x() as f32 + text_origin.0 as f32;
+            let text_top = image_size.y() as f32 + text_origin.1 as f32;
+
+            ScreenRect {
+                left: shape.left,
+                top: shape.top.min(text_top),
+                right: text_left + text_width,
+                bottom: shape.bottom.max(text_top + text_height),
             }
-            v
-        },
-    );
-    forecast_levels.sort();
+        })
+        .collect()
+}
 
-    forecast_levels
+/// 発表されている区域がある情報の種類と区域の数を、弱いものから順に返す。
+fn visible_forecast_levels<T>(tsunami_payload: &T) -> Vec<(津波情報, usize)>
+where
+    T: crate::frame_context::HasTsunamiForecastLevels,
+{
+    let mut forecast_levels = tsunami_payload
+        .forecast_levels()
         .iter()
-        .enumerate()
-        .for_each(|(i, forecast_level)| {
-            let (shape, text_origin) = calculate_legend_position(frame_context.image_size, i);
-            let shape = VertexBuffer::dynamic(facade, &shape).unwrap();
-
-            let color = match forecast_level {
-                津波情報::津波予報 => theme.tsunami_colors.forecast,
-                津波情報::津波注意報 => theme.tsunami_colors.advisory,
-                津波情報::津波警報 => theme.tsunami_colors.warning,
-                津波情報::大津波警報 => theme.tsunami_colors.major_warning,
-            };
-            let color = [color[0], color[1], color[2], 1.0];
-
-            frame_context
-                .resources
-                .shader
-                .shape
-                .draw(
-                    frame_context.surface.borrow_mut().deref_mut(),
-                    &shape,
-                    NoIndices(PrimitiveType::TriangleStrip),
-                    &ShapeUniform { color },
-                    draw_parameters,
-                )
-                .unwrap();
-
-            frame_context
-                .font_manager
-                .borrow_mut()
-                .deref_mut()
-                .draw_text(
-                    &forecast_level.to_string(),
-                    Font::BizUDPGothicBold,
-                    theme.tsunami_legend_color,
-                    Scale::uniform(22.0),
-                    Offset::new(
-                        Origin::RightDown,
-                        Origin::LeftUp,
-                        text_origin.0,
-                        text_origin.1,
-                    ),
-                    frame_context.image_size.into(),
-                    resources,
-                    facade,
-                    frame_context.surface.borrow_mut().deref_mut(),
-                    draw_parameters,
-                );
-        });
+        .filter(|(_, entries)| !entries.is_empty())
+        .map(|(level, entries)| (level, entries.len()))
+        .collect::<Vec<_>>();
+    forecast_levels.sort();
+    forecast_levels
+}
 
-    Ok(())
+fn legend_text(forecast_level: 津波情報, count: usize) -> String {
+    format!("{forecast_level} {count}区域")
 }
 
 fn calculate_legend_position(
@@ -1,3 +1,4 @@
+use crate::model::RenderingError;
 use crate::worker::vertex::TsunamiUniform;
 use crate::worker::FrameContext;
 use glium::backend::Facade;
@@ -12,14 +13,15 @@ use std::ops::DerefMut;
 pub fn draw<F: ?Sized + Facade, S: ?Sized + Surface, T>(
     frame_context: &FrameContext<F, S>,
     tsunami_payload: &T,
-) where
+    blink_frame: Option<usize>,
+) -> Result<(), RenderingError>
+where
     T: crate::frame_context::HasTsunamiForecastLevels,
 {
     let facade = frame_context.facade;
     let resources = frame_context.resources;
-    let offset = frame_context.offset;
-    let scale = frame_context.scale;
-    let draw_parameters = frame_context.draw_parameters;
+    let scale = frame_context.frame.scale;
+    let draw_parameters = &frame_context.draw_parameters;
     let theme = frame_context.theme;
 
     let area_code_count = QueryInterface::tsunami_area_code_count();
@@ -27,16 +29,19 @@ pub fn draw<F: ?Sized + Facade, S: ?Sized + Surface, T>(
 
     let mut levels = vec![0_u8; area_code_count];
 
-    tsunami_payload
-        .forecast_levels()
-        .iter()
-        .for_each(|(level, areas)| {
-            areas.iter().for_each(|area| {
-                levels
-                    [QueryInterface::tsunami_area_code_to_internal_code(*area).unwrap() as usize] =
-                    level as u8
-            })
-        });
+    for (level, areas) in tsunami_payload.forecast_levels().iter() {
+        // 消灯しているフレームでは、線の無い区域と同じ扱いにする
+        if !theme.tsunami_animation.is_lit(level, blink_frame) {
+            continue;
+        }
+
+        for area in areas {
+            let internal_code = QueryInterface::tsunami_area_code_to_internal_code(*area)
+                .ok_or(RenderingError::UnknownTsunamiArea(*area))?;
+
+            levels[internal_code as usize] = level as u8;
+        }
+    }
 
     // println!("{:?}", levels);
     let levels = RawImage1d {
@@ -49,25 +54,29 @@ pub fn draw<F: ?Sized + Facade, S: ?Sized + Surface, T>(
         levels,
         UncompressedUintFormat::U8,
         MipmapsOption::NoMipmap,
-    )
-    .unwrap();
+    )?;
 
-    resources
-        .shader
-        .tsunami
-        .draw(
+    let mut uniform = TsunamiUniform {
+        dimension: frame_context.image_size.to_f32().into(),
+        offset: frame_context.frame.offset.into(),
+        zoom: scale,
+        projection: frame_context.projection_uniform(),
+        colors: theme.tsunami_colors,
+        levels,
+        line_width: theme.tsunami_width,
+    };
+
+    for offset in frame_context.wrapped_offsets() {
+        uniform.offset = offset;
+
+        resources.shader.tsunami.draw(
             frame_context.surface.borrow_mut().deref_mut(),
             &frame_context.resources.buffer.tsunami_vertex,
             &frame_context.resources.buffer.tsunami_indices,
-            &TsunamiUniform {
-                dimension: frame_context.image_size.to_f32().into(),
-                offset: offset.into(),
-                zoom: scale,
-                colors: theme.tsunami_colors,
-                levels,
-                line_width: theme.tsunami_width,
-            },
+            &uniform,
             draw_parameters,
-        )
-        .unwrap();
+        )?;
+    }
+
+    Ok(())
 }
@@ -0,0 +1,132 @@
+use crate::frame_context::HasTsunamiForecastLevels;
+use crate::model::RenderingError;
+use crate::worker::collision::ScreenRect;
+use crate::worker::vertex::{ShapeUniform, ShapeVertex};
+use crate::worker::FrameContext;
+use glium::backend::Facade;
+use glium::index::{NoIndices, PrimitiveType};
+use glium::{Surface, VertexBuffer};
+use renderer_assets::QueryInterface;
+use renderer_types::Size;
+use std::ops::DerefMut;
+
+/// 印の円を近似する多角形の頂点数
+const MARKER_SEGMENTS: usize = 24;
+
+/// 線として見えないほど小さい津波予報区を、海岸線の重心に縁取り付きの円で描く。
+/// 重い情報の印が上に来るように、軽い情報から順に描く。
+pub fn draw<F: ?Sized + Facade, S: ?Sized + Surface, T>(
+    frame_context: &FrameContext<F, S>,
+    tsunami_payload: &T,
+    blink_frame: Option<usize>,
+) -> Result<(), RenderingError>
+where
+    T: HasTsunamiForecastLevels,
+{
+    let theme = frame_context.theme;
+
+    for (level, areas) in tsunami_payload.forecast_levels().iter() {
+        if !theme.tsunami_animation.is_lit(level, blink_frame) {
+            continue;
+        }
+
+        let centers: Vec<_> = areas
+            .iter()
+            .filter_map(|area| QueryInterface::query_tsunami_marker_position(*area))
+            .map(|position| frame_context.geo_to_pixel(position))
+            .collect();
+
+        if centers.is_empty() {
+            continue;
+        }
+
+        draw_circles(
+            frame_context,
+            &centers,
+            theme.tsunami_marker_radius + theme.tsunami_marker_halo_width,
+            theme.tsunami_marker_halo_color,
+        )?;
+        draw_circles(
+            frame_context,
+            &centers,
+            theme.tsunami_marker_radius,
+            theme.tsunami_colors.of(level),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// 印が画面上で占める領域。どのフレームでも同じ配置になるよう、点滅に関わらず全ての印を返す。
+pub fn occupied_rects<F: ?Sized + Facade, S: ?Sized + Surface, T>(
+    frame_context: &FrameContext<F, S>,
+    tsunami_payload: &T,
+) -> Vec<ScreenRect>
+where
+    T: HasTsunamiForecastLevels,
+{
+    let theme = frame_context.theme;
+    let diameter = (theme.tsunami_marker_radius + theme.tsunami_marker_halo_width) * 2.0;
+
+    tsunami_payload
+        .forecast_levels()
+        .values()
+        .flatten()
+        .filter_map(|area| QueryInterface::query_tsunami_marker_position(*area))
+        .map(|position| {
+            ScreenRect::from_center(frame_context.geo_to_pixel(position), (diameter, diameter))
+        })
+        .collect()
+}
+
+fn draw_circles<F: ?Sized + Facade, S: ?Sized + Surface>(
+    frame_context: &FrameContext<F, S>,
+    centers: &[(f32, f32)],
+    radius: f32,
+    color: [f32; 3],
+) -> Result<(), RenderingError> {
+    let vertices = centers
+        .iter()
+        .flat_map(|center| calculate_circle_vertices(*center, radius, frame_context.image_size))
+        .collect::<Vec<_>>();
+    let vertices = VertexBuffer::dynamic(frame_context.facade, &vertices)?;
+
+    frame_context.resources.shader.shape.draw(
+        frame_context.surface.borrow_mut().deref_mut(),
+        &vertices,
+        NoIndices(PrimitiveType::TrianglesList),
+        &ShapeUniform {
+            color: [color[0], color[1], color[2], 1.0],
+        },
+        &frame_context.draw_parameters,
+    )?;
+
+    Ok(())
+}
+
+/// 中心と円周上の隣り合う2点を結んだ三角形を並べて、円を塗りつぶす。
+fn calculate_circle_vertices(
+    center: (f32, f32),
+    radius: f32,
+    image_size: Size<u32>,
+) -> Vec<ShapeVertex> {
+    let image_size = image_size.to_f32();
+    let to_screen = |x: f32, y: f32| ShapeVertex {
+        position: [
+            x / image_size.x() * 2.0 - 1.0,
+            1.0 - y / image_size.y() * 2.0,
+        ],
+    };
+
+    let rim = |i: usize| {
+        let angle = std::f32::consts::TAU * i as f32 / MARKER_SEGMENTS as f32;
+        to_screen(
+            center.0 + radius * angle.cos(),
+            center.1 + radius * angle.sin(),
+        )
+    };
+
+    (0..MARKER_SEGMENTS)
+        .flat_map(|i| [to_screen(center.0, center.1), rim(i), rim(i + 1)])
+        .collect()
+}
@@ -0,0 +1,351 @@
+use crate::frame_context::HasTsunamiForecastLevels;
+use crate::model::{津波情報, RenderingError};
+use crate::worker::collision::ScreenRect;
+use crate::worker::fonts::{Font, Offset, Origin};
+use crate::worker::vertex::{ShapeUniform, ShapeVertex};
+use crate::worker::FrameContext;
+use glium::backend::Facade;
+use glium::index::{NoIndices, PrimitiveType};
+use glium::{Surface, VertexBuffer};
+use renderer_assets::QueryInterface;
+use renderer_types::Size;
+use rusttype::Scale;
+use std::ops::DerefMut;
+
+/// 一覧の幅の、画像の幅に対する比
+const SIDEBAR_RATIO_IN_X_AXIS: f32 = 0.24;
+/// 一覧と画像の端や、権利表記などとの間隔
+const SIDEBAR_MARGIN_PIXELS: f32 = 10.0;
+/// 一覧の枠の内側の余白
+const SIDEBAR_PADDING_PIXELS: f32 = 8.0;
+const HEADER_TEXT_SCALE: f32 = 20.0;
+const HEADER_ROW_PIXELS: f32 = 28.0;
+const NAME_TEXT_SCALE: f32 = 15.0;
+const NAME_ROW_PIXELS: f32 = 19.0;
+/// 見出しの色見本の大きさ
+const SWATCH_PIXELS: (f32, f32) = (24.0, 12.0);
+/// 区域名を見出しより字下げする幅
+const NAME_INDENT_PIXELS: f32 = 12.0;
+
+/// 一覧の1行
+#[derive(Clone, Debug, PartialEq)]
+enum Row<'a> {
+    /// 情報の種類と、その区域の数
+    Header(津波情報, usize),
+    Name(&'a str),
+    /// 入り切らずに省いた区域の数
+    Remainder(usize),
+}
+
+impl Row<'_> {
+    fn height(&self) -> f32 {
+        match self {
+            Row::Header(..) => HEADER_ROW_PIXELS,
+            Row::Name(_) | Row::Remainder(_) => NAME_ROW_PIXELS,
+        }
+    }
+}
+
+/// 配置の決まった一覧
+pub struct Sidebar {
+    rect: ScreenRect,
+    rows: Vec<Row<'static>>,
+}
+
+impl Sidebar {
+    pub fn occupied_rects(&self) -> Vec<ScreenRect> {
+        vec![self.rect]
+    }
+}
+
+/// 発表されている津波予報区の名前を、重い情報から順に種類ごとにまとめた一覧を画像の右端に置く。
+/// fixedには権利表記などの動かせないものを渡し、それらの上下の空いている範囲に収める。
+pub fn layout<F: ?Sized + Facade, S: ?Sized + Surface, T>(
+    frame_context: &FrameContext<F, S>,
+    tsunami_payload: &T,
+    fixed: &[ScreenRect],
+) -> Sidebar
+where
+    T: HasTsunamiForecastLevels,
+{
+    let column = calculate_column(frame_context.image_size, fixed);
+
+    let mut groups: Vec<_> = tsunami_payload
+        .forecast_levels()
+        .iter()
+        .filter(|(_, areas)| !areas.is_empty())
+        .map(|(level, areas)| {
+            let mut areas = areas.clone();
+            areas.sort();
+            let names = areas
+                .into_iter()
+                .filter_map(QueryInterface::query_tsunami_area_name)
+                .collect::<Vec<_>>();
+            (level, names)
+        })
+        .collect();
+    groups.sort_by_key(|(level, _)| std::cmp::Reverse(*level));
+
+    let rows = layout_rows(
+        &groups,
+        column.bottom - column.top - SIDEBAR_PADDING_PIXELS * 2.0,
+    );
+    let height = rows.iter().map(Row::height).sum::<f32>() + SIDEBAR_PADDING_PIXELS * 2.0;
+
+    Sidebar {
+        rect: ScreenRect {
+            bottom: column.top + height,
+            ..column
+        },
+        rows,
+    }
+}
+
+pub fn draw<F: ?Sized + Facade, S: ?Sized + Surface>(
+    frame_context: &FrameContext<F, S>,
+    sidebar: &Sidebar,
+) -> Result<(), RenderingError> {
+    let facade = frame_context.facade;
+    let resources = frame_context.resources;
+    let draw_parameters = &frame_context.draw_parameters;
+    let theme = frame_context.theme;
+    let image_size = frame_context.image_size;
+
+    let background = VertexBuffer::dynamic(facade, &rect_vertices(sidebar.rect, image_size))?;
+    resources.shader.shape.draw(
+        frame_context.surface.borrow_mut().deref_mut(),
+        &background,
+        NoIndices(PrimitiveType::TrianglesList),
+        &ShapeUniform {
+            color: theme.tsunami_sidebar_background,
+        },
+        draw_parameters,
+    )?;
+
+    let left = sidebar.rect.left + SIDEBAR_PADDING_PIXELS;
+    let mut top = sidebar.rect.top + SIDEBAR_PADDING_PIXELS;
+
+    for row in &sidebar.rows {
+        let (text, font, scale, x) = match row {
+            Row::Header(level, count) => {
+                let swatch = ScreenRect {
+                    left,
+                    top: top + (HEADER_ROW_PIXELS - SWATCH_PIXELS.1) / 2.0,
+                    right: left + SWATCH_PIXELS.0,
+                    bottom: top + (HEADER_ROW_PIXELS + SWATCH_PIXELS.1) / 2.0,
+                };
+                let swatch = VertexBuffer::dynamic(facade, &rect_vertices(swatch, image_size))?;
+                let color = theme.tsunami_colors.of(*level);
+
+                resources.shader.shape.draw(
+                    frame_context.surface.borrow_mut().deref_mut(),
+                    &swatch,
+                    NoIndices(PrimitiveType::TrianglesList),
+                    &ShapeUniform {
+                        color: [color[0], color[1], color[2], 1.0],
+                    },
+                    draw_parameters,
+                )?;
+
+                (
+                    format!("{level} {count}区域"),
+                    Font::BizUDPGothicBold,
+                    HEADER_TEXT_SCALE,
+                    left + SWATCH_PIXELS.0 + SIDEBAR_PADDING_PIXELS,
+                )
+            }
+            Row::Name(name) => (
+                name.to_string(),
+                Font::BizUDPGothicRegular,
+                NAME_TEXT_SCALE,
+                left + NAME_INDENT_PIXELS,
+            ),
+            Row::Remainder(count) => (
+                format!("他{count}区域"),
+                Font::BizUDPGothicRegular,
+                NAME_TEXT_SCALE,
+                left + NAME_INDENT_PIXELS,
+            ),
+        };
+
+        let (text_width, _) =
+            frame_context
+                .font_manager
+                .borrow()
+                .measure_text(&text, font, Scale::uniform(scale));
+        let width = sidebar.rect.right - SIDEBAR_PADDING_PIXELS - x;
+
+        frame_context
+            .font_manager
+            .borrow_mut()
+            .deref_mut()
+            .draw_text(
+                &text,
+                font,
+                &theme.tsunami_legend_text,
+                fitted_scale(scale, text_width, width),
+                Offset::new(
+                    Origin::LeftUp,
+                    Origin::LeftUp,
+                    x.round() as i32,
+                    (top + (row.height() - scale) / 2.0).round() as i32,
+                ),
+                image_size.into(),
+                resources,
+                facade,
+                frame_context.surface.borrow_mut().deref_mut(),
+                draw_parameters,
+            )?;
+
+        top += row.height();
+    }
+
+    Ok(())
+}
+
+/// 画像の右端の、fixedの上下の空いている範囲
+fn calculate_column(image_size: Size<u32>, fixed: &[ScreenRect]) -> ScreenRect {
+    let right = image_size.x() as f32 - SIDEBAR_MARGIN_PIXELS;
+    let mut column = ScreenRect {
+        left: right - SIDEBAR_RATIO_IN_X_AXIS * image_size.x() as f32,
+        top: SIDEBAR_MARGIN_PIXELS,
+        right,
+        bottom: image_size.y() as f32 - SIDEBAR_MARGIN_PIXELS,
+    };
+
+    let middle = image_size.y() as f32 / 2.0;
+    for obstacle in fixed.iter().filter(|rect| rect.intersects(&column)) {
+        if obstacle.center().1 < middle {
+            column.top = column.top.max(obstacle.bottom + SIDEBAR_MARGIN_PIXELS);
+        } else {
+            column.bottom = column.bottom.min(obstacle.top - SIDEBAR_MARGIN_PIXELS);
+        }
+    }
+
+    column
+}
+
+/// 見出しは必ず載せ、残りの高さに重い情報の区域名から詰める。
+/// 後の種類にも1行ずつは残しておき、入り切らない種類ではそれぞれ最後の1行を「他N区域」にする。
+fn layout_rows<'a>(groups: &[(津波情報, Vec<&'a str>)], height: f32) -> Vec<Row<'a>> {
+    let name_height = height - groups.len() as f32 * HEADER_ROW_PIXELS;
+    let mut name_rows = (name_height / NAME_ROW_PIXELS).floor().max(0.0) as usize;
+    let mut rows = Vec::new();
+
+    for (i, (level, names)) in groups.iter().enumerate() {
+        rows.push(Row::Header(*level, names.len()));
+
+        let later = groups[i + 1..]
+            .iter()
+            .filter(|(_, names)| !names.is_empty())
+            .count();
+        let available = name_rows.saturating_sub(later).max(name_rows.min(1));
+
+        if names.len() <= available {
+            rows.extend(names.iter().map(|name| Row::Name(name)));
+            name_rows -= names.len();
+        } else if available > 0 {
+            let shown = available - 1;
+            rows.extend(names[..shown].iter().map(|name| Row::Name(name)));
+            rows.push(Row::Remainder(names.len() - shown));
+            name_rows -= available;
+        }
+    }
+
+    rows
+}
+
+/// 幅text_widthの文字列が幅widthに収まるよう、はみ出す分だけ横方向に縮めた大きさ
+fn fitted_scale(size: f32, text_width: f32, width: f32) -> Scale {
+    let ratio = if text_width > width {
+        width.max(0.0) / text_width
+    } else {
+        1.0
+    };
+
+    Scale {
+        x: size * ratio,
+        y: size,
+    }
+}
+
+fn rect_vertices(rect: ScreenRect, image_size: Size<u32>) -> [ShapeVertex; 6] {
+    let image_size = image_size.to_f32();
+    let to_screen = |x: f32, y: f32| ShapeVertex {
+        position: [
+            x / image_size.x() * 2.0 - 1.0,
+            1.0 - y / image_size.y() * 2.0,
+        ],
+    };
+
+    [
+        to_screen(rect.left, rect.top),
+        to_screen(rect.right, rect.top),
+        to_screen(rect.left, rect.bottom),
+        to_screen(rect.right, rect.top),
+        to_screen(rect.right, rect.bottom),
+        to_screen(rect.left, rect.bottom),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::津波情報;
+    use crate::worker::drawer_tsunami_sidebar::*;
+
+    #[test]
+    fn test_layout_rows() {
+        let groups = [
+            (津波情報::大津波警報, vec!["岩手県", "宮城県", "福島県"]),
+            (津波情報::津波警報, vec!["青森県太平洋沿岸", "茨城県"]),
+        ];
+
+        // 全て入る
+        let rows = layout_rows(&groups, 1000.0);
+        assert_eq!(rows.len(), 7);
+
+        // 見出し2行と区域名3行分の高さでは、後の種類にも1行を残して重い方から詰め、
+        // 入り切らない種類ごとに残りを省く
+        let rows = layout_rows(&groups, HEADER_ROW_PIXELS * 2.0 + NAME_ROW_PIXELS * 3.0);
+        assert_eq!(
+            rows,
+            [
+                Row::Header(津波情報::大津波警報, 3),
+                Row::Name("岩手県"),
+                Row::Remainder(2),
+                Row::Header(津波情報::津波警報, 2),
+                Row::Remainder(2),
+            ]
+        );
+
+        let rows = layout_rows(&groups, HEADER_ROW_PIXELS * 2.0 + NAME_ROW_PIXELS * 4.0);
+        assert_eq!(
+            rows,
+            [
+                Row::Header(津波情報::大津波警報, 3),
+                Row::Name("岩手県"),
+                Row::Name("宮城県"),
+                Row::Name("福島県"),
+                Row::Header(津波情報::津波警報, 2),
+                Row::Remainder(2),
+            ]
+        );
+
+        // 1行しか無ければ、重い方だけを省いて載せる
+        let rows = layout_rows(&groups, HEADER_ROW_PIXELS * 2.0 + NAME_ROW_PIXELS);
+        assert_eq!(
+            rows,
+            [
+                Row::Header(津波情報::大津波警報, 3),
+                Row::Remainder(3),
+                Row::Header(津波情報::津波警報, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fitted_scale() {
+        assert_eq!(fitted_scale(15.0, 100.0, 200.0), Scale::uniform(15.0));
+        assert_eq!(fitted_scale(15.0, 300.0, 200.0), Scale { x: 10.0, y: 15.0 });
+    }
+}
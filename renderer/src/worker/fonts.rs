@@ -1,3 +1,4 @@
+use crate::model::RenderingError;
 use crate::worker::resources::Resources;
 use crate::worker::vertex::{TextUniform, TextVertex};
 use glium::backend::Facade;
@@ -5,17 +6,37 @@ use glium::index::PrimitiveType;
 use glium::texture::{ClientFormat, MipmapsOption, RawImage2d, UncompressedFloatFormat};
 use glium::uniforms::MagnifySamplerFilter;
 use glium::{DrawParameters, IndexBuffer, Surface, Texture2d, VertexBuffer};
-use rusttype::gpu_cache::{Cache, CacheBuilder};
-use rusttype::{point, Point, Scale};
+use rusttype::gpu_cache::{Cache, CacheBuilder, CacheWriteErr, CachedBy};
+use rusttype::{point, Point, PositionedGlyph, Scale};
 use std::borrow::Cow;
 use std::cmp::{max, min};
 use strum::{EnumIter, IntoEnumIterator};
 
 const FONT_CACHE_SIZE: u32 = 512;
+/// グリフキャッシュのテクスチャをこれ以上は拡張しない上限
+const MAX_FONT_CACHE_SIZE: u32 = 4096;
 
-#[derive(Copy, Clone, Debug, EnumIter)]
+/// 指定されたフォントに含まれないグリフを描くためのフォント。
+/// フォントを加えるときは、assets/fontにライセンスと一緒に置く
+const FALLBACK_FONTS: &[Font] = &[Font::NotoSansJPRegular];
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, EnumIter)]
 pub enum Font {
+    BizUDPGothicRegular,
     BizUDPGothicBold,
+    /// 等幅。数字の並ぶ文字列の幅が中身によって変わらない
+    BizUDGothicBold,
+    /// 収録字数が多く、他のフォントに無い文字を補う
+    NotoSansJPRegular,
+}
+
+impl Font {
+    /// グリフを探す順番。先頭のフォントに無い文字は後ろのフォントで描く。
+    fn fallback_chain(self) -> Vec<Font> {
+        std::iter::once(self)
+            .chain(FALLBACK_FONTS.iter().copied().filter(|font| *font != self))
+            .collect()
+    }
 }
 
 trait FontInfo {
@@ -31,55 +52,202 @@ impl FontInfo for Font {
 
     fn font_binary(&self) -> &'static [u8] {
         match self {
+            Font::BizUDPGothicRegular => {
+                include_bytes!("../../../assets/font/biz-udpgothic/BIZUDPGothic-Regular.ttf")
+            }
             Font::BizUDPGothicBold => {
                 include_bytes!("../../../assets/font/biz-udpgothic/BIZUDPGothic-Bold.ttf")
             }
+            Font::BizUDGothicBold => {
+                include_bytes!("../../../assets/font/biz-udgothic/BIZUDGothic-Bold.ttf")
+            }
+            Font::NotoSansJPRegular => {
+                include_bytes!("../../../assets/font/noto-sans-jp/NotoSansJP-Regular.ttf")
+            }
         }
     }
 }
 
+/// 文字の縁取り。
+/// グリフをずらして何度も重ねて描くので、重なりで濃さがむらにならないよう不透明な色で描く
+#[derive(Copy, Clone, Debug)]
+pub struct TextOutline {
+    pub color: [f32; 3],
+    /// ピクセル単位の太さ
+    pub width: f32,
+}
+
+/// 文字の影
+#[derive(Copy, Clone, Debug)]
+pub struct TextShadow {
+    pub color: [f32; 4],
+    /// ピクセル単位のずらし量(右下が正)
+    pub offset: (f32, f32),
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct TextStyle {
+    pub color: [f32; 4],
+    pub outline: Option<TextOutline>,
+    pub shadow: Option<TextShadow>,
+}
+
+impl TextStyle {
+    pub const fn plain(color: [f32; 4]) -> Self {
+        Self {
+            color,
+            outline: None,
+            shadow: None,
+        }
+    }
+
+    /// 描画順(影、縁取り、本体)に、色とグリフをずらす量の組を返す。
+    /// 縁取りはグリフを周囲8方向にずらして重ねることで表現する。
+    fn passes(&self) -> Vec<([f32; 4], Vec<(f32, f32)>)> {
+        let mut passes = vec![];
+
+        if let Some(shadow) = self.shadow {
+            passes.push((shadow.color, vec![shadow.offset]));
+        }
+
+        if let Some(outline) = self.outline {
+            let offsets = (0..8)
+                .map(|i| {
+                    let angle = std::f32::consts::FRAC_PI_4 * i as f32;
+                    (angle.cos() * outline.width, angle.sin() * outline.width)
+                })
+                .collect();
+            let [r, g, b] = outline.color;
+            passes.push(([r, g, b, 1.0], offsets));
+        }
+
+        passes.push((self.color, vec![(0.0, 0.0)]));
+
+        passes
+    }
+}
+
+/// グリフキャッシュの入れ替えの発生状況
+#[derive(Copy, Clone, Debug, Default)]
+struct GlyphCacheStats {
+    /// 既存のグリフを追い出して詰め直した回数
+    evictions: u64,
+    /// テクスチャを拡張した回数
+    grows: u64,
+}
+
 pub struct FontManager<'a> {
     fonts: Vec<rusttype::Font<'a>>,
     font_cache: Cache<'a>,
     font_cache_texture: Texture2d,
+    font_cache_size: u32,
+    stats: GlyphCacheStats,
 }
 
-impl FontManager<'_> {
+impl<'a> FontManager<'a> {
     pub fn new<F: ?Sized + Facade>(facade: &F) -> Self {
-        let fonts: Vec<_> = Font::iter()
-            .map(|f| rusttype::Font::try_from_bytes(f.font_binary()).unwrap())
-            .collect();
+        let fonts = load_fonts();
         let font_cache = CacheBuilder::default()
             .dimensions(FONT_CACHE_SIZE, FONT_CACHE_SIZE)
             .multithread(true)
             .build();
-        let image = RawImage2d {
-            data: Cow::Owned(vec![0u8; (FONT_CACHE_SIZE * FONT_CACHE_SIZE) as usize]),
-            width: FONT_CACHE_SIZE,
-            height: FONT_CACHE_SIZE,
-            format: ClientFormat::U8,
-        };
-        let font_cache_texture = Texture2d::with_format(
-            facade,
-            image,
-            UncompressedFloatFormat::U8,
-            MipmapsOption::NoMipmap,
-        )
-        .unwrap();
+        let font_cache_texture = create_cache_texture(facade, FONT_CACHE_SIZE).unwrap();
 
         Self {
             fonts,
             font_cache,
             font_cache_texture,
+            font_cache_size: FONT_CACHE_SIZE,
+            stats: GlyphCacheStats::default(),
         }
     }
 
+    /// キャッシュに載り切らなかった場合に、テクスチャを一辺2倍に拡張する。
+    /// 上限に達している場合は拡張せず元のエラーを返す。
+    fn grow_cache<F: ?Sized + Facade>(
+        &mut self,
+        facade: &F,
+        cause: CacheWriteErr,
+    ) -> Result<(), RenderingError> {
+        let Some(size) = grown_cache_size(self.font_cache_size) else {
+            return Err(RenderingError::FontCacheOverflow(cause));
+        };
+
+        self.font_cache_texture = create_cache_texture(facade, size)?;
+        self.font_cache
+            .to_builder()
+            .dimensions(size, size)
+            .rebuild(&mut self.font_cache);
+        self.font_cache_size = size;
+        self.stats.grows += 1;
+
+        tracing::info!(
+            "Glyph cache grown to {size}x{size} ({cause}, grows: {}, evictions: {})",
+            self.stats.grows,
+            self.stats.evictions,
+        );
+
+        Ok(())
+    }
+
+    /// 1行分のグリフを並べる。
+    /// 文字ごとにフォールバックの順でグリフを持つフォントを選ぶ。
+    fn layout_line(
+        &self,
+        text: &str,
+        font: Font,
+        scale: Scale,
+        start: Point<f32>,
+    ) -> Vec<(usize, PositionedGlyph<'a>)> {
+        let chain = font.fallback_chain();
+        let mut caret = start;
+        let mut last = None;
+
+        text.chars()
+            .map(|c| {
+                let font_id = find_font(&self.fonts, &chain, c);
+                let font = &self.fonts[font_id];
+
+                let glyph = font.glyph(c).scaled(scale);
+                if let Some((last_font_id, last_glyph_id)) = last {
+                    if last_font_id == font_id {
+                        caret.x += font.pair_kerning(scale, last_glyph_id, glyph.id());
+                    }
+                }
+                let advance_width = glyph.h_metrics().advance_width;
+                let glyph = glyph.positioned(caret);
+
+                caret.x += advance_width;
+                last = Some((font_id, glyph.id()));
+
+                (font_id, glyph)
+            })
+            .collect()
+    }
+
+    /// 1行のテキストを描画した場合の幅と高さをピクセル単位で返す。
+    pub fn measure_text(&self, text: &str, font: Font, scale: Scale) -> (f32, f32) {
+        let v_metrics = self.fonts[font.font_id()].v_metrics(scale);
+        let glyphs = self.layout_line(text, font, scale, point(0.0, v_metrics.ascent));
+
+        let width = glyphs
+            .iter()
+            .filter_map(|(_, glyph)| glyph.pixel_bounding_box())
+            .fold(None, |bounds: Option<(i32, i32)>, rect| match bounds {
+                Some((min_x, max_x)) => Some((min(min_x, rect.min.x), max(max_x, rect.max.x))),
+                None => Some((rect.min.x, rect.max.x)),
+            })
+            .map_or(0, |(min_x, max_x)| max_x - min_x);
+
+        (width as f32, v_metrics.ascent - v_metrics.descent)
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn draw_text<F: ?Sized + Facade, S: ?Sized + Surface>(
         &mut self,
         text: &str,
         font: Font,
-        color: [f32; 4],
+        style: &TextStyle,
         scale: Scale,
         offset: Offset,
         image_dimension: (u32, u32),
@@ -87,24 +255,19 @@ impl FontManager<'_> {
         facade: &F,
         surface: &mut S,
         draw_params: &DrawParameters,
-    ) {
-        let font_id = font.font_id();
-        let font = &self.fonts[font_id];
-        let v_metrics = font.v_metrics(scale);
+    ) -> Result<(), RenderingError> {
+        let v_metrics = self.fonts[font.font_id()].v_metrics(scale);
         let lines = text.lines();
 
-        let text_width = lines
+        let Some(text_width) = lines
             .clone()
-            .map(|l| {
-                font.layout(l, scale, point(0.0, v_metrics.ascent))
-                    .collect::<Vec<_>>()
-            })
+            .map(|l| self.layout_line(l, font, scale, point(0.0, v_metrics.ascent)))
             .map(|v| {
                 let mut min_x = i32::MAX;
                 let mut max_x = i32::MIN;
 
                 v.iter()
-                    .filter_map(|glyph| glyph.pixel_bounding_box())
+                    .filter_map(|(_, glyph)| glyph.pixel_bounding_box())
                     .for_each(|rect| {
                         min_x = min(min_x, rect.min.x);
                         max_x = max(max_x, rect.max.x);
@@ -113,7 +276,9 @@ impl FontManager<'_> {
                 max_x - min_x
             })
             .max()
-            .unwrap();
+        else {
+            return Ok(());
+        };
 
         let line_count = lines.clone().count();
         let glyphs: Vec<_> = lines
@@ -127,16 +292,20 @@ impl FontManager<'_> {
                         + (line_height + v_metrics.line_gap) * (i as f32 - line_count as f32 + 1.0),
                 );
 
-                font.layout(l, scale, glyph_offset).collect::<Vec<_>>()
+                self.layout_line(l, font, scale, glyph_offset)
             })
             .collect();
 
-        for glyph in &glyphs {
-            self.font_cache.queue_glyph(font_id, glyph.clone())
-        }
-        self.font_cache
-            .cache_queued(|rect, data| {
-                self.font_cache_texture.main_level().write(
+        // 載り切らなければテクスチャを拡張して、同じグリフを積み直す
+        loop {
+            self.font_cache.clear_queue();
+            for (font_id, glyph) in &glyphs {
+                self.font_cache.queue_glyph(*font_id, glyph.clone())
+            }
+
+            let font_cache_texture = &self.font_cache_texture;
+            let result = self.font_cache.cache_queued(|rect, data| {
+                font_cache_texture.main_level().write(
                     glium::Rect {
                         left: rect.min.x,
                         bottom: rect.min.y,
@@ -150,23 +319,64 @@ impl FontManager<'_> {
                         format: ClientFormat::U8,
                     },
                 );
-            })
-            .unwrap();
+            });
+
+            match result {
+                Ok(CachedBy::Adding) => break,
+                Ok(CachedBy::Reordering) => {
+                    self.stats.evictions += 1;
+                    tracing::debug!("Glyph cache flushed (evictions: {})", self.stats.evictions);
+                    break;
+                }
+                Err(e) => self.grow_cache(facade, e)?,
+            }
+        }
 
         let glyph_rects: Vec<_> = glyphs
             .iter()
-            .filter_map(|glyph| self.font_cache.rect_for(font_id, glyph).ok().flatten())
+            .filter_map(|(font_id, glyph)| self.font_cache.rect_for(*font_id, glyph).ok().flatten())
             .collect();
-        let vertices: Vec<TextVertex> = glyph_rects
-            .into_iter()
-            .flat_map(|(uv_rect, screen_rect)| {
+
+        for (color, offsets) in style.passes() {
+            self.draw_glyphs(
+                &glyph_rects,
+                &offsets,
+                color,
+                image_dimension,
+                resources,
+                facade,
+                surface,
+                draw_params,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// キャッシュ済みのグリフを、ずらす量ごとに1色で描く。
+    #[allow(clippy::too_many_arguments)]
+    fn draw_glyphs<F: ?Sized + Facade, S: ?Sized + Surface>(
+        &self,
+        glyph_rects: &[(rusttype::Rect<f32>, rusttype::Rect<i32>)],
+        offsets: &[(f32, f32)],
+        color: [f32; 4],
+        image_dimension: (u32, u32),
+        resources: &Resources,
+        facade: &F,
+        surface: &mut S,
+        draw_params: &DrawParameters,
+    ) -> Result<(), RenderingError> {
+        let vertices: Vec<TextVertex> = offsets
+            .iter()
+            .flat_map(|offset| glyph_rects.iter().map(move |rect| (offset, rect)))
+            .flat_map(|((dx, dy), (uv_rect, screen_rect))| {
                 let min = (
-                    screen_rect.min.x as f32 / image_dimension.0 as f32 * 2.0 - 1.0,
-                    screen_rect.min.y as f32 / image_dimension.1 as f32 * -2.0 + 1.0,
+                    (screen_rect.min.x as f32 + dx) / image_dimension.0 as f32 * 2.0 - 1.0,
+                    (screen_rect.min.y as f32 + dy) / image_dimension.1 as f32 * -2.0 + 1.0,
                 );
                 let max = (
-                    screen_rect.max.x as f32 / image_dimension.0 as f32 * 2.0 - 1.0,
-                    screen_rect.max.y as f32 / image_dimension.1 as f32 * -2.0 + 1.0,
+                    (screen_rect.max.x as f32 + dx) / image_dimension.0 as f32 * 2.0 - 1.0,
+                    (screen_rect.max.y as f32 + dy) / image_dimension.1 as f32 * -2.0 + 1.0,
                 );
 
                 vec![
@@ -202,30 +412,66 @@ impl FontManager<'_> {
             .flatten()
             .collect();
 
-        let vertex_buffer = VertexBuffer::dynamic(facade, &vertices).unwrap();
-        let index_buffer =
-            IndexBuffer::dynamic(facade, PrimitiveType::TriangleStrip, &indices).unwrap();
-
-        resources
-            .shader
-            .text
-            .draw(
-                surface,
-                &vertex_buffer,
-                &index_buffer,
-                &TextUniform {
-                    font_texture: &self
-                        .font_cache_texture
-                        .sampled()
-                        .magnify_filter(MagnifySamplerFilter::Nearest),
-                    color,
-                },
-                draw_params,
-            )
-            .unwrap();
+        let vertex_buffer = VertexBuffer::dynamic(facade, &vertices)?;
+        let index_buffer = IndexBuffer::dynamic(facade, PrimitiveType::TriangleStrip, &indices)?;
+
+        resources.shader.text.draw(
+            surface,
+            &vertex_buffer,
+            &index_buffer,
+            &TextUniform {
+                font_texture: &self
+                    .font_cache_texture
+                    .sampled()
+                    .magnify_filter(MagnifySamplerFilter::Nearest),
+                color,
+            },
+            draw_params,
+        )?;
+
+        Ok(())
     }
 }
 
+fn create_cache_texture<F: ?Sized + Facade>(
+    facade: &F,
+    size: u32,
+) -> Result<Texture2d, RenderingError> {
+    let image = RawImage2d {
+        data: Cow::Owned(vec![0u8; (size * size) as usize]),
+        width: size,
+        height: size,
+        format: ClientFormat::U8,
+    };
+
+    Ok(Texture2d::with_format(
+        facade,
+        image,
+        UncompressedFloatFormat::U8,
+        MipmapsOption::NoMipmap,
+    )?)
+}
+
+/// Fontの並び順に、フォントのデータを読み込む。
+fn load_fonts() -> Vec<rusttype::Font<'static>> {
+    Font::iter()
+        .map(|f| rusttype::Font::try_from_bytes(f.font_binary()).unwrap())
+        .collect()
+}
+
+/// 文字cのグリフを持つフォントをchainの順に探す。どれにも無ければ先頭のフォントで描く。
+fn find_font(fonts: &[rusttype::Font], chain: &[Font], c: char) -> usize {
+    chain
+        .iter()
+        .map(|f| f.font_id())
+        .find(|id| fonts[*id].glyph(c).id().0 != 0)
+        .unwrap_or(chain[0].font_id())
+}
+
+fn grown_cache_size(size: u32) -> Option<u32> {
+    Some(size * 2).filter(|size| *size <= MAX_FONT_CACHE_SIZE)
+}
+
 #[allow(dead_code)]
 #[derive(Copy, Clone, Debug)]
 pub enum Origin {
@@ -287,7 +533,10 @@ impl Offset {
 
 #[cfg(test)]
 mod tests {
-    use crate::worker::fonts::{Offset, Origin};
+    use crate::worker::fonts::{
+        find_font, grown_cache_size, load_fonts, Font, FontInfo, Offset, Origin, TextOutline,
+        TextShadow, TextStyle, FONT_CACHE_SIZE,
+    };
     use rusttype::point;
 
     #[test]
@@ -322,4 +571,65 @@ mod tests {
             point(45.0, 45.0)
         );
     }
+
+    #[test]
+    fn test_text_style_passes() {
+        let plain = TextStyle::plain([0.0, 0.0, 0.0, 1.0]);
+        let passes = plain.passes();
+        assert_eq!(passes.len(), 1);
+        assert_eq!(passes[0].1, vec![(0.0, 0.0)]);
+
+        let decorated = TextStyle {
+            color: [1.0, 1.0, 1.0, 1.0],
+            outline: Some(TextOutline {
+                color: [0.0, 0.0, 0.0],
+                width: 2.0,
+            }),
+            shadow: Some(TextShadow {
+                color: [0.0, 0.0, 0.0, 0.5],
+                offset: (3.0, 3.0),
+            }),
+        };
+        let passes = decorated.passes();
+        assert_eq!(passes.len(), 3);
+        assert_eq!(passes[0], ([0.0, 0.0, 0.0, 0.5], vec![(3.0, 3.0)]));
+        assert_eq!(passes[1].0, [0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(passes[1].1.len(), 8);
+        assert!(passes[1]
+            .1
+            .iter()
+            .all(|(x, y)| ((x * x + y * y).sqrt() - 2.0).abs() < 1e-5));
+        assert_eq!(passes[2], ([1.0, 1.0, 1.0, 1.0], vec![(0.0, 0.0)]));
+    }
+
+    #[test]
+    fn test_grown_cache_size() {
+        assert_eq!(grown_cache_size(FONT_CACHE_SIZE), Some(1024));
+        assert_eq!(grown_cache_size(2048), Some(4096));
+        assert_eq!(grown_cache_size(4096), None);
+    }
+
+    #[test]
+    fn test_fallback_glyph() {
+        let fonts = load_fonts();
+        let chain = Font::BizUDPGothicBold.fallback_chain();
+        let has_glyph = |font: Font, c: char| fonts[font.font_id()].glyph(c).id().0 != 0;
+
+        // BIZ UDPゴシックに無い漢字は、フォールバックのフォントで描く
+        let missing = ('\u{4e00}'..='\u{9fff}')
+            .find(|c| {
+                !has_glyph(Font::BizUDPGothicBold, *c) && has_glyph(Font::NotoSansJPRegular, *c)
+            })
+            .expect("フォールバックのフォントにだけある漢字が無い");
+        assert_eq!(
+            find_font(&fonts, &chain, missing),
+            Font::NotoSansJPRegular.font_id()
+        );
+
+        // 両方にある文字は、指定されたフォントで描く
+        assert_eq!(
+            find_font(&fonts, &chain, 'あ'),
+            Font::BizUDPGothicBold.font_id()
+        );
+    }
 }
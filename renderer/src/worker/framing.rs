@@ -0,0 +1,556 @@
+use crate::frame_context::{
+    EarthquakePayload, FramePayload, HasEpicenter, HasTsunamiForecastLevels,
+};
+use crate::model::{津波情報, 震度, RenderingError};
+use crate::worker::collision::ScreenRect;
+use renderer_types::lod::{MAXIMUM_SCALE, SCALE_FACTOR};
+use renderer_types::projection::Projection;
+use renderer_types::{extent, BoundingBox, GeoDegree, Projected, Size, Vertex};
+
+/// 何も含まない範囲。merge_floatで他の範囲と合わせるための初期値
+const EMPTY: BoundingBox<GeoDegree> =
+    BoundingBox::new(Vertex::new(180.0, 90.0), Vertex::new(-180.0, -90.0));
+
+/// 描画範囲の決め方
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FramingConfig {
+    /// 描画範囲の周囲に取る余白の比
+    pub padding: f32,
+    /// 描画範囲の幅と高さの最小値(度)。狭い範囲だけのときに周りの様子が分かるようにする
+    pub minimum_extent: f32,
+    /// 描画範囲の幅と高さの最大値(度)。超える分は中心を保ったまま切り詰める
+    pub maximum_extent: f32,
+    /// この震度未満の地域は、描画範囲を広げすぎる場合には範囲に含めない
+    pub optional_below: 震度,
+    /// 任意の地域は、必須の範囲の幅と高さがそれぞれこの倍率に収まる限りで含める
+    pub optional_growth_limit: f32,
+    /// 津波情報がこの段階未満のものだけなら、発表された予報区の周辺だけを描く。
+    /// Noneなら常に日本全体を描く
+    pub regional_tsunami_below: Option<津波情報>,
+}
+
+impl FramingConfig {
+    pub const DEFAULT: Self = Self {
+        padding: SCALE_FACTOR,
+        minimum_extent: 1.5,
+        maximum_extent: 360.0,
+        optional_below: 震度::震度3,
+        optional_growth_limit: 2.0,
+        regional_tsunami_below: Some(津波情報::津波警報),
+    };
+
+    /// 描画範囲を最小・最大の大きさに収める。
+    pub fn clamp_extent(&self, bounding_box: BoundingBox<GeoDegree>) -> BoundingBox<GeoDegree> {
+        let center = bounding_box.center();
+        let size = bounding_box.size();
+        let half_size = Vertex::<GeoDegree>::new(
+            size.x().clamp(self.minimum_extent, self.maximum_extent),
+            size.y().clamp(self.minimum_extent, self.maximum_extent),
+        ) / 2.0;
+
+        BoundingBox::new(center - half_size, center + half_size)
+    }
+
+    /// 必須の範囲に、広がりすぎない限りで任意の範囲を順に加える。
+    pub fn include_optional(
+        &self,
+        required: BoundingBox<GeoDegree>,
+        optional: impl IntoIterator<Item = BoundingBox<GeoDegree>>,
+    ) -> BoundingBox<GeoDegree> {
+        let limit = self.clamp_extent(required).size();
+        let limit = (
+            limit.x() * self.optional_growth_limit,
+            limit.y() * self.optional_growth_limit,
+        );
+
+        optional.into_iter().fold(required, |bbox, area| {
+            let merged = bbox.merge_float(&area);
+            if merged.size().x() <= limit.0 && merged.size().y() <= limit.1 {
+                merged
+            } else {
+                bbox
+            }
+        })
+    }
+}
+
+/// 描画範囲と、それを画面上のどこにどの大きさで描くか
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Frame {
+    pub bounding_box: BoundingBox<GeoDegree>,
+    pub projection: Projection,
+    pub projection_origin: Vertex<GeoDegree>,
+    pub offset: Vertex<Projected>,
+    pub scale: f32,
+    /// 描画範囲を収めた画面上の枠。予約された領域とは重ならない
+    pub content_rect: ScreenRect,
+}
+
+impl Frame {
+    /// 描画範囲を、画面上の枠areaのうちreservedと重ならない部分に収める。
+    /// 避け方が複数あるときは、最も大きく描けるものを選ぶ。
+    pub fn fit(
+        bounding_box: BoundingBox<GeoDegree>,
+        projection: Projection,
+        area: ScreenRect,
+        reserved: &[ScreenRect],
+        image_size: Size<u32>,
+        config: &FramingConfig,
+    ) -> Self {
+        let projection_origin = projection.origin(bounding_box.center());
+        let rendering_bbox = projection.project_bounding_box(&bounding_box, projection_origin);
+        let scale_in = |rect| calculate_map_scale(rendering_bbox, image_size, rect, config.padding);
+
+        // 避けられる置き方が無ければ、重なるのは諦めて枠全体に収める
+        let (content_rect, scale) = free_rects(area, reserved)
+            .into_iter()
+            .map(|rect| (rect, scale_in(rect)))
+            .reduce(|best, candidate| {
+                let is_better = candidate.1 > best.1
+                    || (candidate.1 == best.1 && rect_area(&candidate.0) > rect_area(&best.0));
+                if is_better {
+                    candidate
+                } else {
+                    best
+                }
+            })
+            .unwrap_or((area, scale_in(area)));
+
+        // 描画範囲の中心が枠の中心に来るようにずらす
+        let image = image_size.to_f32();
+        let center = content_rect.center();
+        let center = Vertex::<Projected>::new(
+            center.0 / image.x() * 2.0 - 1.0,
+            (1.0 - center.1 / image.y() * 2.0) * image_size.aspect_ratio(),
+        );
+        let offset = center / scale - rendering_bbox.center();
+
+        Self {
+            bounding_box,
+            projection,
+            projection_origin,
+            offset,
+            scale,
+            content_rect,
+        }
+    }
+
+    /// 画像のうちrectの部分だけを、rectと同じ大きさの画像に描くための描画範囲。
+    /// 拡大率は画像の幅に対する値なので、rectの幅に合わせて換算される。
+    pub fn localize(&self, rect: ScreenRect, image_size: Size<u32>) -> Self {
+        let image = image_size.to_f32();
+        let (width, height) = rect.size();
+        let scale = self.scale * image.x() / width;
+
+        // 画像の中心からrectの中心までのずれを、地図平面上の長さに直して打ち消す
+        let shift = Vertex::<Projected>::new(
+            (image.x() - 2.0 * rect.left - width) / (self.scale * image.x()),
+            (height - image.y() + 2.0 * rect.top) / (self.scale * image.x()),
+        );
+
+        Self {
+            offset: self.offset + shift,
+            scale,
+            content_rect: ScreenRect {
+                left: self.content_rect.left - rect.left,
+                top: self.content_rect.top - rect.top,
+                right: self.content_rect.right - rect.left,
+                bottom: self.content_rect.bottom - rect.top,
+            },
+            ..*self
+        }
+    }
+}
+
+/// free_rectsが障害物ごとに残す候補の数
+const MAXIMUM_FREE_RECT_CANDIDATES: usize = 16;
+
+/// areaからreservedを避けた矩形の候補を列挙する。
+/// 重なる予約領域ごとに、近い方の上下の端から削るか左右の端から削るかを試し、
+/// 組み合わせが増えすぎないよう面積の大きいものから一定数だけを残して次の領域に進む。
+fn free_rects(area: ScreenRect, reserved: &[ScreenRect]) -> Vec<ScreenRect> {
+    let area_center = area.center();
+
+    reserved.iter().filter(|rect| rect.intersects(&area)).fold(
+        vec![area],
+        |candidates, obstacle| {
+            let center = obstacle.center();
+            let mut candidates = candidates
+                .into_iter()
+                .flat_map(|free| {
+                    if !obstacle.intersects(&free) {
+                        return vec![free];
+                    }
+
+                    let mut vertical = free;
+                    if center.1 < area_center.1 {
+                        vertical.top = vertical.top.max(obstacle.bottom);
+                    } else {
+                        vertical.bottom = vertical.bottom.min(obstacle.top);
+                    }
+                    let mut horizontal = free;
+                    if center.0 < area_center.0 {
+                        horizontal.left = horizontal.left.max(obstacle.right);
+                    } else {
+                        horizontal.right = horizontal.right.min(obstacle.left);
+                    }
+
+                    vec![vertical, horizontal]
+                })
+                .filter(|free| free.left < free.right && free.top < free.bottom)
+                .collect::<Vec<_>>();
+
+            candidates.sort_by(|a, b| rect_area(b).total_cmp(&rect_area(a)));
+            candidates.dedup();
+            candidates.truncate(MAXIMUM_FREE_RECT_CANDIDATES);
+            candidates
+        },
+    )
+}
+
+fn rect_area(rect: &ScreenRect) -> f32 {
+    let (width, height) = rect.size();
+    width * height
+}
+
+/// 描画範囲が画面上の枠に収まる拡大率を求める。
+fn calculate_map_scale(
+    bounding_box: BoundingBox<Projected>,
+    image_size: Size<u32>,
+    rect: ScreenRect,
+    padding: f32,
+) -> f32 {
+    let (width, height) = rect.size();
+    let x_scale = width / image_size.x() as f32 / bounding_box.size().x();
+    let y_scale =
+        height / image_size.y() as f32 / bounding_box.size().y() * image_size.aspect_ratio();
+
+    f32::min(f32::min(x_scale, y_scale) * 2.0, MAXIMUM_SCALE) / padding
+}
+
+/// マップの描画範囲を決定する。
+/// 地震の場合、震度情報または震央のいずれかまたは両方があればその範囲を返す。
+/// どちらも存在しない場合は不正値であり範囲が計算できないのでエラーを返す。
+/// 津波の場合、発表された予報区と震央を含む範囲か、日本全体を返す。
+/// 遠地地震の場合は、日本全体と震央を含む広域にする。
+pub fn calculate_bounding_box(
+    payload: &FramePayload,
+    config: &FramingConfig,
+) -> Result<BoundingBox<GeoDegree>, RenderingError> {
+    let bbox = calculate_domestic_bounding_box(payload, config)?;
+
+    if !payload.is_distant() {
+        return Ok(bbox);
+    }
+
+    let bbox = payload
+        .epicenter()
+        .iter()
+        .fold(bbox.merge_float(&extent::JAPAN), |bbox, epicenter| {
+            bbox.encapsulate_float(epicenter)
+        });
+
+    Ok(config.clamp_extent(bbox))
+}
+
+fn calculate_domestic_bounding_box(
+    payload: &FramePayload,
+    config: &FramingConfig,
+) -> Result<BoundingBox<GeoDegree>, RenderingError> {
+    match payload {
+        FramePayload::Earthquake(payload) => calculate_earthquake_bounding_box(payload, config),
+        FramePayload::Tsunami(payload) => Ok(calculate_tsunami_bounding_box(payload, config)),
+    }
+}
+
+/// 発表された予報区の海岸線と震央を含む範囲にする。
+/// 設定された段階以上の情報が出ている場合は、全国の状況が分かるよう日本全体を返す。
+fn calculate_tsunami_bounding_box<T: HasTsunamiForecastLevels + HasEpicenter>(
+    payload: &T,
+    config: &FramingConfig,
+) -> BoundingBox<GeoDegree> {
+    let forecast_levels = payload.forecast_levels();
+    let highest = forecast_levels
+        .iter()
+        .filter(|(_, areas)| !areas.is_empty())
+        .map(|(level, _)| level)
+        .max();
+
+    let is_regional = matches!(
+        (config.regional_tsunami_below, highest),
+        (Some(below), Some(highest)) if highest < below
+    );
+    if !is_regional {
+        return extent::JAPAN;
+    }
+
+    let bbox = forecast_levels
+        .values()
+        .flatten()
+        .filter_map(|code| {
+            renderer_assets::QueryInterface::query_bounding_box_by_tsunami_area(*code)
+        })
+        .fold(EMPTY, |acc, e| acc.merge_float(&e));
+    let bbox = payload
+        .epicenter()
+        .iter()
+        .fold(bbox, |bbox, epicenter| bbox.encapsulate_float(epicenter));
+
+    if bbox.size().x() < 0.0 {
+        return extent::JAPAN;
+    }
+
+    config.clamp_extent(bbox)
+}
+
+/// 震央と、強い揺れの地域は必ず含める。
+/// 弱い揺れの地域は、描画範囲を広げすぎない場合のみ含める。
+/// ただし最も強い揺れがoptional_below未満であれば、その震度の地域は必ず含める。
+pub fn calculate_earthquake_bounding_box(
+    payload: &EarthquakePayload,
+    config: &FramingConfig,
+) -> Result<BoundingBox<GeoDegree>, RenderingError> {
+    let strongest = payload
+        .area_intensities
+        .iter()
+        .filter(|(_, areas)| !areas.is_empty())
+        .map(|(intensity, _)| intensity)
+        .max();
+    let threshold = strongest.map_or(config.optional_below, |strongest| {
+        strongest.min(config.optional_below)
+    });
+
+    let area_bounding_boxes = |required: bool| {
+        payload
+            .area_intensities
+            .iter()
+            .rev()
+            .filter(move |(intensity, _)| (*intensity >= threshold) == required)
+            .flat_map(|(_, areas)| areas)
+            .filter_map(|code| renderer_assets::QueryInterface::query_bounding_box_by_area(*code))
+    };
+
+    let required = area_bounding_boxes(true).fold(EMPTY, |acc, e| acc.merge_float(&e));
+    let required = payload.epicenter.iter().fold(required, |bbox, epicenter| {
+        bbox.encapsulate_float(epicenter)
+    });
+
+    if required.size().x() < 0.0 {
+        return Err(RenderingError::InvalidBoundingBox);
+    }
+
+    let bbox = config.include_optional(required, area_bounding_boxes(false));
+
+    Ok(config.clamp_extent(bbox))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::frame_context::TsunamiPayload;
+    use crate::model::津波情報;
+    use crate::worker::collision::ScreenRect;
+    use crate::worker::framing::{calculate_tsunami_bounding_box, Frame, FramingConfig};
+    use chrono::Utc;
+    use enum_map::EnumMap;
+    use renderer_types::codes;
+    use renderer_types::projection::Projection;
+    use renderer_types::{extent, BoundingBox, GeoDegree, Size, Vertex};
+
+    fn bbox(min: (f32, f32), max: (f32, f32)) -> BoundingBox<GeoDegree> {
+        BoundingBox::new(Vertex::new(min.0, min.1), Vertex::new(max.0, max.1))
+    }
+
+    /// FrameContext::geo_to_pixelと同じ変換
+    fn to_pixel(frame: &Frame, image_size: Size<u32>, position: Vertex<GeoDegree>) -> (f32, f32) {
+        let screen = frame
+            .projection
+            .project(position, frame.projection_origin)
+            .to_screen(frame.offset, frame.scale);
+        let image = image_size.to_f32();
+
+        (
+            (screen.x() + 1.0) * 0.5 * image.x(),
+            (1.0 - screen.y() / image_size.aspect_ratio()) * 0.5 * image.y(),
+        )
+    }
+
+    #[test]
+    fn test_localize() {
+        let image_size = Size::new(1024, 768);
+        let rect = ScreenRect {
+            left: 10.0,
+            top: 200.0,
+            right: 202.0,
+            bottom: 392.0,
+        };
+        let frame = Frame::fit(
+            bbox((141.0, 26.0), (143.0, 28.0)),
+            Projection::Mercator,
+            rect,
+            &[],
+            image_size,
+            &FramingConfig::DEFAULT,
+        );
+
+        // 枠と同じ大きさの画像には、枠の中と同じ位置関係で描かれる
+        let local = frame.localize(rect, image_size);
+        for position in [Vertex::new(142.2, 27.1), Vertex::new(141.2, 26.3)] {
+            let (x, y) = to_pixel(&frame, image_size, position);
+            let (local_x, local_y) = to_pixel(&local, Size::new(192, 192), position);
+
+            assert!((x - rect.left - local_x).abs() < 1e-2, "{x} {local_x}");
+            assert!((y - rect.top - local_y).abs() < 1e-2, "{y} {local_y}");
+        }
+    }
+
+    #[test]
+    fn test_include_optional() {
+        let config = FramingConfig::DEFAULT;
+        let required = bbox((139.0, 35.0), (141.0, 37.0));
+
+        // 近くの地域は含め、遠くの地域は含めない
+        let near = bbox((141.0, 37.0), (142.0, 38.0));
+        let far = bbox((130.0, 31.0), (131.0, 32.0));
+
+        assert_eq!(
+            config.include_optional(required, [near, far]),
+            bbox((139.0, 35.0), (142.0, 38.0))
+        );
+    }
+
+    #[test]
+    fn test_clamp_extent() {
+        let config = FramingConfig {
+            maximum_extent: 10.0,
+            ..FramingConfig::DEFAULT
+        };
+
+        assert_eq!(
+            config.clamp_extent(bbox((140.0, 36.0), (140.0, 36.0))),
+            bbox((139.25, 35.25), (140.75, 36.75))
+        );
+        assert_eq!(
+            config.clamp_extent(bbox((120.0, 30.0), (150.0, 36.0))),
+            bbox((130.0, 30.0), (140.0, 36.0))
+        );
+    }
+
+    /// オホーツク海沿岸にだけlevelの津波情報が出ているもの
+    fn tsunami(level: 津波情報) -> TsunamiPayload {
+        let mut forecast_levels = EnumMap::default();
+        forecast_levels[level] = vec![codes::津波予報区(120)];
+
+        TsunamiPayload {
+            time: Utc::now(),
+            epicenter: vec![Vertex::new(144.0, 44.5)],
+            forecast_levels,
+            blink_frame: None,
+            region_list: false,
+        }
+    }
+
+    #[test]
+    fn test_regional_tsunami_bounding_box() {
+        let config = FramingConfig {
+            regional_tsunami_below: Some(津波情報::津波警報),
+            ..FramingConfig::DEFAULT
+        };
+
+        // 設定された段階未満なら、予報区と震央の周辺だけを描く
+        let regional = calculate_tsunami_bounding_box(&tsunami(津波情報::津波注意報), &config);
+        assert_ne!(regional, extent::JAPAN);
+        assert!(regional.contains(&Vertex::new(144.0, 44.5)));
+        assert!(regional.size().x() < extent::JAPAN.size().x());
+
+        // 設定された段階そのものからは、日本全体を描く
+        assert_eq!(
+            calculate_tsunami_bounding_box(&tsunami(津波情報::津波警報), &config),
+            extent::JAPAN
+        );
+
+        // 設定が無ければ、どの段階でも日本全体を描く
+        let config = FramingConfig {
+            regional_tsunami_below: None,
+            ..config
+        };
+        assert_eq!(
+            calculate_tsunami_bounding_box(&tsunami(津波情報::津波予報), &config),
+            extent::JAPAN
+        );
+    }
+
+    #[test]
+    fn test_fit_avoids_reserved() {
+        let image_size = Size::new(1024, 768);
+        let area = ScreenRect::full(image_size);
+        let bounding_box = extent::JAPAN;
+        let config = FramingConfig::DEFAULT;
+
+        let frame = Frame::fit(
+            bounding_box,
+            Projection::Mercator,
+            area,
+            &[],
+            image_size,
+            &config,
+        );
+        assert_eq!(frame.content_rect, area);
+
+        // 日本全体は縦に長いので、右上の透かしは右から削れば拡大率を落とさずに避けられる
+        let watermark = ScreenRect {
+            left: 800.0,
+            top: 0.0,
+            right: 1024.0,
+            bottom: 100.0,
+        };
+        let reserved_frame = Frame::fit(
+            bounding_box,
+            Projection::Mercator,
+            area,
+            &[watermark],
+            image_size,
+            &config,
+        );
+        assert_eq!(
+            reserved_frame.content_rect,
+            ScreenRect {
+                right: 800.0,
+                ..area
+            }
+        );
+        assert_eq!(reserved_frame.scale, frame.scale);
+        assert!(reserved_frame.offset.x() < frame.offset.x());
+    }
+
+    #[test]
+    fn test_fit_avoids_many_reserved() {
+        let image_size = Size::new(1024, 768);
+        let area = ScreenRect::full(image_size);
+
+        // 候補を絞らなければ数え切れない数の予約領域でも、全てを避けた枠を選ぶ
+        let reserved = (0..40)
+            .map(|i| {
+                let top = i as f32 * 19.0;
+                ScreenRect {
+                    left: 0.0,
+                    top,
+                    right: 60.0 + (i % 3) as f32 * 20.0,
+                    bottom: top + 18.0,
+                }
+            })
+            .collect::<Vec<_>>();
+        let frame = Frame::fit(
+            extent::JAPAN,
+            Projection::Mercator,
+            area,
+            &reserved,
+            image_size,
+            &FramingConfig::DEFAULT,
+        );
+
+        assert!(reserved
+            .iter()
+            .all(|rect| !rect.intersects(&frame.content_rect)));
+        assert_eq!(frame.content_rect.left, 100.0);
+    }
+}
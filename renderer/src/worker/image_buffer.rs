@@ -13,18 +13,50 @@ impl Texture2dDataSink<(u8, u8, u8, u8)> for RGBAImageData {
     where
         [(u8, u8, u8, u8)]: ToOwned,
     {
-        let data = data.into_owned();
-
-        let ptr = data.as_ptr() as *mut u8;
-        let length = data.len() * 4;
-        let capacity = data.capacity() * 4;
+        RGBAImageData {
+            data: data.iter().flat_map(|&(r, g, b, a)| [r, g, b, a]).collect(),
+            width,
+            height,
+        }
+    }
+}
 
-        std::mem::forget(data);
+impl RGBAImageData {
+    /// OpenGLの読み出し結果(下の行から順に並ぶ)を、上の行から順に並べ替えて取り込む。
+    pub fn from_bottom_up_pixels(pixels: &[(u8, u8, u8, u8)], width: u32, height: u32) -> Self {
+        let row_length = width as usize;
+        let data = pixels
+            .chunks_exact(row_length)
+            .rev()
+            .flatten()
+            .flat_map(|&(r, g, b, a)| [r, g, b, a])
+            .collect();
 
         RGBAImageData {
-            data: unsafe { Vec::from_raw_parts(ptr, length, capacity) },
+            data,
             width,
             height,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::worker::image_buffer::RGBAImageData;
+
+    #[test]
+    fn test_from_bottom_up_pixels() {
+        let pixels = [
+            (1, 2, 3, 4),
+            (5, 6, 7, 8),
+            (9, 10, 11, 12),
+            (13, 14, 15, 16),
+        ];
+        let image = RGBAImageData::from_bottom_up_pixels(&pixels, 2, 2);
+
+        assert_eq!(
+            image.data,
+            [9, 10, 11, 12, 13, 14, 15, 16, 1, 2, 3, 4, 5, 6, 7, 8]
+        );
+    }
+}
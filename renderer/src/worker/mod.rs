@@ -1,66 +1,96 @@
 use crate::frame_context::FramePayload;
-use crate::model::Message;
+use crate::model::{津波情報, Message, RenderingError};
+use crate::worker::animation::TsunamiAnimation;
+use crate::worker::basemap::BasemapCache;
+use crate::worker::collision::ScreenRect;
 use crate::worker::fonts::FontManager;
+use crate::worker::framing::{Frame, FramingConfig};
+use crate::worker::pool::{Dispatch, WorkerInbox, WorkerPool};
+use crate::worker::render_target::{RenderTarget, RenderTargetPool};
 use crate::worker::theme::Theme;
+use crate::worker::vertex::ProjectionUniform;
+use crate::worker::viewport::Layout;
 use glium::backend::Facade;
-use glium::glutin::surface::{GlSurface, SwapInterval};
+use glium::glutin::surface::{GlSurface, Surface as GlutinSurface, SwapInterval};
 use glium::{
     draw_parameters::{Blend, LinearBlendingFactor},
     framebuffer::SimpleFrameBuffer,
     glutin::{
         config::ConfigTemplateBuilder,
-        context::{ContextAttributesBuilder, NotCurrentGlContext},
+        context::{ContextAttributesBuilder, NotCurrentContext, NotCurrentGlContext},
         display::{GetGlDisplay, GlDisplay},
         surface::{SurfaceAttributesBuilder, WindowSurface},
     },
-    BlendingFunction, Display, DrawParameters, Surface, Texture2d,
+    BlendingFunction, Display, DrawParameters, Surface,
 };
 use glutin_winit::DisplayBuilder;
 use image_buffer::RGBAImageData;
+use renderer_types::lod::DIMENSION;
+use renderer_types::projection::{conic_parameters, Projection};
 use renderer_types::*;
 use std::cell::RefCell;
 use std::error::Error;
-use std::num::NonZeroU32;
+use std::num::{NonZeroU32, NonZeroUsize};
 use std::rc::Rc;
-use std::time::Instant;
-use tokio::sync::mpsc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot};
 use winit::application::ApplicationHandler;
 use winit::event::{StartCause, WindowEvent};
 use winit::event_loop::ActiveEventLoop;
-use winit::window::WindowId;
+use winit::window::{Window, WindowId};
 use winit::{raw_window_handle::HasWindowHandle, window::WindowAttributes};
 
+pub mod animation;
+mod basemap;
+mod collision;
 mod drawer_epicenter;
+mod drawer_inset;
 mod drawer_intensity_icon;
+mod drawer_labels;
 mod drawer_map;
 mod drawer_overlay;
 mod drawer_tsunami_legends;
 mod drawer_tsunami_line;
+mod drawer_tsunami_markers;
+mod drawer_tsunami_sidebar;
 mod fonts;
+mod framing;
 pub mod image_buffer;
+pub mod pool;
+mod render_target;
 mod resources;
 mod shader;
 mod theme;
 mod vertex;
+mod viewport;
 
-const DIMENSION: (u32, u32) = (1024, 768);
-const MAXIMUM_SCALE: f32 = 100.0;
-const SCALE_FACTOR: f32 = 1.2;
 const ICON_RATIO_IN_Y_AXIS: f32 = 0.05;
 
-pub async fn run(mut rx: mpsc::Receiver<Message>) -> Result<(), Box<dyn Error>> {
-    let event_loop = winit::event_loop::EventLoop::<Message>::with_user_event().build()?;
+/// ワーカーが描画に使うテーマ
+const THEME: &Theme = &theme::DEFAULT;
 
-    let proxy = event_loop.create_proxy();
+/// ワーカーが描く津波情報の点滅アニメーション。依頼するフレームの数と表示時間はこれに合わせる
+pub fn tsunami_animation() -> &'static TsunamiAnimation {
+    &THEME.tsunami_animation
+}
+
+pub async fn run(
+    mut rx: mpsc::Receiver<Message>,
+    worker_count: NonZeroUsize,
+    dispatch: Dispatch,
+) -> Result<(), Box<dyn Error>> {
+    let event_loop = winit::event_loop::EventLoop::new()?;
 
+    let (mut pool, inboxes) = WorkerPool::new(worker_count.get(), dispatch);
+
+    // 送り手が全て無くなれば、プールごとワーカーへの受け口を閉じて終わる
     tokio::spawn(async move {
-        loop {
-            let message = rx.recv().await.unwrap();
-            proxy.send_event(message).unwrap();
+        while let Some(message) = rx.recv().await {
+            pool.dispatch(message);
         }
     });
 
-    event_loop.run_app(&mut App::default()).unwrap();
+    event_loop.run_app(&mut App::new(inboxes)).unwrap();
 
     Ok(())
 }
@@ -72,67 +102,336 @@ pub struct FrameContext<'a, 'b, F: ?Sized + Facade, S: ?Sized + Surface> {
     pub theme: &'a Theme,
     pub resources: &'a resources::Resources<'a>,
     pub font_manager: Rc<RefCell<&'a mut FontManager<'b>>>,
-    pub draw_parameters: &'a DrawParameters<'a>,
-    pub scale: f32,
-    pub offset: Vertex<Mercator>,
+    pub draw_parameters: DrawParameters<'a>,
+    pub frame: Frame,
+    /// 地図を描く画面上の枠。差し込み図以外では画像全体
+    pub viewport: ScreenRect,
+}
+
+impl<'a, 'b, F: ?Sized + Facade, S: ?Sized + Surface> FrameContext<'a, 'b, F, S> {
+    /// 同じ範囲を、画像全体のうちreservedを避けた部分に収め直した文脈を作る。
+    pub fn reframe(&self, reserved: &[ScreenRect]) -> FrameContext<'a, 'b, F, S> {
+        let frame = Frame::fit(
+            self.frame.bounding_box,
+            self.frame.projection,
+            ScreenRect::full(self.image_size),
+            reserved,
+            self.image_size,
+            &self.theme.framing,
+        );
+
+        FrameContext {
+            frame,
+            ..self.derive()
+        }
+    }
+
+    /// 画面上の枠の中に、別の範囲を差し込み図として描くための文脈を作る。
+    /// 枠の外にはみ出して描かないよう、シザーを設定する。
+    pub fn inset(
+        &self,
+        rect: ScreenRect,
+        bounding_box: BoundingBox<GeoDegree>,
+    ) -> FrameContext<'a, 'b, F, S> {
+        let frame = Frame::fit(
+            bounding_box,
+            self.frame.projection,
+            rect,
+            &[],
+            self.image_size,
+            &self.theme.framing,
+        );
+
+        let (width, height) = rect.size();
+        let scissor = glium::Rect {
+            left: rect.left as u32,
+            bottom: (self.image_size.y() as f32 - rect.bottom) as u32,
+            width: width as u32,
+            height: height as u32,
+        };
+
+        FrameContext {
+            draw_parameters: DrawParameters {
+                scissor: Some(scissor),
+                ..self.draw_parameters.clone()
+            },
+            frame,
+            viewport: rect,
+            ..self.derive()
+        }
+    }
+
+    fn derive(&self) -> FrameContext<'a, 'b, F, S> {
+        FrameContext {
+            facade: self.facade,
+            surface: self.surface.clone(),
+            image_size: self.image_size,
+            theme: self.theme,
+            resources: self.resources,
+            font_manager: self.font_manager.clone(),
+            draw_parameters: self.draw_parameters.clone(),
+            frame: self.frame,
+            viewport: self.viewport,
+        }
+    }
+
+    /// 経緯度を左上原点のピクセル座標に変換する。
+    pub fn geo_to_pixel(&self, position: Vertex<GeoDegree>) -> (f32, f32) {
+        let frame = &self.frame;
+        let screen = frame
+            .projection
+            .project(self.unwrap(position), frame.projection_origin)
+            .to_screen(frame.offset, frame.scale);
+        let image_size = self.image_size.to_f32();
+
+        (
+            (screen.x() + 1.0) * 0.5 * image_size.x(),
+            (1.0 - screen.y() / self.image_size.aspect_ratio()) * 0.5 * image_size.y(),
+        )
+    }
+
+    /// 経度を一周単位でずらし、描画範囲の中心に最も近い位置にする。
+    /// 日付変更線をまたぐ描画範囲でも、震央などが反対側に描かれないようにするため。
+    pub fn unwrap(&self, position: Vertex<GeoDegree>) -> Vertex<GeoDegree> {
+        let frame = &self.frame;
+        let Some(period) = frame.projection.period() else {
+            return position;
+        };
+
+        let x = frame
+            .projection
+            .project(position, frame.projection_origin)
+            .x();
+        let turns = ((-frame.offset.x() - x) / period).round();
+
+        Vertex::new(position.x() + 360.0 * turns, position.y())
+    }
+
+    /// 頂点バッファを描くときのoffset。
+    /// 円筒図法では経度一周ごとに同じ地図が並ぶので、画面にかかる分だけずらして並べる。
+    pub fn wrapped_offsets(&self) -> Vec<[f32; 2]> {
+        let frame = &self.frame;
+        let Some(period) = frame.projection.period() else {
+            return vec![frame.offset.into()];
+        };
+
+        // 画面に映る地図平面のxの範囲と、k枚目の地図のxの範囲(-period/2..period/2をk周ずらしたもの)が重なるものを描く
+        let half_width = 1.0 / frame.scale;
+        let (left, right) = (
+            -frame.offset.x() - half_width,
+            -frame.offset.x() + half_width,
+        );
+        let first = ((left - period / 2.0) / period).ceil() as i32;
+        let last = ((right + period / 2.0) / period).floor() as i32;
+
+        (first..=last)
+            .map(|k| [frame.offset.x() + k as f32 * period, frame.offset.y()])
+            .collect()
+    }
+
+    pub fn projection_uniform(&self) -> ProjectionUniform {
+        ProjectionUniform {
+            projection: self.frame.projection.shader_id(),
+            origin: self.frame.projection_origin.into(),
+            conic_parameters: conic_parameters(),
+        }
+    }
+
+    /// 震度アイコンや震央のアイコンが画面上で占める領域
+    pub fn icon_rect(&self, position: Vertex<GeoDegree>) -> ScreenRect {
+        let size = ICON_RATIO_IN_Y_AXIS * self.image_size.y() as f32;
+
+        ScreenRect::from_center(self.geo_to_pixel(position), (size, size))
+    }
 }
 
-#[derive(Default)]
-struct App<'a> {
-    display: Option<Display<WindowSurface>>,
-    resources: Option<resources::Resources<'a>>,
-    font_manager: Option<FontManager<'a>>,
+struct App {
+    inboxes: Vec<WorkerInbox>,
 }
 
-impl ApplicationHandler<Message> for App<'_> {
+impl App {
+    fn new(inboxes: Vec<WorkerInbox>) -> Self {
+        Self { inboxes }
+    }
+}
+
+impl ApplicationHandler for App {
     fn new_events(&mut self, event_loop: &ActiveEventLoop, cause: StartCause) {
         if cause != StartCause::Init {
             return;
         }
 
-        let display = create_gl_context(event_loop);
+        // ウィンドウの生成はイベントループ上でしか行えないので、
+        // コンテキストまでをここで作り、以降の処理は各ワーカースレッドに任せる。
+        // 終了したワーカーは受信口が閉じるので、振り分け側で取り除かれる
+        for (i, inbox) in self.inboxes.drain(..).enumerate() {
+            let gl_context = create_gl_context(event_loop);
+
+            std::thread::Builder::new()
+                .name(format!("render-worker-{i}"))
+                .spawn(move || Worker::new(i, gl_context).run(inbox))
+                .unwrap();
+        }
+    }
+
+    fn resumed(&mut self, _: &ActiveEventLoop) {}
+
+    fn window_event(&mut self, _: &ActiveEventLoop, _: WindowId, _: WindowEvent) {}
+}
+
+/// スレッド間で受け渡すための、まだカレントになっていないGLコンテキスト
+struct GlContext {
+    window: Window,
+    context: NotCurrentContext,
+    surface: GlutinSurface<WindowSurface>,
+}
+
+struct Worker<'a> {
+    id: usize,
+    _window: Window,
+    display: Display<WindowSurface>,
+    resources: resources::Resources<'a>,
+    font_manager: FontManager<'a>,
+    render_targets: RenderTargetPool,
+    basemaps: BasemapCache,
+}
+
+/// 描画を終え、読み出しの完了を待っているフレーム
+struct PendingFrame {
+    frame: RenderedFrame,
+    response_socket: oneshot::Sender<Result<RGBAImageData, RenderingError>>,
+    request_identity: String,
+}
+
+struct RenderedFrame {
+    target: RenderTarget,
+    /// 予約領域を避けて決めた本図の描画範囲
+    map_frame: Frame,
+    init_time: Duration,
+    alloc_time: Duration,
+    render_time: Duration,
+}
+
+impl Worker<'_> {
+    fn new(id: usize, gl_context: GlContext) -> Self {
+        let GlContext {
+            window,
+            context,
+            surface,
+        } = gl_context;
+
+        let current_context = context.make_current(&surface).unwrap();
+
+        surface
+            .set_swap_interval(&current_context, SwapInterval::DontWait)
+            .unwrap();
+
+        let display = Display::from_context_surface(current_context, surface).unwrap();
 
         let gl_vendor = display.get_opengl_vendor_string();
         let gl_renderer = display.get_opengl_renderer_string();
         let gl_version = display.get_opengl_version_string();
 
-        tracing::info!("GL_VENDOR: {gl_vendor}");
-        tracing::info!("GL_RENDERER: {gl_renderer}");
-        tracing::info!("GL_VERSION: {gl_version}");
+        tracing::info!("[Worker {id}] GL_VENDOR: {gl_vendor}");
+        tracing::info!("[Worker {id}] GL_RENDERER: {gl_renderer}");
+        tracing::info!("[Worker {id}] GL_VERSION: {gl_version}");
 
         let resources = resources::Resources::load(&display);
         let font_manager = FontManager::new(&display);
 
-        self.display = Some(display);
-        self.resources = Some(resources);
-        self.font_manager = Some(font_manager);
+        Self {
+            id,
+            _window: window,
+            display,
+            resources,
+            font_manager,
+            render_targets: RenderTargetPool::default(),
+            basemaps: BasemapCache::default(),
+        }
     }
 
-    fn resumed(&mut self, _: &ActiveEventLoop) {}
+    fn run(mut self, inbox: WorkerInbox) {
+        while let Ok(message) = inbox.receiver.recv() {
+            let mut pending = self.start(message, &inbox);
+
+            // 後続のリクエストがあれば先に描画を発行し、
+            // その間に前のフレームの読み出しを済ませる
+            while let Ok(message) = inbox.receiver.try_recv() {
+                let next = self.start(message, &inbox);
+                if let Some(pending) = pending {
+                    self.finish(pending, &inbox);
+                }
+                pending = next;
+            }
 
-    fn user_event(&mut self, _: &ActiveEventLoop, event: Message) {
-        let Message::FrameRequest((request_frame_context, response_socket)) = event;
+            if let Some(pending) = pending {
+                self.finish(pending, &inbox);
+            }
+        }
+    }
+
+    /// 描画を発行する。失敗した場合はその場でエラーを返送し、Noneを返す。
+    fn start(&mut self, message: Message, inbox: &WorkerInbox) -> Option<PendingFrame> {
+        let Message::FrameRequest((request_frame_context, response_socket)) = message;
+
+        match self.render(
+            &request_frame_context.payload,
+            request_frame_context.projection,
+            request_frame_context.regional_tsunami_below,
+        ) {
+            Ok(frame) => Some(PendingFrame {
+                frame,
+                response_socket,
+                request_identity: request_frame_context.request_identity,
+            }),
+            Err(e) => {
+                tracing::error!(
+                    "[Worker {}] {e} ({})",
+                    self.id,
+                    request_frame_context.request_identity
+                );
 
+                let _ = response_socket.send(Err(e));
+                inbox.complete();
+
+                None
+            }
+        }
+    }
+
+    fn render(
+        &mut self,
+        payload: &FramePayload,
+        projection: Option<Projection>,
+        regional_tsunami_below: Option<Option<津波情報>>,
+    ) -> Result<RenderedFrame, RenderingError> {
         let start_at = std::time::Instant::now();
 
-        let display = self.display.as_ref().unwrap();
-        let resources = self.resources.as_ref().unwrap();
-        let font_manager = self.font_manager.as_mut().unwrap();
-        let font_manager = Rc::new(RefCell::new(font_manager));
+        let display = &self.display;
+        let resources = &self.resources;
+        let font_manager = Rc::new(RefCell::new(&mut self.font_manager));
 
         let image_size = Size::from(DIMENSION);
+        let theme = THEME;
 
-        let bounding_box = calculate_bounding_box(&request_frame_context.payload);
-
-        let rendering_bbox = BoundingBox::from_vertices_float(
-            &bounding_box
-                .gl_vertices()
-                .iter()
-                .map(|v| v.to_mercator())
-                .collect::<Vec<_>>(),
+        let layout = Layout::new(payload);
+        let framing_config = FramingConfig {
+            regional_tsunami_below: regional_tsunami_below
+                .unwrap_or(theme.framing.regional_tsunami_below),
+            ..theme.framing
+        };
+        let bounding_box = framing::calculate_bounding_box(&layout.main, &framing_config)?;
+
+        // 画面上の予約領域は描画の段階で分かるので、ここではひとまず画像全体に収める
+        let viewport = ScreenRect::full(image_size);
+        let frame = Frame::fit(
+            bounding_box,
+            projection.unwrap_or(theme.projection),
+            viewport,
+            &[],
+            image_size,
+            &theme.framing,
         );
-        let offset = -rendering_bbox.center();
-        let scale = calculate_map_scale(rendering_bbox, image_size);
 
         let draw_parameters = DrawParameters {
             multisampling: false,
@@ -149,8 +448,8 @@ impl ApplicationHandler<Message> for App<'_> {
 
         let t_before_alloc = Instant::now();
 
-        let texture = Texture2d::empty(display, image_size.x(), image_size.y()).unwrap();
-        let frame_buffer = SimpleFrameBuffer::new(display, &texture).unwrap();
+        let target = self.render_targets.acquire(display, image_size)?;
+        let frame_buffer = SimpleFrameBuffer::new(display, &target.texture)?;
         let frame_buffer = Rc::new(RefCell::new(frame_buffer));
 
         let t_before_render = Instant::now();
@@ -159,66 +458,75 @@ impl ApplicationHandler<Message> for App<'_> {
             facade: display,
             surface: frame_buffer.clone(),
             image_size,
-            theme: &theme::DEFAULT,
+            theme,
             resources,
             font_manager,
-            draw_parameters: &draw_parameters,
-            scale,
-            offset,
+            draw_parameters,
+            frame,
+            viewport,
         };
 
-        let clear_color = frame_context.theme.clear_color;
-        frame_buffer.borrow_mut().clear_color(
-            clear_color[0],
-            clear_color[1],
-            clear_color[2],
-            clear_color[3],
-        );
+        let result = draw(&frame_context, &mut self.basemaps, &layout);
 
-        match &request_frame_context.payload {
-            FramePayload::Earthquake(earthquake) => {
-                drawer_map::draw(&frame_context, true);
-                drawer_intensity_icon::draw_all(&frame_context, earthquake);
-                drawer_epicenter::draw(&frame_context, earthquake);
-                drawer_overlay::draw(&frame_context, earthquake);
-            }
-            FramePayload::TsunamiFirst(tsunami) => {
-                drawer_map::draw(&frame_context, false);
-                drawer_tsunami_line::draw(&frame_context, tsunami);
-                drawer_tsunami_legends::draw(&frame_context, tsunami);
-                drawer_epicenter::draw(&frame_context, tsunami);
-                drawer_overlay::draw(&frame_context, tsunami);
-            }
-            FramePayload::TsunamiSecond(tsunami) => {
-                drawer_map::draw(&frame_context, false);
-                drawer_tsunami_legends::draw(&frame_context, tsunami);
-                drawer_epicenter::draw(&frame_context, tsunami);
-                drawer_overlay::draw(&frame_context, tsunami);
+        drop(frame_context);
+        drop(frame_buffer);
+
+        let map_frame = match result.and_then(|frame| target.start_readback().map(|_| frame)) {
+            Ok(frame) => frame,
+            Err(e) => {
+                self.render_targets.release(target);
+                return Err(e);
             }
-        }
+        };
+
+        let t_done = Instant::now();
+
+        Ok(RenderedFrame {
+            target,
+            map_frame,
+            init_time: t_before_alloc - start_at,
+            alloc_time: t_before_render - t_before_alloc,
+            render_time: t_done - t_before_render,
+        })
+    }
+
+    fn finish(&mut self, pending: PendingFrame, inbox: &WorkerInbox) {
+        let PendingFrame {
+            mut frame,
+            response_socket,
+            request_identity,
+        } = pending;
 
         let t_before_bufcpy = Instant::now();
 
-        let image: RGBAImageData = texture.read();
+        let image = frame.target.finish_readback();
 
         let t_done = Instant::now();
 
         tracing::info!(
-            "Init: {:?} Alloc: {:?} Render: {:?} BufCpy: {:?} ({})",
-            t_before_alloc - start_at,
-            t_before_render - t_before_alloc,
-            t_before_bufcpy - t_before_render,
+            "[Worker {}] Init: {:?} Alloc: {:?} Render: {:?} BufCpy: {:?} ({})",
+            self.id,
+            frame.init_time,
+            frame.alloc_time,
+            frame.render_time,
             t_done - t_before_bufcpy,
-            request_frame_context.request_identity,
+            request_identity,
         );
+        tracing::debug!(
+            "[Worker {}] Frame: {:?} ({})",
+            self.id,
+            frame.map_frame,
+            request_identity,
+        );
+
+        self.render_targets.release(frame.target);
 
         let _ = response_socket.send(Ok(image));
+        inbox.complete();
     }
-
-    fn window_event(&mut self, _: &ActiveEventLoop, _: WindowId, _: WindowEvent) {}
 }
 
-fn create_gl_context(event_loop: &ActiveEventLoop) -> Display<WindowSurface> {
+fn create_gl_context(event_loop: &ActiveEventLoop) -> GlContext {
     let display_builder =
         DisplayBuilder::new().with_window_attributes(Some(WindowAttributes::default()));
 
@@ -246,68 +554,128 @@ fn create_gl_context(event_loop: &ActiveEventLoop) -> Display<WindowSurface> {
     let attributes =
         ContextAttributesBuilder::new().build(Some(window.window_handle().unwrap().as_raw()));
 
-    let current_context = unsafe {
+    let context = unsafe {
         gl_config
             .display()
             .create_context(&gl_config, &attributes)
             .unwrap()
-    }
-    .make_current(&surface)
-    .unwrap();
-
-    surface
-        .set_swap_interval(&current_context, SwapInterval::DontWait)
-        .unwrap();
+    };
 
-    Display::from_context_surface(current_context, surface).unwrap()
+    GlContext {
+        window,
+        context,
+        surface,
+    }
 }
 
-/// マップの描画範囲を決定する。
-/// 地震の場合、震度情報または震央のいずれかまたは両方があればSomeを返す。
-/// どちらも存在しない場合は不正値であり範囲が計算できないのでNoneを返す。
-/// 津波の場合、発報範囲に関わらず固定値を返す。
-pub fn calculate_bounding_box(payload: &FramePayload) -> BoundingBox<GeoDegree> {
-    match payload {
-        FramePayload::Earthquake(payload) => {
-            let areas = payload
-                .area_intensities
-                .values()
-                .flatten()
-                .copied()
-                .collect::<Vec<_>>();
-            let bbox = areas
-                .iter()
-                .filter_map(|code| {
-                    renderer_assets::QueryInterface::query_bounding_box_by_area(*code)
-                })
-                .fold(
-                    BoundingBox {
-                        min: Vertex::new(180.0, 90.0),
-                        max: Vertex::new(-180.0, -90.0),
-                    },
-                    |acc, e| acc.merge_float(&e),
-                );
+/// 描画し、予約領域を避けて決めた本図の描画範囲を返す。
+fn draw<F: ?Sized + Facade, S: Surface>(
+    frame_context: &FrameContext<F, S>,
+    basemaps: &mut BasemapCache,
+    layout: &Layout,
+) -> Result<Frame, RenderingError> {
+    let payload = layout.main.as_ref();
+    let has_world = payload.is_distant();
 
-            let bbox = payload
-                .epicenter
-                .iter()
-                .fold(bbox, |bbox, epicenter| bbox.encapsulate_float(epicenter));
+    match payload {
+        FramePayload::Earthquake(earthquake) => {
+            let overlay = drawer_overlay::occupied_rects(frame_context, earthquake);
+
+            // 差し込み図は本図のアイコンを避けて置き、本図はその枠も避けて収め直す
+            let inset_rects = {
+                let frame_context = frame_context.reframe(&overlay);
+                let icons = [
+                    drawer_intensity_icon::occupied_rects(&frame_context, earthquake),
+                    drawer_epicenter::occupied_rects(&frame_context, earthquake),
+                ]
+                .concat();
+                viewport::inset_rects(
+                    layout.insets.len(),
+                    frame_context.image_size,
+                    &overlay,
+                    &icons,
+                )
+            };
+            let reserved = [overlay, inset_rects.clone()].concat();
+            let frame_context = &frame_context.reframe(&reserved);
+
+            let mut insets = Vec::with_capacity(layout.insets.len());
+            for (inset, rect) in layout.insets.iter().zip(inset_rects) {
+                let bounding_box = inset.bounding_box(&frame_context.theme.framing)?;
+                insets.push((inset, frame_context.inset(rect, bounding_box)));
+            }
 
-            if bbox.size().x() < 0.0 {
-                panic!("Failed to determinate bounding_box");
+            // 差し込み図と重なっても本図のアイコンが隠れないよう、差し込み図を先に描く
+            basemaps.draw(frame_context, true, has_world)?;
+            for (inset, inset_context) in &insets {
+                draw_inset(inset_context, basemaps, inset)?;
             }
 
-            bbox
+            let obstacles = [
+                drawer_intensity_icon::occupied_rects(frame_context, earthquake),
+                drawer_epicenter::occupied_rects(frame_context, earthquake),
+                reserved,
+            ]
+            .concat();
+            drawer_labels::draw(frame_context, earthquake, &obstacles)?;
+            drawer_intensity_icon::draw_all(frame_context, earthquake)?;
+            drawer_epicenter::draw(frame_context, earthquake)?;
+            drawer_overlay::draw(frame_context, earthquake)?;
+
+            Ok(frame_context.frame)
         }
-        FramePayload::TsunamiFirst(_) | FramePayload::TsunamiSecond(_) => {
-            BoundingBox::new(Vertex::new(122.9, 24.0), Vertex::new(148.9, 45.5))
+        FramePayload::Tsunami(tsunami) => {
+            let overlay = drawer_overlay::occupied_rects(frame_context, tsunami);
+            let sidebar = tsunami
+                .region_list
+                .then(|| drawer_tsunami_sidebar::layout(frame_context, tsunami, &overlay));
+            let legends = match &sidebar {
+                Some(sidebar) => sidebar.occupied_rects(),
+                None => drawer_tsunami_legends::occupied_rects(frame_context, tsunami),
+            };
+            let reserved = [legends, overlay].concat();
+            let frame_context = &frame_context.reframe(&reserved);
+
+            basemaps.draw(frame_context, false, has_world)?;
+            drawer_tsunami_line::draw(frame_context, tsunami, tsunami.blink_frame)?;
+            drawer_tsunami_markers::draw(frame_context, tsunami, tsunami.blink_frame)?;
+            let obstacles = [
+                drawer_epicenter::occupied_rects(frame_context, tsunami),
+                drawer_tsunami_markers::occupied_rects(frame_context, tsunami),
+                reserved,
+            ]
+            .concat();
+            drawer_labels::draw(frame_context, tsunami, &obstacles)?;
+            match &sidebar {
+                Some(sidebar) => drawer_tsunami_sidebar::draw(frame_context, sidebar)?,
+                None => drawer_tsunami_legends::draw(frame_context, tsunami)?,
+            }
+            drawer_epicenter::draw(frame_context, tsunami)?;
+            drawer_overlay::draw(frame_context, tsunami)?;
+
+            Ok(frame_context.frame)
         }
     }
 }
 
-fn calculate_map_scale(bounding_box: BoundingBox<Mercator>, image_size: Size<u32>) -> f32 {
-    let x_scale = 1.0 / bounding_box.size().x();
-    let y_scale = 1.0 / bounding_box.size().y() * image_size.aspect_ratio();
+fn draw_inset<F: ?Sized + Facade, S: Surface>(
+    frame_context: &FrameContext<F, S>,
+    basemaps: &mut BasemapCache,
+    inset: &viewport::Inset,
+) -> Result<(), RenderingError> {
+    let earthquake = &inset.payload;
+
+    basemaps.draw(frame_context, true, false)?;
+    let obstacles = [
+        drawer_intensity_icon::occupied_rects(frame_context, earthquake),
+        drawer_epicenter::occupied_rects(frame_context, earthquake),
+        vec![drawer_inset::name_rect(frame_context, inset.islands.name)],
+    ]
+    .concat();
+    drawer_labels::draw(frame_context, earthquake, &obstacles)?;
+    drawer_intensity_icon::draw_all(frame_context, earthquake)?;
+    drawer_epicenter::draw(frame_context, earthquake)?;
+    drawer_inset::draw(frame_context, inset.islands.name)?;
 
-    f32::min(f32::min(x_scale, y_scale) * 2.0, MAXIMUM_SCALE) / SCALE_FACTOR
+    Ok(())
 }
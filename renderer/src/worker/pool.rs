@@ -0,0 +1,154 @@
+use crate::model::{Message, RenderingError};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+
+/// レンダーワーカーへのリクエストの振り分け方
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum Dispatch {
+    /// 順番に振り分ける
+    RoundRobin,
+    /// 処理中のリクエストが最も少ないワーカーに振り分ける
+    LeastLoaded,
+}
+
+/// ワーカー側が持つ受信口
+pub struct WorkerInbox {
+    pub receiver: mpsc::Receiver<Message>,
+    pub load: Arc<AtomicUsize>,
+}
+
+impl WorkerInbox {
+    /// リクエストを1件処理し終えたことを通知する。
+    pub fn complete(&self) {
+        self.load.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+struct WorkerHandle {
+    sender: mpsc::Sender<Message>,
+    load: Arc<AtomicUsize>,
+}
+
+pub struct WorkerPool {
+    workers: Vec<WorkerHandle>,
+    dispatch: Dispatch,
+    next: usize,
+}
+
+impl WorkerPool {
+    pub fn new(worker_count: usize, dispatch: Dispatch) -> (Self, Vec<WorkerInbox>) {
+        let (workers, inboxes) = (0..worker_count)
+            .map(|_| {
+                let (sender, receiver) = mpsc::channel();
+                let load = Arc::new(AtomicUsize::new(0));
+
+                (
+                    WorkerHandle {
+                        sender,
+                        load: load.clone(),
+                    },
+                    WorkerInbox { receiver, load },
+                )
+            })
+            .unzip();
+
+        let pool = Self {
+            workers,
+            dispatch,
+            next: 0,
+        };
+
+        (pool, inboxes)
+    }
+
+    /// リクエストをいずれかのワーカーに渡す。
+    /// 終了したワーカーは取り除いて別のワーカーに渡し直し、残っていなければWorkerUnavailableを返す。
+    pub fn dispatch(&mut self, mut message: Message) {
+        while !self.workers.is_empty() {
+            let index = self.select();
+            let worker = &self.workers[index];
+
+            worker.load.fetch_add(1, Ordering::AcqRel);
+            match worker.sender.send(message) {
+                Ok(()) => return,
+                Err(mpsc::SendError(returned)) => {
+                    tracing::error!("Render worker is gone, removing it from the pool");
+                    self.workers.remove(index);
+                    self.next = 0;
+                    message = returned;
+                }
+            }
+        }
+
+        tracing::error!("No render worker is available");
+        match message {
+            Message::FrameRequest((_, tx)) => {
+                let _ = tx.send(Err(RenderingError::WorkerUnavailable));
+            }
+        }
+    }
+
+    fn select(&mut self) -> usize {
+        match self.dispatch {
+            Dispatch::RoundRobin => {
+                let index = self.next % self.workers.len();
+                self.next = (index + 1) % self.workers.len();
+                index
+            }
+            Dispatch::LeastLoaded => self
+                .workers
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, worker)| worker.load.load(Ordering::Acquire))
+                .map(|(i, _)| i)
+                .unwrap(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::frame_context::{EarthquakePayload, FrameContext, FramePayload};
+    use crate::model::{Message, RenderingError};
+    use crate::worker::image_buffer::RGBAImageData;
+    use crate::worker::pool::{Dispatch, WorkerPool};
+    use chrono::Utc;
+    use tokio::sync::oneshot;
+
+    fn message() -> (
+        Message,
+        oneshot::Receiver<Result<RGBAImageData, RenderingError>>,
+    ) {
+        let (tx, rx) = oneshot::channel();
+        let context = FrameContext {
+            payload: FramePayload::Earthquake(EarthquakePayload {
+                time: Utc::now(),
+                epicenter: vec![],
+                area_intensities: Default::default(),
+            }),
+            projection: None,
+            regional_tsunami_below: None,
+            request_identity: "test".to_string(),
+        };
+
+        (Message::FrameRequest((context, tx)), rx)
+    }
+
+    #[test]
+    fn test_dispatch_skips_dead_workers() {
+        let (mut pool, mut inboxes) = WorkerPool::new(2, Dispatch::RoundRobin);
+        drop(inboxes.remove(0));
+
+        let (request, _rx) = message();
+        pool.dispatch(request);
+        assert!(inboxes[0].receiver.try_recv().is_ok());
+
+        drop(inboxes);
+        let (request, mut rx) = message();
+        pool.dispatch(request);
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(Err(RenderingError::WorkerUnavailable))
+        ));
+    }
+}
@@ -0,0 +1,83 @@
+use crate::model::{GlResourceError, RenderingError};
+use crate::worker::image_buffer::RGBAImageData;
+use glium::backend::Facade;
+use glium::texture::pixel_buffer::PixelBuffer;
+use glium::texture::TextureAny;
+use glium::{Rect, Texture2d};
+use renderer_types::Size;
+use std::collections::HashMap;
+
+/// 描画先のテクスチャと、その読み出し先のピクセルバッファの組
+pub struct RenderTarget {
+    pub texture: Texture2d,
+    pixel_buffer: PixelBuffer<(u8, u8, u8, u8)>,
+    size: Size<u32>,
+}
+
+impl RenderTarget {
+    fn new<F: ?Sized + Facade>(facade: &F, size: Size<u32>) -> Result<Self, RenderingError> {
+        let texture = Texture2d::empty(facade, size.x(), size.y())?;
+        let pixel_buffer = PixelBuffer::new_empty(facade, (size.x() * size.y()) as usize);
+
+        Ok(Self {
+            texture,
+            pixel_buffer,
+            size,
+        })
+    }
+
+    /// テクスチャからピクセルバッファへの転送を発行する。
+    /// 転送は非同期に行われ、この関数は完了を待たない。
+    pub fn start_readback(&self) -> Result<(), RenderingError> {
+        let rect = Rect {
+            left: 0,
+            bottom: 0,
+            width: self.size.x(),
+            height: self.size.y(),
+        };
+
+        TextureAny::main_level(&self.texture)
+            .first_layer()
+            .into_image(None)
+            .ok_or(GlResourceError::Readback)?
+            .raw_read_to_pixel_buffer(&rect, &self.pixel_buffer);
+
+        Ok(())
+    }
+
+    /// 転送の完了を待ち、上の行から順に並んだ画像として取り出す。
+    pub fn finish_readback(&mut self) -> RGBAImageData {
+        let size = self.size;
+        let pixels = self.pixel_buffer.map_read();
+
+        RGBAImageData::from_bottom_up_pixels(&pixels, size.x(), size.y())
+    }
+}
+
+/// 画像サイズごとに描画先を使い回すためのプール
+#[derive(Default)]
+pub struct RenderTargetPool {
+    targets: HashMap<(u32, u32), Vec<RenderTarget>>,
+}
+
+impl RenderTargetPool {
+    pub fn acquire<F: ?Sized + Facade>(
+        &mut self,
+        facade: &F,
+        size: Size<u32>,
+    ) -> Result<RenderTarget, RenderingError> {
+        let key: (u32, u32) = size.into();
+
+        match self.targets.get_mut(&key).and_then(|targets| targets.pop()) {
+            Some(target) => Ok(target),
+            None => RenderTarget::new(facade, size),
+        }
+    }
+
+    pub fn release(&mut self, target: RenderTarget) {
+        self.targets
+            .entry(target.size.into())
+            .or_default()
+            .push(target);
+    }
+}
@@ -14,7 +14,6 @@ use glium::{IndexBuffer, Texture2d, VertexBuffer};
 pub struct Resources<'a> {
     pub shader: Shader<'a>,
     pub buffer: Buffer,
-    pub lake: Lake,
     pub texture: Texture,
 }
 
@@ -22,13 +21,11 @@ impl Resources<'_> {
     pub fn load<F: ?Sized + Facade>(facade: &F) -> Self {
         let shader = Shader::load(facade);
         let buffer = Buffer::load(facade);
-        let lake = Lake::load(facade);
         let texture = Texture::load(facade);
 
         Self {
             shader,
             buffer,
-            lake,
             texture,
         }
     }
@@ -39,7 +36,9 @@ pub struct Buffer {
     pub map_vertex: VertexBuffer<MapVertex>,
     area_line: Vec<IndexBuffer<u32>>,
     pref_line: Vec<IndexBuffer<u32>>,
-    pub map: IndexBuffer<u32>,
+    map: Vec<IndexBuffer<u32>>,
+    pub world_vertex: VertexBuffer<MapVertex>,
+    world: Vec<IndexBuffer<u32>>,
     pub tsunami_vertex: VertexBuffer<TsunamiVertex>,
     pub tsunami_indices: IndexBuffer<u32>,
 }
@@ -48,6 +47,7 @@ impl Buffer {
     fn load<F: ?Sized + Facade>(facade: &F) -> Self {
         let geom = renderer_assets::QueryInterface::geometries();
         let tsunami_geom = renderer_assets::QueryInterface::tsunami_geometries();
+        let world_geom = renderer_assets::QueryInterface::world_geometries();
 
         let vertices: Vec<_> = geom
             .vertices
@@ -59,8 +59,11 @@ impl Buffer {
 
         let vertex = VertexBuffer::new(facade, &vertices).unwrap();
 
-        let map =
-            IndexBuffer::new(facade, PrimitiveType::TrianglesList, geom.map_triangles).unwrap();
+        let map = geom
+            .map_triangles
+            .iter()
+            .map(|i| IndexBuffer::new(facade, PrimitiveType::TrianglesList, i).unwrap())
+            .collect();
 
         let area_line: Vec<_> = geom
             .area_lines
@@ -74,6 +77,21 @@ impl Buffer {
             .map(|i| IndexBuffer::new(facade, PrimitiveType::LineStrip, i).unwrap())
             .collect();
 
+        let world_vertex = world_geom
+            .vertices
+            .iter()
+            .map(|v| MapVertex {
+                position: [v.0, v.1],
+            })
+            .collect::<Vec<_>>();
+        let world_vertex = VertexBuffer::immutable(facade, &world_vertex).unwrap();
+
+        let world = world_geom
+            .triangles
+            .iter()
+            .map(|i| IndexBuffer::immutable(facade, PrimitiveType::TrianglesList, i).unwrap())
+            .collect();
+
         let tsunami_vertex = tsunami_geom
             .vertices
             .iter()
@@ -91,11 +109,24 @@ impl Buffer {
             map,
             area_line,
             pref_line,
+            world_vertex,
+            world,
             tsunami_vertex,
             tsunami_indices,
         }
     }
 
+    pub fn get_map_by_scale(&self, scale: f32) -> Option<&IndexBuffer<u32>> {
+        let i = renderer_assets::QueryInterface::query_lod_level_by_scale(scale)?;
+        self.map.get(i)
+    }
+
+    /// 世界の陸地のアセットが無い場合は空のバッファを返す
+    pub fn get_world_by_scale(&self, scale: f32) -> Option<&IndexBuffer<u32>> {
+        let i = renderer_assets::QueryInterface::query_lod_level_by_scale(scale)?;
+        self.world.get(i)
+    }
+
     pub fn get_area_line_by_scale(&self, scale: f32) -> Option<&IndexBuffer<u32>> {
         let i = renderer_assets::QueryInterface::query_lod_level_by_scale(scale)?;
         self.area_line.get(i)
@@ -107,32 +138,6 @@ impl Buffer {
     }
 }
 
-#[derive(Debug)]
-pub struct Lake {
-    pub vertex: VertexBuffer<MapVertex>,
-    pub index: IndexBuffer<u32>,
-}
-
-impl Lake {
-    fn load<F: ?Sized + Facade>(facade: &F) -> Self {
-        let geom = renderer_assets::QueryInterface::lake_geometries();
-
-        let vertex: Vec<_> = geom
-            .vertices
-            .iter()
-            .map(|v| MapVertex {
-                position: [v.0, v.1],
-            })
-            .collect();
-        let vertex = VertexBuffer::immutable(facade, &vertex).unwrap();
-
-        let index =
-            IndexBuffer::immutable(facade, PrimitiveType::TrianglesList, geom.indices).unwrap();
-
-        Lake { vertex, index }
-    }
-}
-
 #[derive(Debug)]
 pub struct Shader<'a> {
     pub border_line: ShaderProgram<BorderLineUniform, MapVertex>,
@@ -149,7 +154,7 @@ impl Shader<'_> {
     fn load<F: ?Sized + Facade>(facade: &F) -> Self {
         let border_line = ShaderProgram::from_source(
             facade,
-            include_str!("../../../assets/shader/border_line.vsh"),
+            &with_projection(include_str!("../../../assets/shader/border_line.vsh")),
             include_str!("../../../assets/shader/border_line.fsh"),
             Some(include_str!("../../../assets/shader/border_line.gsh")),
         )
@@ -157,7 +162,7 @@ impl Shader<'_> {
 
         let epicenter = ShaderProgram::from_source(
             facade,
-            include_str!("../../../assets/shader/epicenter.vsh"),
+            &with_projection(include_str!("../../../assets/shader/epicenter.vsh")),
             include_str!("../../../assets/shader/epicenter.fsh"),
             Some(include_str!("../../../assets/shader/epicenter.gsh")),
         )
@@ -165,7 +170,7 @@ impl Shader<'_> {
 
         let intensity_icon = ShaderProgram::from_source(
             facade,
-            include_str!("../../../assets/shader/intensity_icon.vsh"),
+            &with_projection(include_str!("../../../assets/shader/intensity_icon.vsh")),
             include_str!("../../../assets/shader/intensity_icon.fsh"),
             Some(include_str!("../../../assets/shader/intensity_icon.gsh")),
         )
@@ -173,7 +178,7 @@ impl Shader<'_> {
 
         let map = ShaderProgram::from_source(
             facade,
-            include_str!("../../../assets/shader/map.vsh"),
+            &with_projection(include_str!("../../../assets/shader/map.vsh")),
             include_str!("../../../assets/shader/map.fsh"),
             None,
         )
@@ -189,7 +194,7 @@ impl Shader<'_> {
 
         let tsunami = ShaderProgram::from_source(
             facade,
-            include_str!("../../../assets/shader/tsunami.vsh"),
+            &with_projection(include_str!("../../../assets/shader/tsunami.vsh")),
             include_str!("../../../assets/shader/tsunami.fsh"),
             Some(include_str!("../../../assets/shader/tsunami.gsh")),
         )
@@ -224,6 +229,18 @@ impl Shader<'_> {
     }
 }
 
+/// 頂点シェーダーの#versionの直後に、共通の投影関数(projection.glsl)を差し込む
+fn with_projection(vertex_shader: &str) -> String {
+    let (version, body) = vertex_shader
+        .split_once('\n')
+        .unwrap_or((vertex_shader, ""));
+
+    format!(
+        "{version}\n{}\n{body}",
+        include_str!("../../../assets/shader/projection.glsl")
+    )
+}
+
 #[derive(Debug)]
 pub struct Texture {
     pub intensity: Texture2d,
@@ -1,7 +1,15 @@
 #![allow(clippy::eq_op)]
+use crate::worker::animation::TsunamiAnimation;
+use crate::worker::fonts::{TextOutline, TextShadow, TextStyle};
+use crate::worker::framing::FramingConfig;
 use crate::worker::vertex::TsunamiLineColors;
+use renderer_types::projection::Projection;
 
 pub struct Theme {
+    pub name: &'static str,
+    /// リクエストで指定されなかったときの投影法
+    pub projection: Projection,
+    pub framing: FramingConfig,
     pub clear_color: [f32; 4],
     pub ground_color: [f32; 3],
     pub prefectural_border_color: [f32; 3],
@@ -10,11 +18,28 @@ pub struct Theme {
     pub area_border_width: f32,
     pub tsunami_colors: TsunamiLineColors,
     pub tsunami_width: f32,
-    pub tsunami_legend_color: [f32; 4],
-    pub occurrence_time_color: [f32; 4],
+    pub tsunami_animation: TsunamiAnimation,
+    /// 線として見えないほど小さい津波予報区に描く印の半径
+    pub tsunami_marker_radius: f32,
+    pub tsunami_marker_halo_color: [f32; 3],
+    pub tsunami_marker_halo_width: f32,
+    pub tsunami_legend_text: TextStyle,
+    /// 津波予報区の一覧の背景
+    pub tsunami_sidebar_background: [f32; 4],
+    pub occurrence_time_text: TextStyle,
+    pub prefecture_label_text: TextStyle,
+    pub area_label_text: TextStyle,
+    /// 震央付近の地名
+    pub hypocenter_label_text: TextStyle,
+    pub inset_border_color: [f32; 3],
+    pub inset_border_width: f32,
+    pub inset_label_text: TextStyle,
 }
 
 pub const DEFAULT: Theme = Theme {
+    name: "default",
+    projection: Projection::Mercator,
+    framing: FramingConfig::DEFAULT,
     #[allow(clippy::eq_op)]
     clear_color: [130.0 / 255.0, 188.0 / 255.0, 255.0 / 255.0, 1.0],
     ground_color: [222.0 / 255.0, 226.0 / 255.0, 229.0 / 255.0],
@@ -29,12 +54,61 @@ pub const DEFAULT: Theme = Theme {
         major_warning: [200.0 / 255.0, 0.0 / 255.0, 255.0 / 255.0],
     },
     tsunami_width: 3.0,
-    tsunami_legend_color: [0.0, 0.0, 0.0, 0.8],
-    occurrence_time_color: [0.0, 0.0, 0.0, 0.63],
+    tsunami_animation: TsunamiAnimation::DEFAULT,
+    tsunami_marker_radius: 5.0,
+    tsunami_marker_halo_color: [1.0, 1.0, 1.0],
+    tsunami_marker_halo_width: 1.5,
+    tsunami_legend_text: TextStyle::plain([0.0, 0.0, 0.0, 0.8]),
+    tsunami_sidebar_background: [1.0, 1.0, 1.0, 0.75],
+    occurrence_time_text: TextStyle {
+        color: [0.0, 0.0, 0.0, 0.63],
+        outline: Some(TextOutline {
+            color: [1.0, 1.0, 1.0],
+            width: 1.5,
+        }),
+        shadow: None,
+    },
+    prefecture_label_text: TextStyle {
+        color: [0.2, 0.2, 0.2, 0.9],
+        outline: Some(TextOutline {
+            color: [1.0, 1.0, 1.0],
+            width: 1.5,
+        }),
+        shadow: None,
+    },
+    area_label_text: TextStyle {
+        color: [0.35, 0.35, 0.35, 0.9],
+        outline: Some(TextOutline {
+            color: [1.0, 1.0, 1.0],
+            width: 1.0,
+        }),
+        shadow: None,
+    },
+    hypocenter_label_text: TextStyle {
+        color: [0.1, 0.1, 0.1, 1.0],
+        outline: Some(TextOutline {
+            color: [1.0, 1.0, 1.0],
+            width: 2.0,
+        }),
+        shadow: None,
+    },
+    inset_border_color: [80.0 / 255.0, 84.0 / 255.0, 87.0 / 255.0],
+    inset_border_width: 2.0,
+    inset_label_text: TextStyle {
+        color: [0.2, 0.2, 0.2, 0.9],
+        outline: Some(TextOutline {
+            color: [1.0, 1.0, 1.0],
+            width: 1.5,
+        }),
+        shadow: None,
+    },
 };
 
 #[allow(dead_code)]
 pub const DARK_DEMO: Theme = Theme {
+    name: "dark_demo",
+    projection: Projection::Mercator,
+    framing: FramingConfig::DEFAULT,
     clear_color: [0.1, 0.12, 0.15, 1.0],
     ground_color: [0.35, 0.35, 0.35],
     prefectural_border_color: [0.75, 0.75, 0.75],
@@ -48,6 +122,62 @@ pub const DARK_DEMO: Theme = Theme {
         major_warning: [200.0 / 255.0, 0.0 / 255.0, 255.0 / 255.0],
     },
     tsunami_width: 8.0,
-    tsunami_legend_color: [0.0, 0.0, 0.0, 0.8],
-    occurrence_time_color: [1.0, 1.0, 1.0, 0.63],
+    tsunami_animation: TsunamiAnimation::DEFAULT,
+    tsunami_marker_radius: 8.0,
+    tsunami_marker_halo_color: [0.0, 0.0, 0.0],
+    tsunami_marker_halo_width: 2.0,
+    tsunami_legend_text: TextStyle {
+        color: [1.0, 1.0, 1.0, 0.9],
+        outline: Some(TextOutline {
+            color: [0.0, 0.0, 0.0],
+            width: 2.0,
+        }),
+        shadow: None,
+    },
+    tsunami_sidebar_background: [0.0, 0.0, 0.0, 0.6],
+    occurrence_time_text: TextStyle {
+        color: [1.0, 1.0, 1.0, 0.8],
+        outline: Some(TextOutline {
+            color: [0.0, 0.0, 0.0],
+            width: 2.0,
+        }),
+        shadow: Some(TextShadow {
+            color: [0.0, 0.0, 0.0, 0.5],
+            offset: (2.0, 2.0),
+        }),
+    },
+    prefecture_label_text: TextStyle {
+        color: [0.9, 0.9, 0.9, 0.9],
+        outline: Some(TextOutline {
+            color: [0.0, 0.0, 0.0],
+            width: 1.5,
+        }),
+        shadow: None,
+    },
+    area_label_text: TextStyle {
+        color: [0.75, 0.75, 0.75, 0.9],
+        outline: Some(TextOutline {
+            color: [0.0, 0.0, 0.0],
+            width: 1.0,
+        }),
+        shadow: None,
+    },
+    hypocenter_label_text: TextStyle {
+        color: [1.0, 1.0, 1.0, 1.0],
+        outline: Some(TextOutline {
+            color: [0.0, 0.0, 0.0],
+            width: 2.0,
+        }),
+        shadow: None,
+    },
+    inset_border_color: [0.75, 0.75, 0.75],
+    inset_border_width: 3.0,
+    inset_label_text: TextStyle {
+        color: [0.9, 0.9, 0.9, 0.9],
+        outline: Some(TextOutline {
+            color: [0.0, 0.0, 0.0],
+            width: 1.5,
+        }),
+        shadow: None,
+    },
 };
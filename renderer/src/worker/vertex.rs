@@ -1,3 +1,4 @@
+use crate::model::津波情報;
 use glium::texture::UnsignedTexture1d;
 use glium::uniforms::{
     AsUniformValue, ImageUnitAccess, ImageUnitBehavior, ImageUnitFormat, Sampler, UniformValue,
@@ -5,11 +6,28 @@ use glium::uniforms::{
 };
 use glium::{implement_uniform_block, implement_vertex, Texture2d};
 
+/// 頂点シェーダーに差し込まれるprojection.glslに渡す値
+#[derive(Clone, Copy, Debug)]
+pub struct ProjectionUniform {
+    pub projection: i32,
+    pub origin: [f32; 2],
+    pub conic_parameters: [f32; 3],
+}
+
+impl ProjectionUniform {
+    fn visit_values<'a, F: FnMut(&str, UniformValue<'a>)>(&'a self, visitor: &mut F) {
+        visitor("projection", self.projection.as_uniform_value());
+        visitor("projection_origin", self.origin.as_uniform_value());
+        visitor("conic_parameters", self.conic_parameters.as_uniform_value());
+    }
+}
+
 #[derive(Debug)]
 pub struct BorderLineUniform {
     pub dimension: [f32; 2],
     pub offset: [f32; 2],
     pub zoom: f32,
+    pub projection: ProjectionUniform,
     pub line_width: f32,
     pub color: [f32; 3],
 }
@@ -19,6 +37,7 @@ impl Uniforms for BorderLineUniform {
         visitor("dimension", self.dimension.as_uniform_value());
         visitor("offset", self.offset.as_uniform_value());
         visitor("zoom", self.zoom.as_uniform_value());
+        self.projection.visit_values(&mut visitor);
         visitor("line_width", self.line_width.as_uniform_value());
         visitor("color", self.color.as_uniform_value());
     }
@@ -35,6 +54,7 @@ pub struct EpicenterUniform<'a> {
     pub aspect_ratio: f32,
     pub offset: [f32; 2],
     pub zoom: f32,
+    pub projection: ProjectionUniform,
     pub icon_ratio_in_y_axis: f32,
     pub texture_sampler: &'a Texture2d,
 }
@@ -44,6 +64,7 @@ impl Uniforms for EpicenterUniform<'_> {
         visitor("aspect_ratio", self.aspect_ratio.as_uniform_value());
         visitor("offset", self.offset.as_uniform_value());
         visitor("zoom", self.zoom.as_uniform_value());
+        self.projection.visit_values(&mut visitor);
         visitor(
             "icon_ratio_in_y_axis",
             self.icon_ratio_in_y_axis.as_uniform_value(),
@@ -64,6 +85,7 @@ pub struct IntensityIconUniform<'a> {
     pub aspect_ratio: f32,
     pub offset: [f32; 2],
     pub zoom: f32,
+    pub projection: ProjectionUniform,
     pub icon_ratio_in_y_axis: f32,
     pub texture_sampler: &'a Texture2d,
 }
@@ -73,6 +95,7 @@ impl Uniforms for IntensityIconUniform<'_> {
         visitor("aspect_ratio", self.aspect_ratio.as_uniform_value());
         visitor("offset", self.offset.as_uniform_value());
         visitor("zoom", self.zoom.as_uniform_value());
+        self.projection.visit_values(&mut visitor);
         visitor(
             "icon_ratio_in_y_axis",
             self.icon_ratio_in_y_axis.as_uniform_value(),
@@ -92,6 +115,7 @@ pub struct MapUniform {
     pub aspect_ratio: f32,
     pub offset: [f32; 2],
     pub zoom: f32,
+    pub projection: ProjectionUniform,
     pub color: [f32; 3],
 }
 
@@ -100,6 +124,7 @@ impl Uniforms for MapUniform {
         visitor("aspect_ratio", self.aspect_ratio.as_uniform_value());
         visitor("offset", self.offset.as_uniform_value());
         visitor("zoom", self.zoom.as_uniform_value());
+        self.projection.visit_values(&mut visitor);
         visitor("color", self.color.as_uniform_value());
     }
 }
@@ -133,6 +158,7 @@ pub struct TsunamiUniform {
     pub dimension: [f32; 2],
     pub offset: [f32; 2],
     pub zoom: f32,
+    pub projection: ProjectionUniform,
     pub colors: TsunamiLineColors,
     pub levels: UnsignedTexture1d,
     pub line_width: f32,
@@ -143,6 +169,7 @@ impl Uniforms for TsunamiUniform {
         visitor("dimension", self.dimension.as_uniform_value());
         visitor("offset", self.offset.as_uniform_value());
         visitor("zoom", self.zoom.as_uniform_value());
+        self.projection.visit_values(&mut visitor);
         // gliumが構造体の転送に対応していないので、バラバラに渡すしかない
         visitor("forecast_color", self.colors.forecast.as_uniform_value());
         visitor("advisory_color", self.colors.advisory.as_uniform_value());
@@ -178,6 +205,17 @@ implement_uniform_block!(
     major_warning
 );
 
+impl TsunamiLineColors {
+    pub fn of(&self, level: 津波情報) -> [f32; 3] {
+        match level {
+            津波情報::津波予報 => self.forecast,
+            津波情報::津波注意報 => self.advisory,
+            津波情報::津波警報 => self.warning,
+            津波情報::大津波警報 => self.major_warning,
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct TextVertex {
     pub position: [f32; 2],
@@ -0,0 +1,189 @@
+use crate::frame_context::{EarthquakePayload, FramePayload};
+use crate::model::RenderingError;
+use crate::worker::collision::ScreenRect;
+use crate::worker::framing::{self, FramingConfig};
+use renderer_types::extent::{outlying_islands_of, OutlyingIslands, OUTLYING_ISLANDS};
+use renderer_types::{BoundingBox, GeoDegree, Size};
+use std::borrow::Cow;
+
+/// 差し込み図の一辺の、画像の高さに対する比
+const INSET_RATIO_IN_Y_AXIS: f32 = 0.25;
+/// 差し込み図と画像の端や、差し込み図同士の間隔
+const INSET_MARGIN_PIXELS: f32 = 10.0;
+
+/// 本図と差し込み図への描き分け
+pub struct Layout<'a> {
+    /// 画面全体に描く内容
+    pub main: Cow<'a, FramePayload>,
+    pub insets: Vec<Inset>,
+}
+
+/// 離島の分だけを描く差し込み図
+pub struct Inset {
+    pub islands: &'static OutlyingIslands,
+    pub payload: EarthquakePayload,
+}
+
+impl<'a> Layout<'a> {
+    /// 本土と離島の両方にかかる地震情報では、離島の分を島ごとの差し込み図に分ける。
+    /// 遠地地震は広域をそのまま描くので分けない。
+    /// 津波情報も分けない。重い情報では元から沖縄・大東・小笠原を含む日本全体を描き、
+    /// 軽い情報で本土と離島にまたがっても、描画範囲は日本全体より広がらないので、離島の予報区が潰れることはない。
+    pub fn new(payload: &'a FramePayload) -> Self {
+        let single = Self {
+            main: Cow::Borrowed(payload),
+            insets: Vec::new(),
+        };
+
+        let FramePayload::Earthquake(earthquake) = payload else {
+            return single;
+        };
+
+        if payload.is_distant() {
+            return single;
+        }
+
+        let mainland = earthquake.filter(|position| outlying_islands_of(position).is_none());
+        if mainland.is_empty() {
+            return single;
+        }
+
+        let insets = OUTLYING_ISLANDS
+            .iter()
+            .map(|islands| Inset {
+                islands,
+                payload: earthquake.filter(|position| islands.extent.contains(position)),
+            })
+            .filter(|inset| !inset.payload.is_empty())
+            .collect::<Vec<_>>();
+
+        if insets.is_empty() {
+            return single;
+        }
+
+        Self {
+            main: Cow::Owned(FramePayload::Earthquake(mainland)),
+            insets,
+        }
+    }
+}
+
+impl Inset {
+    pub fn bounding_box(
+        &self,
+        config: &FramingConfig,
+    ) -> Result<BoundingBox<GeoDegree>, RenderingError> {
+        framing::calculate_earthquake_bounding_box(&self.payload, config)
+    }
+}
+
+/// 差し込み図を置く枠を決める。
+/// 画像の左端か右端に上から並べ、fixedの権利表記などとiconsの本図のアイコンのどちらとも重ならないようにする。
+/// 両端とも収まるなら、より上に詰めて並べられる方を選ぶ。
+/// どちらにも収まらなければfixedだけを避け、アイコンとの重なりが最も少ない方にする。
+/// アイコンは差し込み図の後に描くので、重なっても隠れはしない。
+pub fn inset_rects(
+    count: usize,
+    image_size: Size<u32>,
+    fixed: &[ScreenRect],
+    icons: &[ScreenRect],
+) -> Vec<ScreenRect> {
+    let size = INSET_RATIO_IN_Y_AXIS * image_size.y() as f32;
+    let columns = [
+        INSET_MARGIN_PIXELS,
+        image_size.x() as f32 - INSET_MARGIN_PIXELS - size,
+    ];
+    let fits = |rects: &Vec<ScreenRect>| rects.iter().all(|rect| rect.is_within(image_size));
+    let bottom = |rects: &Vec<ScreenRect>| rects.last().map_or(0.0, |rect| rect.bottom);
+
+    let obstacles = [fixed, icons].concat();
+    let avoiding_icons = columns
+        .iter()
+        .map(|left| stack_rects(*left, size, count, &obstacles))
+        .filter(fits)
+        .min_by(|a, b| bottom(a).total_cmp(&bottom(b)));
+
+    avoiding_icons
+        .or_else(|| {
+            columns
+                .iter()
+                .map(|left| stack_rects(*left, size, count, fixed))
+                .filter(fits)
+                .min_by_key(|rects| {
+                    rects
+                        .iter()
+                        .map(|rect| icons.iter().filter(|icon| icon.intersects(rect)).count())
+                        .sum::<usize>()
+                })
+        })
+        .unwrap_or_else(|| stack_rects(columns[0], size, count, &[]))
+}
+
+fn stack_rects(left: f32, size: f32, count: usize, fixed: &[ScreenRect]) -> Vec<ScreenRect> {
+    let mut rects = Vec::with_capacity(count);
+    let mut top = INSET_MARGIN_PIXELS;
+
+    while rects.len() < count {
+        let rect = ScreenRect {
+            left,
+            top,
+            right: left + size,
+            bottom: top + size,
+        };
+
+        match fixed
+            .iter()
+            .filter(|obstacle| obstacle.intersects(&rect))
+            .map(|obstacle| obstacle.bottom)
+            .reduce(f32::max)
+        {
+            Some(bottom) => top = bottom + INSET_MARGIN_PIXELS,
+            None => {
+                rects.push(rect);
+                top = rect.bottom + INSET_MARGIN_PIXELS;
+            }
+        }
+    }
+
+    rects
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::worker::collision::ScreenRect;
+    use crate::worker::viewport::inset_rects;
+    use renderer_types::Size;
+
+    #[test]
+    fn test_inset_rects() {
+        let image_size = Size::new(1024, 768);
+        let watermark = ScreenRect {
+            left: 800.0,
+            top: 10.0,
+            right: 1014.0,
+            bottom: 100.0,
+        };
+
+        // 何も無ければ左上から並べる
+        let rects = inset_rects(2, image_size, &[watermark], &[]);
+        assert_eq!(rects[0].left, 10.0);
+        assert_eq!(rects[0].top, 10.0);
+        assert_eq!(rects[1].top, rects[0].bottom + 10.0);
+
+        // 左側にアイコンがあれば、透かしの下を空けて右側に並べる
+        let icon = ScreenRect::from_center((100.0, 100.0), (38.0, 38.0));
+        let rects = inset_rects(2, image_size, &[watermark], &[icon]);
+        assert_eq!(rects[0].right, 1014.0);
+        assert_eq!(rects[0].top, 110.0);
+        assert!(rects.iter().all(|rect| !rect.intersects(&watermark)));
+
+        // 両側にアイコンがあれば、重ならないよう下にずらす
+        let icons = [icon, ScreenRect::from_center((924.0, 300.0), (38.0, 38.0))];
+        let rects = inset_rects(2, image_size, &[watermark], &icons);
+        assert!(rects
+            .iter()
+            .all(|rect| icons.iter().all(|icon| !icon.intersects(rect))));
+        assert_eq!(rects[0].left, 10.0);
+        assert_eq!(rects[0].top, 129.0);
+    }
+}